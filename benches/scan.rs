@@ -0,0 +1,128 @@
+/*
+ *  scan.rs - Benchmarks of walking, line parsing, and keyword matching
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+mod synthetic;
+
+use std::{hint::black_box, num::NonZero, path::Path};
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use pursue::{Options, OutputFormat, matching::Matching};
+use synthetic::{PYTHON, RUST, Rng, SHELL, Tree};
+
+/// Options of a plain scan, machine readable so status lines stay off of
+/// stdout, and without the classification cache so every run does the
+/// same work
+fn options() -> Options {
+    Options {
+        format: OutputFormat::Json,
+        cache: false,
+        ..Options::default()
+    }
+}
+
+/// Whole scans of a generated tree: walking, reading, and parsing, on one
+/// thread and on every CPU
+fn walk(c: &mut Criterion) {
+    let tree: Tree = Tree::generate("walk", 2000, 8).expect("generate the tree");
+    let mut group = c.benchmark_group("walk");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(tree.bytes));
+
+    for jobs in [Some(1), None] {
+        let options: Options = Options {
+            jobs: jobs.and_then(NonZero::new),
+            ..options()
+        };
+        let name: String = jobs.map_or("all cpus".to_string(), |jobs| format!("{} jobs", jobs));
+        group.bench_function(BenchmarkId::new("profile", name), |b| {
+            b.iter(|| pursue::profile(tree.path(), &options).expect("scan"))
+        });
+    }
+    group.finish();
+}
+
+/// Counting and searching the lines of one big file per language, without
+/// touching the disk
+fn parse(c: &mut Criterion) {
+    let options: Options = options();
+    let mut group = c.benchmark_group("parse");
+
+    for syntax in [&RUST, &PYTHON, &SHELL] {
+        let source: String = synthetic::source(syntax, 20_000, 42);
+        let path: String = format!("generated.{}", syntax.extension);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(syntax.extension),
+            &source,
+            |b, source| {
+                b.iter(|| pursue::scan_source(Path::new(&path), black_box(source), &options))
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Looking for one keyword in comment text the way each matching option
+/// does
+fn matching(c: &mut Criterion) {
+    let mut rng: Rng = Rng::new(7);
+    let lines: Vec<String> = (0..10_000)
+        .map(|_| synthetic::comment(&mut rng, 8))
+        .collect();
+    let bytes: u64 = lines.iter().map(|line| line.len() as u64).sum();
+
+    let mut group = c.benchmark_group("matching");
+    group.throughput(Throughput::Bytes(bytes));
+    for (name, matching) in [
+        ("exact", Matching::default()),
+        (
+            "ignore case",
+            Matching {
+                ignore_case: true,
+                whole_word: false,
+            },
+        ),
+        (
+            "whole word",
+            Matching {
+                ignore_case: false,
+                whole_word: true,
+            },
+        ),
+        (
+            "ignore case, whole word",
+            Matching {
+                ignore_case: true,
+                whole_word: true,
+            },
+        ),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                lines
+                    .iter()
+                    .filter(|line| matching.is_match(black_box(line), "TODO"))
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, walk, parse, matching);
+criterion_main!(benches);
@@ -0,0 +1,170 @@
+/*
+ *  synthetic/mod.rs - Generated sources and trees for the benchmarks
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::path::{Path, PathBuf};
+
+/// Comment syntax of a generated language, along with its extension
+pub struct Syntax {
+    pub extension: &'static str,
+    inline: &'static str,
+    block: Option<(&'static str, &'static str)>,
+}
+
+pub const RUST: Syntax = Syntax {
+    extension: "rs",
+    inline: "//",
+    block: Some(("/*", "*/")),
+};
+pub const PYTHON: Syntax = Syntax {
+    extension: "py",
+    inline: "#",
+    block: Some(("\"\"\"", "\"\"\"")),
+};
+pub const SHELL: Syntax = Syntax {
+    extension: "sh",
+    inline: "#",
+    block: None,
+};
+
+const KEYWORDS: [&str; 4] = ["TODO", "HACK", "BUG", "FIXME"];
+const WORDS: [&str; 12] = [
+    "parse", "buffer", "retry", "handle", "config", "mastodon", "index", "todos", "socket",
+    "flush", "offset", "cache",
+];
+
+/// Deterministic xorshift, so every run benchmarks the same input
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    fn words(&mut self, count: usize) -> String {
+        (0..count)
+            .map(|_| WORDS[self.below(WORDS.len())])
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+}
+
+/// One comment line of prose, with a keyword in one of every `keyword_rate`
+pub fn comment(rng: &mut Rng, keyword_rate: usize) -> String {
+    match rng.below(keyword_rate) {
+        0 => format!("{}: {}", KEYWORDS[rng.below(KEYWORDS.len())], rng.words(6)),
+        _ => rng.words(8),
+    }
+}
+
+/// `lines` lines of plausible source in `syntax`: mostly code, some of it
+/// with strings holding comment markers, with inline and block comments
+/// and blank lines mixed in
+pub fn source(syntax: &Syntax, lines: usize, seed: u64) -> String {
+    let mut rng: Rng = Rng::new(seed);
+    let mut source: String = String::new();
+    let mut line: usize = 0;
+    while line < lines {
+        match rng.below(20) {
+            0..=1 => source.push('\n'),
+            2..=4 => {
+                source += &format!("    {} {}\n", syntax.inline, comment(&mut rng, 8));
+            }
+            5 => match syntax.block {
+                Some((opener, closer)) => {
+                    source += &format!("{} {}\n", opener, comment(&mut rng, 8));
+                    source += &format!("   {}\n", comment(&mut rng, 8));
+                    source += &format!("{}\n", closer);
+                    line += 2;
+                }
+                None => source += &format!("{} {}\n", syntax.inline, comment(&mut rng, 8)),
+            },
+            6 => {
+                source += &format!(
+                    "    let url = \"http://{}.example.com\"; {} {}\n",
+                    rng.words(1),
+                    syntax.inline,
+                    comment(&mut rng, 4)
+                );
+            }
+            _ => source += &format!("    call({}, {});\n", rng.words(2), rng.below(1000)),
+        }
+        line += 1;
+    }
+    source
+}
+
+/// A generated tree on disk, removed when dropped
+pub struct Tree {
+    pub root: PathBuf,
+    pub bytes: u64,
+}
+
+impl Tree {
+    /// `files` sources of a few hundred lines each, spread over nested
+    /// directories `fan_out` wide, in the system's temporary directory
+    pub fn generate(name: &str, files: usize, fan_out: usize) -> std::io::Result<Self> {
+        let root: PathBuf =
+            std::env::temp_dir().join(format!("pursue-bench-{}-{}", name, std::process::id()));
+        if root.exists() {
+            std::fs::remove_dir_all(&root)?;
+        }
+
+        let syntaxes: [&Syntax; 3] = [&RUST, &PYTHON, &SHELL];
+        let mut bytes: u64 = 0;
+        for index in 0..files {
+            let mut directory: PathBuf = root.clone();
+            let mut rest: usize = index / fan_out;
+            while rest > 0 {
+                directory.push(format!("d{}", rest % fan_out));
+                rest /= fan_out;
+            }
+            std::fs::create_dir_all(&directory)?;
+
+            let syntax: &Syntax = syntaxes[index % syntaxes.len()];
+            let contents: String = source(syntax, 200 + index % 300, index as u64);
+            bytes += contents.len() as u64;
+            std::fs::write(
+                directory.join(format!("file{}.{}", index, syntax.extension)),
+                contents,
+            )?;
+        }
+
+        Ok(Self { root, bytes })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl Drop for Tree {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
@@ -0,0 +1,13 @@
+#include <stdio.h>
+
+// TODO: plain line comment
+int main(void) { // FIXME trailing
+    /* HACK block on one line */
+    /*
+     * BUG inside a block
+     */
+    const char *url = "http://example.com"; // TODO after a string
+    char quote = '"'; /* FIXME after a char */
+    /** TODO documented */
+    return 0;
+}
@@ -0,0 +1,7 @@
+code(); /* TODO x */ more();
+/* a */ code(); /* FIXME b */
+/* HACK one */ /* BUG two */
+int TODO_count = 0; /* plain */
+/**/ x(); /* */ // TODO tail
+/* a */ /* b
+   FIXME still open */ y(); /* HACK */ z();
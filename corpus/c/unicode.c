@@ -0,0 +1,4 @@
+/* © 2024 — TODO: année */
+int größe = 0; // FIXME: identifiers aren't ASCII
+char *s = "/* ★ */"; /* HACK ★ */
+/*★*/ int a; //★ BUG
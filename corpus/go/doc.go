@@ -0,0 +1,12 @@
+package main
+
+// Frobnicate does a thing
+// TODO: make it faster
+func Frobnicate() {
+	// FIXME inside
+	x := 1
+}
+
+// BUG not documentation
+
+var y = 2
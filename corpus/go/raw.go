@@ -0,0 +1,5 @@
+package main
+
+// TODO: package level
+var pattern = `// not a comment`
+func main() {} /* FIXME trailing block */
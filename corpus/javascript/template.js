@@ -0,0 +1,5 @@
+const url = `http://${host}/path`; // TODO template
+/**
+ * HACK documented
+ */
+const re = "/* not a comment */";
@@ -0,0 +1,4 @@
+/* outer /* inner */ still outer TODO */
+fun main() {} // FIXME
+/* open /* nested
+   */ HACK */ val x = 1
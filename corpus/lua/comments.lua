@@ -0,0 +1,7 @@
+-- TODO: line comment
+local s = "-- not a comment"
+--[[
+  FIXME: in a block
+]]
+--- HACK: documented
+local t = [[ -- raw string ]] -- BUG
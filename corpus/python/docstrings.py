@@ -0,0 +1,9 @@
+#!/usr/bin/env python3
+"""Module docstring. TODO: describe"""
+
+def f(url="http://x#y"):
+    '''
+    HACK: multi line docstring
+    '''
+    s = "# FIXME not a comment"
+    return s  # BUG real
@@ -0,0 +1,6 @@
+# -*- coding: utf-8 -*-
+def grüße():
+    """Grüße 🌍 TODO: mehr Sprachen"""
+    s = "#日本"  # FIXME: 日本語
+    t = 'ö'#HACK ünïcödé
+    return s + t  # ✓ BUG
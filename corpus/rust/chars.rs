@@ -0,0 +1,12 @@
+fn quote() -> char {
+    let c = '"'; // TODO: quote
+    let escaped = '\''; // FIXME: escaped quote
+    let newline = b'\n'; /* HACK */
+    '\u{1F600}' // BUG: emoji
+}
+
+fn longest<'a>(x: &'a str, y: &'a str) -> &'a str { // TODO: lifetimes
+    'outer: loop { break 'outer; } // HACK: labels
+    let s = "'"; // FIXME
+    x
+}
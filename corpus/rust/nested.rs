@@ -0,0 +1,10 @@
+/* outer /* inner TODO */
+   still in the outer comment FIXME
+*/
+fn main() {} /* one /* two */ */ // HACK
+/* /* */ */ let x = 1;
+/*
+ * /* BUG
+ */
+*/
+let y = "/*"; // TODO
@@ -0,0 +1,7 @@
+//! Crate docs, TODO: write
+/// FIXME: documented function
+fn main() {
+    let raw = r#"/* not a comment */"#;
+    let url = "https://example.com"; // HACK
+    /* BUG */ let x = 1;
+}
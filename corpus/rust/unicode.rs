@@ -0,0 +1,11 @@
+// 日本語のコメント TODO: 翻訳する
+/// Größe in µm, FIXME für Ümlaute
+fn grüße() -> &'static str {
+    let s = "😀/*"; // HACK 😀 after an emoji string
+    /* BUG ☃ */ let ß = "//é";
+    let naïve = 1; /* TODO café */
+    "é" // TODO ☕
+}
+/* 🚀 multi
+   line ✓ FIXME
+🚀 */ let y = "ü";
@@ -0,0 +1,6 @@
+#!/bin/sh
+# TODO: shell comment
+echo "# not a comment" # FIXME
+echo 'it''s # still a string'
+echo "${#args[@]}" $# TODO
+shift $# # HACK
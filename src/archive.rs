@@ -0,0 +1,238 @@
+/*
+ *  archive.rs - Reads the files inside zip and tar archives
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::{Component, Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+
+/// Kinds of archive `--archives` looks inside of
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Zip,
+    Tar,
+    /// Gzipped tarballs, `.crate` files among them
+    TarGz,
+}
+
+impl Format {
+    /// Kind of archive `path` is, going by its name
+    pub fn of(path: &Path) -> Option<Format> {
+        let name: String = path.file_name()?.to_str()?.to_ascii_lowercase();
+        [
+            (".zip", Format::Zip),
+            (".tar", Format::Tar),
+            (".tar.gz", Format::TarGz),
+            (".tgz", Format::TarGz),
+            (".crate", Format::TarGz),
+        ]
+        .into_iter()
+        .find(|(extension, _)| name.ends_with(extension))
+        .map(|(_, format)| format)
+    }
+}
+
+/// Hand every regular file in `archive` to `visit`, along with its size
+/// and where it is, as `archive` joined with its path inside of it. The
+/// contents are only decompressed as `visit` reads them, so it can skip
+/// what it has no use for. Archives inside the archive aren't opened
+pub fn members(
+    archive: &Path,
+    format: Format,
+    mut visit: impl FnMut(PathBuf, u64, &mut dyn Read) -> Result<(), std::io::Error>,
+) -> Result<(), std::io::Error> {
+    let file: File = File::open(archive)?;
+    match format {
+        Format::Zip => {
+            let mut zip =
+                zip::ZipArchive::new(BufReader::new(file)).map_err(std::io::Error::other)?;
+            for index in 0..zip.len() {
+                let mut member = zip.by_index(index).map_err(std::io::Error::other)?;
+                if !member.is_file() {
+                    continue;
+                }
+                let Some(name) = member.enclosed_name() else {
+                    continue;
+                };
+                let size: u64 = member.size();
+                visit(inside(archive, &name), size, &mut member)?;
+            }
+            Ok(())
+        }
+        Format::Tar => tar_members(archive, BufReader::new(file), visit),
+        Format::TarGz => tar_members(archive, GzDecoder::new(BufReader::new(file)), visit),
+    }
+}
+
+fn tar_members(
+    archive: &Path,
+    reader: impl Read,
+    mut visit: impl FnMut(PathBuf, u64, &mut dyn Read) -> Result<(), std::io::Error>,
+) -> Result<(), std::io::Error> {
+    let mut tar = tar::Archive::new(reader);
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path: PathBuf = inside(archive, &entry.path()?);
+        let size: u64 = entry.size();
+        visit(path, size, &mut entry)?;
+    }
+    Ok(())
+}
+
+/// Where `member` of `archive` is shown to be. Only the plain parts of its
+/// path are kept, so `/etc/passwd` or `../x` can't point out of the archive
+fn inside(archive: &Path, member: &Path) -> PathBuf {
+    let mut path: PathBuf = archive.to_path_buf();
+    path.extend(member.components().filter_map(|component| match component {
+        Component::Normal(part) => Some(part),
+        _ => None,
+    }));
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::File,
+        io::Write,
+        path::{Path, PathBuf},
+    };
+
+    use flate2::{Compression, write::GzEncoder};
+
+    use super::{Format, inside, members};
+
+    /// An archive in the temporary directory, removed when dropped
+    struct TempArchive(PathBuf);
+
+    impl TempArchive {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!(
+                "pursue-archive-{}-{}",
+                std::process::id(),
+                name
+            )))
+        }
+    }
+
+    impl Drop for TempArchive {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// Path and contents of every member of `archive`
+    fn read(archive: &Path) -> Vec<(PathBuf, String)> {
+        let mut read: Vec<(PathBuf, String)> = Vec::new();
+        members(
+            archive,
+            Format::of(archive).unwrap(),
+            |path, size, member| {
+                let mut contents: String = String::new();
+                member.read_to_string(&mut contents)?;
+                assert_eq!(size, contents.len() as u64);
+                read.push((path, contents));
+                Ok(())
+            },
+        )
+        .unwrap();
+        read
+    }
+
+    #[test]
+    fn formats_by_name() {
+        for (name, format) in [
+            ("src.zip", Some(Format::Zip)),
+            ("SRC.ZIP", Some(Format::Zip)),
+            ("src.tar", Some(Format::Tar)),
+            ("src.tar.gz", Some(Format::TarGz)),
+            ("src.tgz", Some(Format::TarGz)),
+            ("serde-1.0.0.crate", Some(Format::TarGz)),
+            ("src.gz", None),
+            ("zip", None),
+            ("main.rs", None),
+        ] {
+            assert_eq!(Format::of(Path::new(name)), format, "{}", name);
+        }
+    }
+
+    #[test]
+    fn members_stay_inside_the_archive() {
+        let archive: &Path = Path::new("/code/src.zip");
+        assert_eq!(
+            inside(archive, Path::new("src/main.rs")),
+            Path::new("/code/src.zip/src/main.rs")
+        );
+        assert_eq!(
+            inside(archive, Path::new("/etc/../passwd")),
+            Path::new("/code/src.zip/etc/passwd")
+        );
+    }
+
+    #[test]
+    fn gzipped_tarball_members() {
+        let archive: TempArchive = TempArchive::new("members.crate");
+        let mut builder = tar::Builder::new(GzEncoder::new(
+            File::create(&archive.0).unwrap(),
+            Compression::default(),
+        ));
+        for (path, contents) in [
+            ("pkg/src/lib.rs", "// TODO\n"),
+            ("pkg/README.md", "# pkg\n"),
+        ] {
+            let mut header: tar::Header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, contents.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+
+        assert_eq!(
+            read(&archive.0),
+            [
+                (archive.0.join("pkg/src/lib.rs"), "// TODO\n".to_string()),
+                (archive.0.join("pkg/README.md"), "# pkg\n".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn zip_members_without_directories() {
+        let archive: TempArchive = TempArchive::new("members.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&archive.0).unwrap());
+        let options: zip::write::SimpleFileOptions = zip::write::SimpleFileOptions::default();
+        writer.add_directory("src/", options).unwrap();
+        writer.start_file("src/main.py", options).unwrap();
+        writer.write_all(b"# FIXME\n").unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(
+            read(&archive.0),
+            [(archive.0.join("src/main.py"), "# FIXME\n".to_string())]
+        );
+    }
+}
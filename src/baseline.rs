@@ -0,0 +1,147 @@
+/*
+ *  baseline.rs - Findings accepted as existing debt, kept out of counts
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use crate::{dismissed::is_fingerprint, report::Report};
+
+/// Lives next to the dismissed list, in the anchor of the scan
+pub const BASELINE_FILE_NAME: &str = ".pursue-baseline";
+
+const HEADER: &str = "# pursue baseline, one finding per line: fingerprint keyword path
+# Sorted by path so unrelated changes never touch the same lines.
+# Regenerate with `pursue baseline update`, drop fixed findings with `pursue baseline prune`.
+";
+
+/// One accepted finding. Line numbers are left out so that editing a file
+/// doesn't rewrite the baseline
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Entry {
+    // Field order gives the sort order of the file
+    pub path: String,
+    pub keyword: String,
+    pub fingerprint: String,
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.fingerprint, self.keyword, self.path)
+    }
+}
+
+impl Entry {
+    fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.splitn(3, ' ');
+        let fingerprint: &str = parts.next()?;
+        let keyword: &str = parts.next()?;
+        let path: &str = parts.next()?;
+
+        match is_fingerprint(fingerprint) && !path.is_empty() {
+            true => Some(Self {
+                path: path.to_string(),
+                keyword: keyword.to_string(),
+                fingerprint: fingerprint.to_ascii_lowercase(),
+            }),
+            false => None,
+        }
+    }
+}
+
+pub fn path(anchor: &Path) -> PathBuf {
+    anchor.join(BASELINE_FILE_NAME)
+}
+
+/// Entries of a baseline file. A missing file is an empty baseline
+pub fn load(file: &Path) -> Result<BTreeSet<Entry>, std::io::Error> {
+    let contents: String = match std::fs::read_to_string(file) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(BTreeSet::new()),
+        Err(error) => return Err(error),
+    };
+
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            Entry::parse(line.trim_end()).ok_or_else(|| {
+                std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "{}:{}: expected \"fingerprint keyword path\", found {:?}",
+                        file.display(),
+                        index + 1,
+                        line
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Fingerprints in the baseline kept in `anchor`, for skipping findings
+pub fn fingerprints(anchor: &Path) -> Result<HashSet<String>, std::io::Error> {
+    Ok(load(&path(anchor))?
+        .into_iter()
+        .map(|entry| entry.fingerprint)
+        .collect())
+}
+
+pub fn save(file: &Path, entries: &BTreeSet<Entry>) -> Result<(), std::io::Error> {
+    let mut contents: String = String::from(HEADER);
+    for entry in entries {
+        contents += &entry.to_string();
+        contents.push('\n');
+    }
+    std::fs::write(file, contents)
+}
+
+/// Every finding in `report`, which has to be profiled without a baseline,
+/// with its path relative to `anchor`
+pub fn entries(report: &Report, anchor: &Path) -> BTreeSet<Entry> {
+    report
+        .occurrences()
+        .map(|(file, occurrence)| Entry {
+            path: file
+                .strip_prefix(anchor)
+                .unwrap_or(file)
+                .to_string_lossy()
+                .replace('\\', "/"),
+            keyword: occurrence.keyword.clone(),
+            fingerprint: occurrence.fingerprint.clone(),
+        })
+        .collect()
+}
+
+/// Three way merge of baselines the way git merges lines: anything either
+/// side added is kept, anything either side removed is dropped
+pub fn merge(
+    base: &BTreeSet<Entry>,
+    ours: &BTreeSet<Entry>,
+    theirs: &BTreeSet<Entry>,
+) -> BTreeSet<Entry> {
+    ours.union(theirs)
+        .filter(|entry| !base.contains(entry) || (ours.contains(entry) && theirs.contains(entry)))
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,294 @@
+/*
+ *  blame.rs - Attribute findings to the author of their line with git blame
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+
+use crate::{
+    report::{FileReport, Report},
+    severity::Escalation,
+};
+
+/// Markers last touched longer ago than this are flagged when no other age
+/// is given
+pub const DEFAULT_MAX_AGE_DAYS: u64 = 365;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Who last touched a line, and when in seconds since the epoch
+struct LineBlame {
+    author: String,
+    time: u64,
+}
+
+/// Blame of every line of `path`, keyed by 1 based line number. None when
+/// git is missing or the file isn't tracked
+fn blame_file(path: &Path) -> Option<HashMap<usize, LineBlame>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path.parent()?)
+        .args(["blame", "--line-porcelain", "--"])
+        .arg(path.file_name()?)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let mut lines: HashMap<usize, LineBlame> = HashMap::new();
+    let mut line_number: usize = 0;
+    let mut author: String = String::new();
+    let mut time: u64 = 0;
+
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if line.starts_with('\t') {
+            // The line's contents end its entry
+            lines.insert(
+                line_number,
+                LineBlame {
+                    author: std::mem::take(&mut author),
+                    time,
+                },
+            );
+        } else if let Some(name) = line.strip_prefix("author ") {
+            author = name.to_string();
+        } else if let Some(seconds) = line.strip_prefix("author-time ") {
+            time = seconds.parse().unwrap_or(0);
+        } else {
+            let mut parts = line.split(' ');
+            if parts
+                .next()
+                .is_some_and(|hash| hash.len() >= 40 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+            {
+                line_number = parts.nth(1).and_then(|n| n.parse().ok()).unwrap_or(0);
+            }
+        }
+    }
+
+    Some(lines)
+}
+
+fn annotate_file(file: &mut FileReport) {
+    if file.occurrences.is_empty() {
+        return;
+    }
+
+    if let Some(lines) = blame_file(&file.path) {
+        for occurrence in &mut file.occurrences {
+            if let Some(blame) = lines.get(&occurrence.line) {
+                occurrence.author = Some(blame.author.clone());
+                occurrence.authored = Some(blame.time);
+            }
+        }
+    }
+}
+
+/// Attribute every finding of `report` to the author of its line, without
+/// counting or flagging anything
+pub fn attribute(report: &mut Report) {
+    // git blame is slow, so files are blamed on every CPU at once
+    report.files.par_iter_mut().for_each(annotate_file);
+}
+
+/// Attribute every finding of `report` to the author of its line, count
+/// them per author, and flag those older than `max_age_days`
+pub fn annotate(report: &mut Report, max_age_days: u64) {
+    attribute(report);
+
+    let cutoff: u64 = cutoff(max_age_days);
+
+    let mut author_table: BTreeMap<String, usize> = BTreeMap::new();
+    let mut stale_count: usize = 0;
+    for (_, occurrence) in report.occurrences() {
+        if let Some(author) = &occurrence.author {
+            *author_table.entry(author.clone()).or_insert(0) += 1;
+        }
+        if occurrence.authored.is_some_and(|time| time < cutoff) {
+            stale_count += 1;
+        }
+    }
+
+    report.author_table = author_table;
+    report.max_age_days = Some(max_age_days);
+    report.stale_count = stale_count;
+}
+
+/// Lines last touched before this time, in seconds since the epoch, are
+/// older than `max_age_days`
+pub fn cutoff(max_age_days: u64) -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        .saturating_sub(max_age_days * SECONDS_PER_DAY)
+}
+
+/// Raise findings of `report` whose line was last touched longer before
+/// `now` than the rule for their keyword allows to that rule's severity,
+/// returning how many were raised. Findings that weren't attributed keep
+/// their severity, and none is ever lowered
+pub fn escalate(report: &mut Report, rules: &BTreeMap<String, Escalation>, now: u64) -> usize {
+    let mut escalated: usize = 0;
+    for file in &mut report.files {
+        for occurrence in &mut file.occurrences {
+            let (Some(rule), Some(authored)) =
+                (rules.get(&occurrence.keyword), occurrence.authored)
+            else {
+                continue;
+            };
+            if authored < now.saturating_sub(rule.older_than_days * SECONDS_PER_DAY)
+                && rule.severity > occurrence.severity
+            {
+                occurrence.escalated_from = Some(occurrence.severity);
+                occurrence.severity = rule.severity;
+                escalated += 1;
+            }
+        }
+    }
+    escalated
+}
+
+/// `YYYY-MM-DD` of a time in seconds since the epoch, in UTC
+pub fn format_date(time: u64) -> String {
+    // Howard Hinnant's days to civil date algorithm
+    let days: i64 = (time / SECONDS_PER_DAY) as i64 + 719_468;
+    let era: i64 = days.div_euclid(146_097);
+    let day_of_era: i64 = days.rem_euclid(146_097);
+    let year_of_era: i64 =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year: i64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index: i64 = (5 * day_of_year + 2) / 153;
+    let day: i64 = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month: i64 = match month_index < 10 {
+        true => month_index + 3,
+        false => month_index - 9,
+    };
+    let year: i64 = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, path::PathBuf, process::Command};
+
+    use super::{attribute, escalate};
+    use crate::{
+        ScannerBuilder,
+        report::Report,
+        severity::{Escalation, Severity},
+    };
+
+    /// Commit everything in `root` as if on `date`
+    fn commit(root: &PathBuf, date: &str) {
+        for args in [&["add", "-A"][..], &["commit", "-qm", "notes"][..]] {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(root)
+                .args([
+                    "-c",
+                    "user.name=Old Timer",
+                    "-c",
+                    "user.email=old@example.com",
+                ])
+                .args(args)
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_DATE", date)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+    }
+
+    #[test]
+    fn old_markers_are_escalated() {
+        let root: PathBuf =
+            std::env::temp_dir().join(format!("pursue-blame-{}-escalation", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(&root)
+            .args(["init", "-q"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let notes: PathBuf = root.join("notes.rs");
+        std::fs::write(&notes, "// TODO: old\n// BUG: old\n").unwrap();
+        commit(&root, "2001-01-01T00:00:00Z");
+        std::fs::write(&notes, "// TODO: old\n// BUG: old\n// TODO: new\n").unwrap();
+        commit(&root, "2024-06-01T00:00:00Z");
+
+        let mut report: Report = ScannerBuilder::new()
+            .paths([root.clone()])
+            .cache(false)
+            .build()
+            .unwrap()
+            .scan()
+            .unwrap();
+        attribute(&mut report);
+
+        let rules: BTreeMap<String, Escalation> = BTreeMap::from([
+            (
+                "TODO".to_string(),
+                Escalation {
+                    older_than_days: 365,
+                    severity: Severity::Error,
+                },
+            ),
+            (
+                "BUG".to_string(),
+                Escalation {
+                    older_than_days: 365,
+                    severity: Severity::Warning,
+                },
+            ),
+        ]);
+        // 2024-12-01, half a year after the second commit
+        let escalated: usize = escalate(&mut report, &rules, 1_733_011_200);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let severities: Vec<(usize, Severity, Option<Severity>)> = report
+            .occurrences()
+            .map(|(_, occurrence)| {
+                (
+                    occurrence.line,
+                    occurrence.severity,
+                    occurrence.escalated_from,
+                )
+            })
+            .collect();
+        assert_eq!(
+            severities,
+            [
+                (1, Severity::Error, Some(Severity::Info)),
+                // Never lowered
+                (2, Severity::Error, None),
+                (3, Severity::Info, None),
+            ]
+        );
+        assert_eq!(escalated, 1);
+        assert_eq!(report.findings_at_least(Severity::Error), 2);
+    }
+}
@@ -0,0 +1,368 @@
+/*
+ *  cache.rs - Classification decisions and scan results remembered between runs
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::Metadata,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dismissed::{FNV_OFFSET_BASIS, fnv1a, to_hex},
+    report::FileReport,
+    severity::Severity,
+};
+
+/// Per user cache directory, `$XDG_CACHE_HOME/pursue` or the platform's
+/// equivalent
+pub fn directory() -> Option<PathBuf> {
+    let base: PathBuf = match (
+        std::env::var_os("XDG_CACHE_HOME"),
+        std::env::var_os("HOME"),
+        std::env::var_os("LOCALAPPDATA"),
+    ) {
+        (Some(cache), _, _) if !cache.is_empty() => PathBuf::from(cache),
+        (_, Some(home), _) if !home.is_empty() => PathBuf::from(home).join(".cache"),
+        (_, _, Some(local)) if !local.is_empty() => PathBuf::from(local),
+        _ => return None,
+    };
+    Some(base.join("pursue"))
+}
+
+/// What a file looked like when it was classified. Any change to it means
+/// classifying it again
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Stamp {
+    length: u64,
+    modified: u128,
+}
+
+impl Stamp {
+    pub fn of(metadata: &Metadata) -> Self {
+        Self {
+            length: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |since| since.as_nanos()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Entry {
+    stamp: Stamp,
+    /// None when the contents gave nothing away either
+    language: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct CacheFile {
+    /// `Registry::signature` the entries were decided with
+    signature: u64,
+    entries: HashMap<PathBuf, Entry>,
+}
+
+/// Entries a run used or added, which is all that gets saved. Each worker
+/// keeps its own, merged before saving
+#[derive(Default)]
+pub struct Classifications {
+    entries: HashMap<PathBuf, Entry>,
+    changed: bool,
+}
+
+impl Classifications {
+    pub fn insert(&mut self, path: &Path, stamp: Stamp, language: Option<String>) {
+        self.entries
+            .insert(path.to_path_buf(), Entry { stamp, language });
+        self.changed = true;
+    }
+
+    pub fn merge(&mut self, other: Classifications) {
+        self.entries.extend(other.entries);
+        self.changed |= other.changed;
+    }
+}
+
+/// Languages of files that had to be read to be classified, kept per scan
+/// root so repeated runs skip reading them again. Only read while scanning,
+/// so workers share it without locking
+pub struct ClassificationCache {
+    file: Option<PathBuf>,
+    signature: u64,
+    previous: HashMap<PathBuf, Entry>,
+}
+
+impl ClassificationCache {
+    /// A cache that remembers nothing and is never written
+    pub fn disabled() -> Self {
+        Self {
+            file: None,
+            signature: 0,
+            previous: HashMap::new(),
+        }
+    }
+
+    /// The cache of `root`. It is only a shortcut, so a missing or
+    /// unreadable one starts out empty
+    pub fn load(root: &Path, signature: u64) -> Self {
+        let file: Option<PathBuf> = directory().map(|directory| {
+            let key: u64 = fnv1a(FNV_OFFSET_BASIS, root.as_os_str().as_encoded_bytes());
+            directory.join(format!("classification-{}.json", to_hex(key)))
+        });
+
+        let previous: HashMap<PathBuf, Entry> = file
+            .as_ref()
+            .and_then(|file| std::fs::read(file).ok())
+            .and_then(|contents| serde_json::from_slice::<CacheFile>(&contents).ok())
+            .filter(|cache| cache.signature == signature)
+            .map(|cache| cache.entries)
+            .unwrap_or_default();
+
+        Self {
+            file,
+            signature,
+            previous,
+        }
+    }
+
+    /// The remembered language of `path`, Some(None) when it is remembered
+    /// to have none, or None when it has to be classified. Entries found are
+    /// kept in `used`
+    pub fn get(
+        &self,
+        path: &Path,
+        stamp: Stamp,
+        used: &mut Classifications,
+    ) -> Option<Option<String>> {
+        let entry: Entry = self
+            .previous
+            .get(path)
+            .filter(|entry| entry.stamp == stamp)?
+            .clone();
+        let language: Option<String> = entry.language.clone();
+        used.entries.insert(path.to_path_buf(), entry);
+        Some(language)
+    }
+
+    /// Write the entries of this run back, dropping those of files that are
+    /// gone
+    pub fn save(&self, current: &Classifications) -> Result<(), std::io::Error> {
+        let file: &Path = match &self.file {
+            Some(file) if current.changed || current.entries.len() != self.previous.len() => file,
+            _ => return Ok(()),
+        };
+
+        if let Some(directory) = file.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        let cache: CacheFile = CacheFile {
+            signature: self.signature,
+            entries: current.entries.clone(),
+        };
+        std::fs::write(file, serde_json::to_vec(&cache)?)
+    }
+}
+
+/// A file as it was scanned, along with the findings left out of it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Scanned {
+    stamp: Stamp,
+    /// Keywords of the directory the file was in
+    keywords: Vec<String>,
+    /// Severities config files gave keywords in that directory
+    #[serde(default)]
+    severities: BTreeMap<String, Severity>,
+    pub report: FileReport,
+    pub suppressed: usize,
+    pub dismissed: usize,
+    pub baselined: usize,
+}
+
+impl Scanned {
+    pub fn new(
+        stamp: Stamp,
+        keywords: Vec<String>,
+        severities: BTreeMap<String, Severity>,
+        report: FileReport,
+        left_out: (usize, usize, usize),
+    ) -> Self {
+        let (suppressed, dismissed, baselined): (usize, usize, usize) = left_out;
+        Self {
+            stamp,
+            keywords,
+            severities,
+            report,
+            suppressed,
+            dismissed,
+            baselined,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct ResultsFile {
+    /// `Logger::result_signature` the files were scanned with
+    signature: String,
+    files: HashMap<PathBuf, Scanned>,
+}
+
+/// Files a run scanned or found unchanged, which is all that gets saved.
+/// Each worker keeps its own, merged before saving
+#[derive(Default)]
+pub struct ScannedFiles {
+    files: HashMap<PathBuf, Scanned>,
+    changed: bool,
+}
+
+impl ScannedFiles {
+    pub fn insert(&mut self, scanned: Scanned) {
+        self.files.insert(scanned.report.path.clone(), scanned);
+        self.changed = true;
+    }
+
+    pub fn merge(&mut self, other: ScannedFiles) {
+        self.files.extend(other.files);
+        self.changed |= other.changed;
+    }
+}
+
+/// Reports of the files of the last run, saved to the file `--cache`
+/// names, so a run only parses the files that changed since. Only read
+/// while scanning, so workers share it without locking
+pub struct ResultCache {
+    file: Option<PathBuf>,
+    signature: String,
+    previous: HashMap<PathBuf, Scanned>,
+}
+
+impl ResultCache {
+    /// A cache kept in memory by a process that scans the same tree over
+    /// and over, never written. It starts out empty
+    pub fn resident() -> Self {
+        Self {
+            file: None,
+            signature: String::new(),
+            previous: HashMap::new(),
+        }
+    }
+
+    /// The cache saved to `file`. Results scanned with other settings, and
+    /// a missing or unreadable file, leave it empty
+    pub fn load(file: &Path, signature: String) -> Self {
+        // Named the way the scan names files, so it can leave this one out
+        let file: PathBuf = std::path::absolute(file).unwrap_or(file.to_path_buf());
+        let file: PathBuf = match (file.parent().map(Path::canonicalize), file.file_name()) {
+            (Some(Ok(directory)), Some(name)) => directory.join(name),
+            _ => file,
+        };
+        let previous: HashMap<PathBuf, Scanned> = std::fs::read(&file)
+            .ok()
+            .and_then(|contents| serde_json::from_slice::<ResultsFile>(&contents).ok())
+            .filter(|cache| cache.signature == signature)
+            .map(|cache| cache.files)
+            .unwrap_or_default();
+
+        Self {
+            file: Some(file),
+            signature,
+            previous,
+        }
+    }
+
+    /// Forget the files of the last run if it was scanned with settings
+    /// other than `signature`
+    pub fn prepare(&mut self, signature: String) {
+        if self.signature != signature {
+            self.previous.clear();
+            self.signature = signature;
+        }
+    }
+
+    /// Take the files of this run as the ones the next run compares against
+    pub fn keep(&mut self, current: ScannedFiles) {
+        self.previous = current.files;
+    }
+
+    /// Whether `path` is the file the cache is saved to
+    pub fn is_saved_to(&self, path: &Path) -> bool {
+        self.file.as_deref() == Some(path)
+    }
+
+    /// How `path` was scanned, if it is unchanged and in a directory with
+    /// the same `keywords` and `severities`. A file with a new modification time but the old
+    /// length is only changed when `hash`, the `content_hash` of what it
+    /// holds now, differs. Files found are kept in `used`
+    pub fn get(
+        &self,
+        path: &Path,
+        stamp: Stamp,
+        keywords: &[String],
+        severities: &BTreeMap<String, Severity>,
+        hash: impl FnOnce() -> Option<String>,
+        used: &mut ScannedFiles,
+    ) -> Option<Scanned> {
+        let scanned: &Scanned = self
+            .previous
+            .get(path)
+            .filter(|scanned| scanned.keywords == keywords && scanned.severities == *severities)?;
+        let mut scanned: Scanned = match scanned.stamp == stamp {
+            true => scanned.clone(),
+            false
+                if scanned.stamp.length == stamp.length
+                    && scanned.report.content_hash.is_some()
+                    && hash() == scanned.report.content_hash =>
+            {
+                used.changed = true;
+                Scanned {
+                    stamp,
+                    ..scanned.clone()
+                }
+            }
+            false => return None,
+        };
+        scanned.report.path = path.to_path_buf();
+        used.files.insert(path.to_path_buf(), scanned.clone());
+        Some(scanned)
+    }
+
+    /// Write the files of this run back, dropping those that are gone or
+    /// weren't scanned this time
+    pub fn save(&self, current: &ScannedFiles) -> Result<(), std::io::Error> {
+        let file: &Path = match &self.file {
+            Some(file) if current.changed || current.files.len() != self.previous.len() => file,
+            _ => return Ok(()),
+        };
+
+        if let Some(directory) = file
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(directory)?;
+        }
+        let cache: ResultsFile = ResultsFile {
+            signature: self.signature.clone(),
+            files: current.files.clone(),
+        };
+        std::fs::write(file, serde_json::to_vec(&cache)?)
+    }
+}
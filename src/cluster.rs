@@ -0,0 +1,199 @@
+/*
+ *  cluster.rs - Group findings across files whose messages say nearly the same
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::PathBuf,
+};
+
+use crate::{
+    dismissed::{FNV_OFFSET_BASIS, fnv1a},
+    report::{Cluster, ClusterLocation, Report},
+};
+
+/// Share of word pairs two messages need in common to be grouped when no
+/// other similarity is given
+pub const DEFAULT_SIMILARITY: f64 = 0.6;
+
+/// A finding with something to say after its keyword
+struct Member<'a> {
+    path: &'a PathBuf,
+    line: usize,
+    keyword: &'a str,
+    message: String,
+    shingles: BTreeSet<u64>,
+}
+
+/// Lowercased words of what a finding says, without its keyword or owner.
+/// `// TODO(sep): Workaround for driver bug!` says
+/// `workaround for driver bug`
+fn words(snippet: &str, keyword: &str) -> Vec<String> {
+    let message: &str = snippet
+        .find(keyword)
+        .map_or(snippet, |start| &snippet[start + keyword.len()..]);
+    let message: &str = message
+        .strip_prefix('(')
+        .and_then(|rest| rest.split_once(')'))
+        .map_or(message, |(_, rest)| rest);
+
+    message
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Hashes of the overlapping word pairs of a message, so that word order
+/// matters a little but a changed word only costs two of them
+fn shingles(words: &[String]) -> BTreeSet<u64> {
+    let hash = |words: &[String]| -> u64 {
+        words.iter().fold(FNV_OFFSET_BASIS, |hash, word| {
+            fnv1a(fnv1a(hash, word.as_bytes()), b" ")
+        })
+    };
+
+    match words.len() {
+        0 | 1 => words.chunks(1).map(hash).collect(),
+        _ => words.windows(2).map(hash).collect(),
+    }
+}
+
+/// Shingles in common over shingles in either
+fn jaccard(left: &BTreeSet<u64>, right: &BTreeSet<u64>) -> f64 {
+    let common: usize = left.intersection(right).count();
+    common as f64 / (left.len() + right.len() - common) as f64
+}
+
+fn find(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+/// Group the findings of `report` whose messages share at least
+/// `similarity` of their word pairs. Only groups spanning several files are
+/// kept, since those point at something systemic
+pub fn annotate(report: &mut Report, similarity: f64) {
+    let members: Vec<Member> = report
+        .occurrences()
+        .filter_map(|(path, occurrence)| {
+            let words: Vec<String> = words(&occurrence.snippet, &occurrence.keyword);
+            let shingles: BTreeSet<u64> = shingles(&words);
+            (!shingles.is_empty()).then(|| Member {
+                path,
+                line: occurrence.line,
+                keyword: &occurrence.keyword,
+                message: words.join(" "),
+                shingles,
+            })
+        })
+        .collect();
+
+    // Only findings sharing a shingle can be similar, so those are the
+    // only pairs compared
+    let mut holders: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (index, member) in members.iter().enumerate() {
+        for shingle in &member.shingles {
+            holders.entry(*shingle).or_default().push(index);
+        }
+    }
+
+    let mut parents: Vec<usize> = (0..members.len()).collect();
+    for (index, member) in members.iter().enumerate() {
+        let candidates: BTreeSet<usize> = member
+            .shingles
+            .iter()
+            .flat_map(|shingle| &holders[shingle])
+            .copied()
+            .filter(|other| *other > index)
+            .collect();
+
+        for other in candidates {
+            let (root, other_root): (usize, usize) =
+                (find(&mut parents, index), find(&mut parents, other));
+            if root != other_root
+                && jaccard(&member.shingles, &members[other].shingles) >= similarity
+            {
+                parents[other_root] = root;
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<&Member>> = BTreeMap::new();
+    for (index, member) in members.iter().enumerate() {
+        groups
+            .entry(find(&mut parents, index))
+            .or_default()
+            .push(member);
+    }
+
+    let mut clusters: Vec<Cluster> = groups
+        .into_values()
+        .filter(|group| {
+            group
+                .iter()
+                .map(|member| member.path)
+                .collect::<BTreeSet<&PathBuf>>()
+                .len()
+                > 1
+        })
+        .map(|group| {
+            // The wording used most often stands for the whole group
+            let mut wordings: BTreeMap<&str, usize> = BTreeMap::new();
+            for member in &group {
+                *wordings.entry(&member.message).or_insert(0) += 1;
+            }
+            let message: &str = wordings
+                .iter()
+                .max_by(|left, right| left.1.cmp(right.1).then(right.0.cmp(left.0)))
+                .map_or("", |(message, _)| message);
+
+            let mut locations: Vec<ClusterLocation> = group
+                .iter()
+                .map(|member| ClusterLocation {
+                    path: member.path.clone(),
+                    line: member.line,
+                })
+                .collect();
+            locations
+                .sort_by(|left, right| (&left.path, left.line).cmp(&(&right.path, right.line)));
+
+            Cluster {
+                message: message.to_string(),
+                keywords: group
+                    .iter()
+                    .map(|member| member.keyword.to_string())
+                    .collect::<BTreeSet<String>>()
+                    .into_iter()
+                    .collect(),
+                locations,
+            }
+        })
+        .collect();
+
+    clusters.sort_by(|left, right| {
+        right
+            .locations
+            .len()
+            .cmp(&left.locations.len())
+            .then_with(|| left.message.cmp(&right.message))
+    });
+    report.clusters = clusters;
+}
@@ -0,0 +1,672 @@
+/*
+ *  comment.rs - Finds the comments of a line by walking it left to right
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{borrow::Cow, ops::Range};
+
+use crate::{filetype::Language, text};
+
+/// A block comment left open at the end of a line, which the next line
+/// starts inside of
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct OpenComment {
+    /// Index into the language's `block_comments`
+    pub(crate) block: usize,
+    /// How many are open, more than one only in languages where they nest
+    pub(crate) depth: usize,
+}
+
+/// A comment on a line
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Comment {
+    /// Bytes of the line it takes up, its markers included
+    pub(crate) range: Range<usize>,
+    /// Bytes of its text, between its markers
+    pub(crate) text: Range<usize>,
+    /// Index into the language's `block_comments`, None for an inline
+    /// comment
+    pub(crate) block: Option<usize>,
+    /// It was open before the line started, so the line starts inside it
+    /// rather than with its opener
+    pub(crate) continued: bool,
+}
+
+impl Comment {
+    /// Characters of `line` the comment starts and ends at, counting from 1
+    pub(crate) fn columns(&self, line: &str) -> (usize, usize) {
+        let column = |byte: usize| text::slice(line, 0..byte).chars().count();
+        (column(self.range.start) + 1, column(self.range.end))
+    }
+}
+
+/// What a line is made of, as far as telling comments from code goes
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Line {
+    /// Every comment, from left to right
+    pub(crate) comments: Vec<Comment>,
+    /// Contents of the string literals outside of the comments
+    pub(crate) strings: Vec<Range<usize>>,
+    /// Anything but whitespace is outside of the comments
+    pub(crate) has_code: bool,
+    /// The block comment the line ends inside of
+    pub(crate) open: Option<OpenComment>,
+}
+
+/// What starts at some position of a line
+enum Marker<'a> {
+    Inline(&'a str),
+    /// Index into `block_comments` along with the opener
+    Block(usize, &'a str),
+    /// Opener and closer, and whether backslashes escape inside it
+    String(&'a str, &'a str, bool),
+}
+
+impl Line {
+    /// Walk `line` of `language` one character at a time, starting inside
+    /// the block comment `open` if the last line left one open. Strings are
+    /// skipped whole, so markers inside them don't count, and so are
+    /// comments, so markers inside those don't either
+    pub(crate) fn scan(language: &Language, line: &str, open: Option<OpenComment>) -> Line {
+        let mut scanned: Line = Line::default();
+        let mut position: usize = 0;
+
+        if let Some(open) = open {
+            let (comment, closed): (Comment, Result<usize, OpenComment>) =
+                block_comment(language, line, 0, 0, open);
+            scanned.comments.push(Comment {
+                continued: true,
+                ..comment
+            });
+            match closed {
+                Ok(end) => position = end,
+                Err(open) => {
+                    scanned.open = Some(open);
+                    return scanned;
+                }
+            }
+        }
+
+        while let Some(c) = line[position..].chars().next() {
+            if let Some(length) = char_literal(language, &line[position..]) {
+                scanned.has_code = true;
+                scanned.strings.push(position + 1..position + length - 1);
+                position += length;
+                continue;
+            }
+            let marker: Option<Marker> = match marker_at(language, &line[position..]) {
+                Some(Marker::Inline(_))
+                    if !language.opens_inline_comment_after(&line[..position]) =>
+                {
+                    None
+                }
+                Some(Marker::Block(..))
+                    if language.block_comments_at_line_start && position > 0 =>
+                {
+                    None
+                }
+                marker => marker,
+            };
+            match marker {
+                None => {
+                    scanned.has_code |= !c.is_whitespace();
+                    position += c.len_utf8();
+                }
+                Some(Marker::Inline(marker)) => {
+                    // Only whitespace and line prefixes came before it
+                    if language.inline_comments_at_line_start {
+                        scanned.has_code = false;
+                    }
+                    scanned.comments.push(Comment {
+                        range: position..line.len(),
+                        text: position + marker.len()..line.len(),
+                        block: None,
+                        continued: false,
+                    });
+                    break;
+                }
+                Some(Marker::Block(block, opener)) => {
+                    let open: OpenComment = OpenComment { block, depth: 1 };
+                    let (comment, closed): (Comment, Result<usize, OpenComment>) =
+                        block_comment(language, line, position, position + opener.len(), open);
+                    scanned.comments.push(comment);
+                    match closed {
+                        Ok(end) => position = end,
+                        Err(open) => {
+                            scanned.open = Some(open);
+                            break;
+                        }
+                    }
+                }
+                Some(Marker::String(opener, closer, escapes)) => {
+                    scanned.has_code = true;
+                    let contents: usize = position + opener.len();
+                    match find_closer(&line[contents..], closer, escapes) {
+                        Some(found) => {
+                            scanned.strings.push(contents..contents + found);
+                            position = contents + found + closer.len();
+                        }
+                        // Unterminated, the rest of the line is string
+                        None => {
+                            scanned.strings.push(contents..line.len());
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        scanned
+    }
+
+    /// The code of `line` before its first comment, with the contents of
+    /// its string literals blanked out. Byte positions are kept
+    pub(crate) fn code<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let end: usize = self
+            .comments
+            .first()
+            .map_or(line.len(), |comment| comment.range.start);
+        let code: &str = &line[..end];
+        if self.strings.is_empty() {
+            return Cow::Borrowed(code);
+        }
+
+        let mut masked: String = String::with_capacity(code.len());
+        let mut copied: usize = 0;
+        for string in self.strings.iter().filter(|string| string.start < end) {
+            masked += &code[copied..string.start];
+            masked += &" ".repeat(string.end.min(end) - string.start);
+            copied = string.end.min(end);
+        }
+        masked += &code[copied..];
+        Cow::Owned(masked)
+    }
+}
+
+/// The longest comment or string opener at the start of `text`. A block
+/// comment beats an inline one starting the same way, like `--[[` in Lua.
+/// A comment opener that is a word, like `REM`, has to be followed by
+/// whitespace or the end of the line
+fn marker_at<'a>(language: &'a Language, text: &str) -> Option<Marker<'a>> {
+    let mut found: Option<(usize, Marker)> = None;
+    let mut consider = |opener: &str, marker: Marker<'a>| {
+        let comment: bool = !matches!(marker, Marker::String(..));
+        let starts: bool = match comment && language.comments_ignore_case {
+            true => text
+                .get(..opener.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(opener)),
+            false => text.starts_with(opener),
+        };
+        // Only looked at once `text` is known to start with the opener
+        let whole = || {
+            !comment
+                || !opener.ends_with(char::is_alphanumeric)
+                || text[opener.len()..]
+                    .chars()
+                    .next()
+                    .is_none_or(char::is_whitespace)
+        };
+        if starts
+            && whole()
+            && found
+                .as_ref()
+                .is_none_or(|(length, _)| opener.len() > *length)
+        {
+            found = Some((opener.len(), marker));
+        }
+    };
+
+    for (index, (opener, _)) in language.block_comments.iter().enumerate() {
+        consider(opener, Marker::Block(index, opener));
+    }
+    for marker in &language.inline_comments {
+        consider(marker, Marker::Inline(marker));
+    }
+    for (opener, closer) in &language.strings {
+        consider(opener, Marker::String(opener, closer, true));
+    }
+    for (opener, closer) in &language.raw_strings {
+        consider(opener, Marker::String(opener, closer, false));
+    }
+    found.map(|(_, marker)| marker)
+}
+
+/// Length of the character literal at the start of `text`, in a language
+/// with `char_literals`. A `'` not closed right after one character or
+/// escape starts a lifetime or a label instead
+fn char_literal(language: &Language, text: &str) -> Option<usize> {
+    let rest: &str = text.strip_prefix('\'').filter(|_| language.char_literals)?;
+    let mut characters = rest.char_indices();
+    let contents: usize = match characters.next()? {
+        (_, '\'') => return None,
+        (_, '\\') => match characters.next()? {
+            // `'\u{1F600}'`
+            (index, 'u') => index + rest[index..].find('}')? + 1,
+            // `'\x7f'`
+            (index, 'x') => index + 3,
+            (index, escaped) => index + escaped.len_utf8(),
+        },
+        (_, c) => c.len_utf8(),
+    };
+    rest.get(contents..)?
+        .starts_with('\'')
+        .then_some(contents + 2)
+}
+
+/// The block comment `open` on `line` from byte `start`, its text from
+/// byte `text`, along with where it closes
+fn block_comment(
+    language: &Language,
+    line: &str,
+    start: usize,
+    text: usize,
+    open: OpenComment,
+) -> (Comment, Result<usize, OpenComment>) {
+    let closed: Result<usize, OpenComment> = close(language, line, text, open);
+    let (end, text_end): (usize, usize) = match closed {
+        Ok(end) => {
+            let closer: usize = language
+                .block_comments
+                .get(open.block)
+                .map_or(0, |(_, closer)| closer.len());
+            (end, end - closer)
+        }
+        Err(_) => (line.len(), line.len()),
+    };
+    let comment: Comment = Comment {
+        range: start..end,
+        text: text..text_end.max(text),
+        block: Some(open.block),
+        continued: false,
+    };
+    (comment, closed)
+}
+
+/// Where the block comment `open` closes on `line`, looking from byte
+/// `from`, as the end of its closer. Otherwise how it is left open at the
+/// end of the line. Only a block comment that nests counts the openers
+/// inside it
+fn close(
+    language: &Language,
+    line: &str,
+    from: usize,
+    open: OpenComment,
+) -> Result<usize, OpenComment> {
+    let Some((opener, closer)) = language.block_comments.get(open.block) else {
+        return Ok(from);
+    };
+
+    let mut depth: usize = open.depth;
+    let mut position: usize = from;
+    while let Some(c) = line[position..].chars().next() {
+        let rest: &str = &line[position..];
+        if rest.starts_with(closer.as_str())
+            && (position == 0 || !language.block_comments_at_line_start)
+        {
+            depth -= 1;
+            position += closer.len();
+            if depth == 0 {
+                return Ok(position);
+            }
+        } else if language.nests(open.block) && rest.starts_with(opener.as_str()) {
+            depth += 1;
+            position += opener.len();
+        } else {
+            position += c.len_utf8();
+        }
+    }
+    Err(OpenComment {
+        block: open.block,
+        depth,
+    })
+}
+
+/// Where `closer` ends a string whose contents start `text`
+fn find_closer(text: &str, closer: &str, escapes: bool) -> Option<usize> {
+    let mut characters = text.char_indices();
+    while let Some((index, c)) = characters.next() {
+        if escapes && c == '\\' {
+            characters.next();
+        } else if text[index..].starts_with(closer) {
+            return Some(index);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{Line, OpenComment};
+    use crate::filetype::{Language, Registry};
+
+    /// The built in language of files named like `file`
+    fn language(file: &str) -> Language {
+        let registry: Registry = Registry::load(None).unwrap();
+        registry.classify(Path::new(file)).unwrap().0.clone()
+    }
+
+    /// The comments of every line, in order, and whether it has code, with
+    /// block comments carried from one line to the next
+    fn scan(file: &str, lines: &[&str]) -> Vec<(Vec<String>, bool)> {
+        let language: Language = language(file);
+        let mut open: Option<OpenComment> = None;
+        lines
+            .iter()
+            .map(|line| {
+                let scanned: Line = Line::scan(&language, line, open);
+                open = scanned.open;
+                let comments: Vec<String> = scanned
+                    .comments
+                    .iter()
+                    .map(|comment| line[comment.range.clone()].to_string())
+                    .collect();
+                (comments, scanned.has_code)
+            })
+            .collect()
+    }
+
+    /// Comments of a single line and whether it has code
+    fn scan_line(file: &str, line: &str) -> (Vec<String>, bool) {
+        scan(file, &[line]).remove(0)
+    }
+
+    fn comments(texts: &[&str], has_code: bool) -> (Vec<String>, bool) {
+        (
+            texts.iter().map(|text| text.to_string()).collect(),
+            has_code,
+        )
+    }
+
+    #[test]
+    fn inline_comment_after_code() {
+        assert_eq!(
+            scan_line("a.c", "int x = 1; // TODO"),
+            comments(&["// TODO"], true)
+        );
+    }
+
+    #[test]
+    fn block_comments_around_code() {
+        assert_eq!(
+            scan_line("a.c", "/* a */ code(); /* b */"),
+            comments(&["/* a */", "/* b */"], true)
+        );
+        assert_eq!(
+            scan_line("a.c", "/**//**/ // c"),
+            comments(&["/**/", "/**/", "// c"], false)
+        );
+    }
+
+    #[test]
+    fn closer_followed_by_inline_comment() {
+        assert_eq!(
+            scan("a.c", &["/* starts", "ends */ // more", "code();"]),
+            [
+                comments(&["/* starts"], false),
+                comments(&["ends */", "// more"], false),
+                comments(&[], true),
+            ]
+        );
+    }
+
+    #[test]
+    fn closer_followed_by_code_and_another_opener() {
+        assert_eq!(
+            scan("a.c", &["/*", "*/ code(); /* again", "still */"]),
+            [
+                comments(&["/*"], false),
+                comments(&["*/", "/* again"], true),
+                comments(&["still */"], false),
+            ]
+        );
+    }
+
+    #[test]
+    fn openers_inside_inline_comments() {
+        assert_eq!(
+            scan("a.rs", &["//! arch/*/include", "code();"]),
+            [
+                comments(&["//! arch/*/include"], false),
+                comments(&[], true)
+            ]
+        );
+        assert_eq!(
+            scan("a.py", &["#yield \"\"\"", "x = 1"]),
+            [comments(&["#yield \"\"\""], false), comments(&[], true)]
+        );
+    }
+
+    #[test]
+    fn inline_markers_inside_block_comments() {
+        assert_eq!(
+            scan_line("a.c", "/* // */ code();"),
+            comments(&["/* // */"], true)
+        );
+    }
+
+    #[test]
+    fn markers_inside_strings() {
+        assert_eq!(
+            scan_line("a.c", "s = \"/* not */ // either\"; // real"),
+            comments(&["// real"], true)
+        );
+        assert_eq!(scan_line("a.c", "s = \"\\\"//\\\"\";"), comments(&[], true));
+        assert_eq!(
+            scan_line("a.rs", "let raw = r#\"/* \"# ; // end"),
+            comments(&["// end"], true)
+        );
+    }
+
+    #[test]
+    fn unterminated_string_runs_to_the_end() {
+        assert_eq!(scan_line("a.c", "s = \"abc // x"), comments(&[], true));
+    }
+
+    #[test]
+    fn quotes_inside_block_comments() {
+        assert_eq!(
+            scan_line("a.c", "/* it's \" */ x();"),
+            comments(&["/* it's \" */"], true)
+        );
+    }
+
+    #[test]
+    fn nested_block_comments() {
+        assert_eq!(
+            scan(
+                "a.rs",
+                &[
+                    "/* a /* b */ still */ code();",
+                    "/* one /* two",
+                    "*/ three */ x"
+                ]
+            ),
+            [
+                comments(&["/* a /* b */ still */"], true),
+                comments(&["/* one /* two"], false),
+                comments(&["*/ three */"], true),
+            ]
+        );
+    }
+
+    #[test]
+    fn block_comments_that_dont_nest() {
+        assert_eq!(
+            scan_line("a.c", "/* a /* b */ code */"),
+            comments(&["/* a /* b */"], true)
+        );
+    }
+
+    #[test]
+    fn several_kinds_of_block_comment() {
+        assert_eq!(
+            scan_line("a.d", "/+ /* +/ x; /* /+ */"),
+            comments(&["/+ /* +/", "/* /+ */"], true)
+        );
+    }
+
+    #[test]
+    fn same_opener_and_closer() {
+        assert_eq!(
+            scan(
+                "a.py",
+                &["\"\"\"Doc\"\"\"", "x = \"\"\"", "text", "\"\"\" + y"]
+            ),
+            [
+                comments(&["\"\"\"Doc\"\"\""], false),
+                comments(&["\"\"\""], true),
+                comments(&["text"], false),
+                comments(&["\"\"\""], true),
+            ]
+        );
+    }
+
+    #[test]
+    fn longest_marker_wins() {
+        assert_eq!(
+            scan(
+                "a.lua",
+                &["--[[ block ]] x = 1", "-- line", "--[[", "]] y()"]
+            ),
+            [
+                comments(&["--[[ block ]]"], true),
+                comments(&["-- line"], false),
+                comments(&["--[["], false),
+                comments(&["]]"], true),
+            ]
+        );
+    }
+
+    #[test]
+    fn html_comment_left_open() {
+        let language: Language = language("a.html");
+        let scanned: Line = Line::scan(&language, "<!-- a --> <p> <!-- b", None);
+
+        assert!(scanned.has_code);
+        assert_eq!(scanned.comments.len(), 2);
+        assert_eq!(scanned.open, Some(OpenComment { block: 0, depth: 1 }));
+    }
+
+    #[test]
+    fn code_before_the_first_comment_without_strings() {
+        let language: Language = language("a.rs");
+        let line: &str = "let s = \"fn x\"; fn y() {} // fn z";
+        let scanned: Line = Line::scan(&language, line, None);
+
+        assert_eq!(scanned.code(line), "let s = \"    \"; fn y() {} ");
+    }
+
+    #[test]
+    fn multi_byte_characters_around_markers() {
+        assert_eq!(
+            scan_line("a.c", "/*😀*/ é(); //日本"),
+            comments(&["/*😀*/", "//日本"], true)
+        );
+    }
+
+    #[test]
+    fn text_between_markers() {
+        let language: Language = language("a.c");
+        let texts = |line: &str, open: Option<OpenComment>| -> Vec<String> {
+            Line::scan(&language, line, open)
+                .comments
+                .iter()
+                .map(|comment| line[comment.text.clone()].to_string())
+                .collect()
+        };
+        let open: Option<OpenComment> = Some(OpenComment { block: 0, depth: 1 });
+
+        assert_eq!(
+            texts("/* a */ x(); /* b */ // c", None),
+            [" a ", " b ", " c"]
+        );
+        assert_eq!(texts("/**/ /* open", None), ["", " open"]);
+        assert_eq!(texts("still */ x();", open), ["still "]);
+        assert_eq!(texts("all comment", open), ["all comment"]);
+    }
+
+    #[test]
+    fn shell_hashes_inside_words_are_code() {
+        assert_eq!(
+            scan_line("a.sh", "echo ${#arr[@]} TODO"),
+            comments(&[], true)
+        );
+        assert_eq!(
+            scan_line("a.sh", "shift $# # TODO"),
+            comments(&["# TODO"], true)
+        );
+        assert_eq!(scan_line("a.sh", "  # TODO"), comments(&["# TODO"], false));
+    }
+
+    #[test]
+    fn pod_and_begin_only_at_line_start() {
+        assert_eq!(
+            scan(
+                "a.pl",
+                &["$x = 1; =pod TODO", "=pod", " =cut TODO", "=cut", "1;"]
+            ),
+            [
+                comments(&[], true),
+                comments(&["=pod"], false),
+                comments(&[" =cut TODO"], false),
+                comments(&["=cut"], false),
+                comments(&[], true),
+            ]
+        );
+        assert_eq!(
+            scan(
+                "a.rb",
+                &["x =begin", "=begin", "  =end TODO", "=end", "puts x"]
+            ),
+            [
+                comments(&[], true),
+                comments(&["=begin"], false),
+                comments(&["  =end TODO"], false),
+                comments(&["=end"], false),
+                comments(&[], true),
+            ]
+        );
+    }
+
+    #[test]
+    fn batch_comments_start_statements() {
+        assert_eq!(scan_line("a.bat", "echo THEOREM TODO"), comments(&[], true));
+        assert_eq!(
+            scan_line("a.bat", "echo done REM TODO"),
+            comments(&[], true)
+        );
+        assert_eq!(scan_line("a.bat", "echo a::b TODO"), comments(&[], true));
+        assert_eq!(scan_line("a.bat", "REM"), comments(&["REM"], false));
+        assert_eq!(
+            scan_line("a.bat", "REM\tTODO"),
+            comments(&["REM\tTODO"], false)
+        );
+        assert_eq!(
+            scan_line("a.bat", "  rEm TODO"),
+            comments(&["rEm TODO"], false)
+        );
+        assert_eq!(
+            scan_line("a.bat", "@REM TODO"),
+            comments(&["REM TODO"], false)
+        );
+        assert_eq!(
+            scan_line("a.bat", " :: TODO"),
+            comments(&[":: TODO"], false)
+        );
+        assert_eq!(scan_line("a.bat", "REMARK TODO"), comments(&[], true));
+    }
+}
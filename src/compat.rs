@@ -0,0 +1,135 @@
+/*
+ *  compat.rs - Reports shaped like the JSON of other line counters
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value, json};
+
+use crate::report::{FileReport, LineCounts, Report};
+
+/// Counts in tokei's shape, `reports` being the files they add up
+fn tokei_language(counts: &LineCounts, reports: Vec<Value>, children: Value) -> Value {
+    json!({
+        "blanks": counts.blank,
+        "code": counts.code,
+        "comments": counts.comment,
+        "reports": reports,
+        "children": children,
+        "inaccurate": false,
+    })
+}
+
+impl Report {
+    /// Files that were scanned as one of the report's languages, leaving out
+    /// those only holding imported findings
+    fn counted_files(&self) -> impl Iterator<Item = &FileReport> {
+        self.files
+            .iter()
+            .filter(|file| self.filetype_table.contains_key(&file.file_type))
+    }
+
+    /// Same shape as `tokei --output json`: every language with its counts
+    /// and files, followed by a `Total`
+    pub fn tokei_json(&self) -> Value {
+        let mut reports: BTreeMap<&str, Vec<Value>> = BTreeMap::new();
+        for file in self.counted_files() {
+            reports.entry(&file.file_type).or_default().push(json!({
+                "name": file.path,
+                "stats": {
+                    "blanks": file.line_counts.blank,
+                    "code": file.line_counts.code,
+                    "comments": file.line_counts.comment,
+                    "blobs": {},
+                },
+            }));
+        }
+
+        let mut languages: Map<String, Value> = Map::new();
+        let mut total: LineCounts = LineCounts::default();
+        for (language, counts) in &self.language_line_counts {
+            if !self.filetype_table.contains_key(language) {
+                continue;
+            }
+            total += *counts;
+            languages.insert(
+                language.clone(),
+                tokei_language(
+                    counts,
+                    reports.get(language.as_str()).cloned().unwrap_or_default(),
+                    json!({}),
+                ),
+            );
+        }
+
+        let children: Map<String, Value> = reports
+            .into_iter()
+            .map(|(language, reports)| (language.to_string(), Value::from(reports)))
+            .collect();
+        languages.insert(
+            "Total".to_string(),
+            tokei_language(&total, Vec::new(), Value::Object(children)),
+        );
+        Value::Object(languages)
+    }
+
+    /// Same shape as `cloc --json`: a `header` with the totals, every
+    /// language's counts, and their `SUM`
+    pub fn cloc_json(&self) -> Value {
+        let mut languages: Map<String, Value> = Map::new();
+        let mut total: LineCounts = LineCounts::default();
+        let mut file_count: usize = 0;
+        for (language, &files) in &self.filetype_table {
+            let counts: LineCounts = self
+                .language_line_counts
+                .get(language)
+                .copied()
+                .unwrap_or_default();
+            total += counts;
+            file_count += files;
+            languages.insert(
+                language.clone(),
+                json!({
+                    "nFiles": files,
+                    "blank": counts.blank,
+                    "comment": counts.comment,
+                    "code": counts.code,
+                }),
+            );
+        }
+
+        let mut cloc: Map<String, Value> = Map::new();
+        cloc.insert(
+            "header".to_string(),
+            json!({
+                "n_files": file_count,
+                "n_lines": total.blank + total.comment + total.code,
+            }),
+        );
+        cloc.append(&mut languages);
+        cloc.insert(
+            "SUM".to_string(),
+            json!({
+                "blank": total.blank,
+                "comment": total.comment,
+                "code": total.code,
+                "nFiles": file_count,
+            }),
+        );
+        Value::Object(cloc)
+    }
+}
@@ -0,0 +1,1186 @@
+/*
+ *  config.rs - Per-directory configuration files and their inheritance
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fmt,
+    io::ErrorKind,
+    num::NonZero,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use ignore::{
+    Match,
+    gitignore::{Gitignore, GitignoreBuilder},
+};
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::{
+    jobs::JOBS_ENV_VAR,
+    logger::Logger,
+    matching::{Matching, MatchingOverride},
+    report::OutputFormat,
+    severity::{Escalation, Severity},
+    text,
+};
+
+/// Recognized config file names, in order of preference when a directory
+/// has more than one
+pub const CONFIG_FILE_NAMES: [&str; 2] = ["pursue.toml", ".pursue.toml"];
+
+/// Files of gitignore style patterns a directory may hold, read in this
+/// order so `.pursueignore` can re-include what `.ignore` leaves out
+pub const IGNORE_FILE_NAMES: [&str; 2] = [".ignore", ".pursueignore"];
+
+/// Every key a config file may contain
+const KNOWN_KEYS: [&str; 10] = [
+    "keywords",
+    "exclude",
+    "budgets",
+    "format",
+    "default_ignores",
+    "jobs",
+    "languages",
+    "severities",
+    "matching",
+    "escalation",
+];
+
+/// Build output and dependency directories skipped by default in any
+/// directory holding the matching manifest, and git's own directory
+const DEFAULT_IGNORES: [(&str, &[&str]); 11] = [
+    (".git", &[".git/"]),
+    ("Cargo.toml", &["target/"]),
+    ("package.json", &["node_modules/", "dist/"]),
+    ("pyproject.toml", &[".venv/", "__pycache__/"]),
+    ("setup.py", &[".venv/", "__pycache__/"]),
+    ("requirements.txt", &[".venv/", "__pycache__/"]),
+    ("pom.xml", &["build/", ".gradle/"]),
+    ("build.gradle", &["build/", ".gradle/"]),
+    ("build.gradle.kts", &["build/", ".gradle/"]),
+    ("settings.gradle", &["build/", ".gradle/"]),
+    ("settings.gradle.kts", &["build/", ".gradle/"]),
+];
+
+pub const KEYWORDS_ENV_VAR: &str = "PURSUE_KEYWORDS";
+pub const FORMAT_ENV_VAR: &str = "PURSUE_FORMAT";
+
+/// Where a configuration value came from
+#[derive(Clone, Debug)]
+pub enum Source {
+    Default,
+    Environment(&'static str),
+    CommandLine(&'static str),
+    File(PathBuf),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::Environment(variable) => write!(f, "environment (${})", variable),
+            Source::CommandLine(flag) => write!(f, "command line ({})", flag),
+            Source::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// A configuration value along with the layer that set it
+#[derive(Clone, Debug)]
+pub struct Layered<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+/// Settings that apply to the whole run, resolved from the defaults, the
+/// environment, and the command line, in increasing order of precedence
+pub struct Settings {
+    pub keywords: Layered<Vec<String>>,
+    pub format: Layered<OutputFormat>,
+    /// Thread count set by `$PURSUE_JOBS` or a config file
+    pub jobs: Option<Layered<NonZero<usize>>>,
+    /// Languages file set by a config file
+    pub languages: Option<Layered<PathBuf>>,
+    /// Keywords config files give a severity other than their default
+    pub severities: BTreeMap<String, Layered<Severity>>,
+    /// Keywords config files match differently than the command line says
+    pub matching: BTreeMap<String, Layered<MatchingOverride>>,
+    /// Keywords config files raise the severity of once they get old
+    pub escalation: BTreeMap<String, Layered<Escalation>>,
+}
+
+impl Settings {
+    pub fn resolve(
+        cli_keywords: &str,
+        cli_format: Option<OutputFormat>,
+    ) -> Result<Self, std::io::Error> {
+        Self::resolve_with(cli_keywords, cli_format, |variable| {
+            std::env::var(variable).ok()
+        })
+    }
+
+    /// `resolve` with the environment variables `env` returns
+    fn resolve_with(
+        cli_keywords: &str,
+        cli_format: Option<OutputFormat>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, std::io::Error> {
+        let keywords: Layered<Vec<String>> = match (cli_keywords.is_empty(), env(KEYWORDS_ENV_VAR))
+        {
+            (false, _) => Layered {
+                value: split_keywords(cli_keywords),
+                source: Source::CommandLine("--keywords"),
+            },
+            (true, Some(env_keywords)) if !env_keywords.trim().is_empty() => Layered {
+                value: split_keywords(&env_keywords),
+                source: Source::Environment(KEYWORDS_ENV_VAR),
+            },
+            (true, _) => Layered {
+                value: Logger::KEY_COMMENTS.iter().map(|k| k.to_string()).collect(),
+                source: Source::Default,
+            },
+        };
+
+        let format: Layered<OutputFormat> = match (cli_format, env(FORMAT_ENV_VAR)) {
+            (Some(format), _) => Layered {
+                value: format,
+                source: Source::CommandLine("--format"),
+            },
+            (None, Some(env_format)) if !env_format.trim().is_empty() => Layered {
+                value: env_format.trim().parse().map_err(|error: String| {
+                    std::io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("${}: {}", FORMAT_ENV_VAR, error),
+                    )
+                })?,
+                source: Source::Environment(FORMAT_ENV_VAR),
+            },
+            (None, _) => Layered {
+                value: OutputFormat::default(),
+                source: Source::Default,
+            },
+        };
+
+        let jobs: Option<Layered<NonZero<usize>>> = match env(JOBS_ENV_VAR) {
+            Some(env_jobs) if !env_jobs.trim().is_empty() => Some(Layered {
+                value: env_jobs
+                    .trim()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(NonZero::new)
+                    .ok_or_else(|| {
+                        std::io::Error::new(
+                            ErrorKind::InvalidInput,
+                            format!(
+                                "${} must be a whole number of at least 1, not {:?}",
+                                JOBS_ENV_VAR, env_jobs
+                            ),
+                        )
+                    })?,
+                source: Source::Environment(JOBS_ENV_VAR),
+            }),
+            _ => None,
+        };
+
+        Ok(Self {
+            keywords,
+            format,
+            jobs,
+            languages: None,
+            severities: BTreeMap::new(),
+            matching: BTreeMap::new(),
+            escalation: BTreeMap::new(),
+        })
+    }
+
+    /// Layer the output format, thread count, languages file, and keyword
+    /// severities, matching, and escalation of the config files applying to
+    /// a scan of `root` over the defaults, the one closest to `root` winning. The environment and
+    /// command line still take precedence over the output format and
+    /// thread count
+    pub fn with_config_files(mut self, root: &Path) -> Result<Self, std::io::Error> {
+        let config_paths = outer_configs(root)
+            .into_iter()
+            .map(|(_, config_path)| config_path)
+            .chain(find_config(root));
+
+        let format_overridden: bool = !matches!(self.format.source, Source::Default);
+        let jobs_overridden: bool = self.jobs.is_some();
+        for config_path in config_paths {
+            let config: ConfigFile = ConfigFile::load(&config_path)?;
+            if let Some(format) = config.format
+                && !format_overridden
+            {
+                self.format = Layered {
+                    // Checked when the file was loaded
+                    value: format.parse().unwrap_or_default(),
+                    source: Source::File(config_path.clone()),
+                };
+            }
+            if let Some(jobs) = config.jobs.and_then(NonZero::new)
+                && !jobs_overridden
+            {
+                self.jobs = Some(Layered {
+                    value: jobs,
+                    source: Source::File(config_path.clone()),
+                });
+            }
+            if let Some(languages) = config.languages {
+                let directory: &Path = config_path.parent().unwrap_or(root);
+                self.languages = Some(Layered {
+                    value: directory.join(languages),
+                    source: Source::File(config_path.clone()),
+                });
+            }
+            for (keyword, severity) in config.severities {
+                self.severities.insert(
+                    text::nfc(&keyword).into_owned(),
+                    Layered {
+                        // Checked when the file was loaded
+                        value: severity.parse().unwrap_or_default(),
+                        source: Source::File(config_path.clone()),
+                    },
+                );
+            }
+            for (keyword, changes) in config.matching {
+                let keyword: String = text::nfc(&keyword).into_owned();
+                let inherited: MatchingOverride = self
+                    .matching
+                    .get(&keyword)
+                    .map_or_else(MatchingOverride::default, |matching| matching.value);
+                self.matching.insert(
+                    keyword,
+                    Layered {
+                        value: inherited.and(changes),
+                        source: Source::File(config_path.clone()),
+                    },
+                );
+            }
+            for (keyword, rule) in config.escalation {
+                self.escalation.insert(
+                    text::nfc(&keyword).into_owned(),
+                    Layered {
+                        value: rule,
+                        source: Source::File(config_path.clone()),
+                    },
+                );
+            }
+        }
+        Ok(self)
+    }
+
+    /// Severity of every keyword mapped to one other than its default
+    pub fn keyword_severities(&self) -> BTreeMap<String, Severity> {
+        self.severities
+            .iter()
+            .map(|(keyword, severity)| (keyword.clone(), severity.value))
+            .collect()
+    }
+
+    /// Escalation rule of every keyword a config file gives one
+    pub fn escalation_rules(&self) -> BTreeMap<String, Escalation> {
+        self.escalation
+            .iter()
+            .map(|(keyword, rule)| (keyword.clone(), rule.value))
+            .collect()
+    }
+
+    /// How every keyword a config file says something about is matched,
+    /// the rest being matched as `default`
+    pub fn keyword_matching(&self, default: Matching) -> BTreeMap<String, Matching> {
+        self.matching
+            .iter()
+            .map(|(keyword, changes)| (keyword.clone(), default.with(changes.value)))
+            .collect()
+    }
+
+    /// Render the settings in effect for `directory` when scanning from
+    /// `root` as TOML, with every value annotated with its source
+    pub fn render_effective(
+        &self,
+        root: &Path,
+        directory: &Path,
+    ) -> Result<String, std::io::Error> {
+        let mut keywords: Vec<(String, Source)> = self
+            .keywords
+            .value
+            .iter()
+            .map(|keyword| (keyword.clone(), self.keywords.source.clone()))
+            .collect();
+        let mut excludes: Vec<(String, Source)> = Vec::new();
+        let mut budgets: Vec<(String, String, usize, Source)> = Vec::new();
+        let mut severities: BTreeMap<String, Layered<Severity>> = self.severities.clone();
+
+        for (config_directory, config_path, config) in config_chain(root, directory)? {
+            for (path, limits) in config.budgets {
+                let budget_directory: PathBuf = config_directory.join(path.trim_matches('/'));
+                let shown: String = budget_directory
+                    .strip_prefix(root)
+                    .unwrap_or(&budget_directory)
+                    .display()
+                    .to_string();
+                for (keyword, max) in limits {
+                    budgets.push((
+                        shown.clone(),
+                        keyword,
+                        max,
+                        Source::File(config_path.clone()),
+                    ));
+                }
+            }
+            for keyword in config.keywords {
+                if !keywords.iter().any(|(existing, _)| *existing == keyword) {
+                    keywords.push((keyword, Source::File(config_path.clone())));
+                }
+            }
+            for pattern in config.exclude {
+                excludes.push((pattern, Source::File(config_path.clone())));
+            }
+            for (keyword, severity) in config.severities {
+                severities.insert(
+                    text::nfc(&keyword).into_owned(),
+                    Layered {
+                        // Checked when the file was loaded
+                        value: severity.parse().unwrap_or_default(),
+                        source: Source::File(config_path.clone()),
+                    },
+                );
+            }
+        }
+
+        let quote = |value: &str| toml::Value::String(value.to_string()).to_string();
+        let mut rendered: String =
+            format!("# Effective configuration for {}\n", directory.display());
+        rendered += &format!(
+            "{: <40} # {}\n",
+            format!("format = {}", quote(&self.format.value.to_string())),
+            self.format.source
+        );
+        if let Some(jobs) = &self.jobs {
+            rendered += &format!(
+                "{: <40} # {}\n",
+                format!("jobs = {}", jobs.value),
+                jobs.source
+            );
+        }
+        if let Some(languages) = &self.languages {
+            rendered += &format!(
+                "{: <40} # {}\n",
+                format!(
+                    "languages = {}",
+                    quote(&languages.value.display().to_string())
+                ),
+                languages.source
+            );
+        }
+        for (key, values) in [("keywords", &keywords), ("exclude", &excludes)] {
+            rendered += &format!("{} = [\n", key);
+            for (value, source) in values {
+                rendered += &format!(
+                    "{} # {}\n",
+                    text::pad(&format!("    {},", quote(value)), 40),
+                    source
+                );
+            }
+            rendered += "]\n";
+        }
+        for (keyword, severity) in &severities {
+            rendered += &format!(
+                "{: <40} # {}\n",
+                format!(
+                    "severities.{} = {}",
+                    quote(keyword),
+                    quote(&severity.value.to_string())
+                ),
+                severity.source
+            );
+        }
+        for (keyword, changes) in &self.matching {
+            for (key, value) in [
+                ("ignore_case", changes.value.ignore_case),
+                ("whole_word", changes.value.whole_word),
+            ] {
+                if let Some(value) = value {
+                    rendered += &format!(
+                        "{: <40} # {}\n",
+                        format!("matching.{}.{} = {}", quote(keyword), key, value),
+                        changes.source
+                    );
+                }
+            }
+        }
+        for (keyword, rule) in &self.escalation {
+            for (key, value) in [
+                ("older_than_days", rule.value.older_than_days.to_string()),
+                ("severity", quote(&rule.value.severity.to_string())),
+            ] {
+                rendered += &format!(
+                    "{: <40} # {}\n",
+                    format!("escalation.{}.{} = {}", quote(keyword), key, value),
+                    rule.source
+                );
+            }
+        }
+        for (path, keyword, max, source) in budgets {
+            let path: &str = match path.is_empty() {
+                true => ".",
+                false => &path,
+            };
+            rendered += &format!(
+                "{: <40} # {}\n",
+                format!("budgets.{}.{} = {}", quote(path), quote(&keyword), max),
+                source
+            );
+        }
+
+        Ok(rendered)
+    }
+}
+
+/// Answers to the questions asked before the first scan of a directory
+pub struct InitialConfig {
+    pub default_ignores: bool,
+    pub keywords: Vec<String>,
+    pub format: OutputFormat,
+}
+
+/// Write `.pursue.toml` into `directory` from `initial`, refusing to replace
+/// an existing file
+pub fn write_initial(directory: &Path, initial: &InitialConfig) -> Result<PathBuf, std::io::Error> {
+    let quote = |value: &str| toml::Value::String(value.to_string()).to_string();
+    let keywords: Vec<String> = initial
+        .keywords
+        .iter()
+        .map(|keyword| quote(keyword))
+        .collect();
+
+    let contents: String = format!(
+        "# Created by pursue, see `pursue config check` after editing\n\
+         \n\
+         # Skip build output and dependency directories like target/ and node_modules/\n\
+         default_ignores = {}\n\
+         \n\
+         # Tracked on top of {}\n\
+         keywords = [{}]\n\
+         \n\
+         # table, json, markdown, csv, tokei-json, cloc-json, html, jsonl, or sarif\n\
+         format = {}\n\
+         \n\
+         # Gitignore style patterns, relative to this directory\n\
+         exclude = []\n",
+        initial.default_ignores,
+        Logger::KEY_COMMENTS.join(","),
+        keywords.join(", "),
+        quote(&initial.format.to_string())
+    );
+
+    let config_path: PathBuf = directory.join(CONFIG_FILE_NAMES[1]);
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&config_path)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, contents.as_bytes()))?;
+    Ok(config_path)
+}
+
+pub fn split_keywords(keywords: &str) -> Vec<String> {
+    keywords
+        .split(',')
+        .map(str::trim)
+        .filter(|keyword| !keyword.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// The config file `directory` contributes, if it has one
+pub fn find_config(directory: &Path) -> Option<PathBuf> {
+    CONFIG_FILE_NAMES
+        .iter()
+        .map(|name| directory.join(name))
+        .find(|path| path.is_file())
+}
+
+/// The user's own config file, applying to every scan:
+/// `$XDG_CONFIG_HOME/pursue/config.toml` or the platform's equivalent
+pub fn user_config() -> Option<PathBuf> {
+    let base: PathBuf = match (
+        std::env::var_os("XDG_CONFIG_HOME"),
+        std::env::var_os("HOME"),
+        std::env::var_os("APPDATA"),
+    ) {
+        (Some(config), _, _) if !config.is_empty() => PathBuf::from(config),
+        (_, Some(home), _) if !home.is_empty() => PathBuf::from(home).join(".config"),
+        (_, _, Some(roaming)) if !roaming.is_empty() => PathBuf::from(roaming),
+        _ => return None,
+    };
+    Some(base.join("pursue").join("config.toml")).filter(|path| path.is_file())
+}
+
+/// Config files applying to a scan of `root` from outside of it, least
+/// specific first: the user's, then those of the directories above `root`
+/// from the outermost down. Each comes after the directory its patterns
+/// and budgets are relative to, which is `root` for the user's
+pub fn outer_configs(root: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let mut configs: Vec<(PathBuf, PathBuf)> = root
+        .ancestors()
+        .skip(1)
+        .filter_map(|directory| {
+            find_config(directory).map(|config_path| (directory.to_path_buf(), config_path))
+        })
+        .collect();
+    configs.reverse();
+    if let Some(config_path) = user_config() {
+        configs.insert(0, (root.to_path_buf(), config_path));
+    }
+    configs
+}
+
+/// Every config file that applies to `directory` when scanning from `root`,
+/// outermost first, each after the directory it applies to
+pub fn config_chain(
+    root: &Path,
+    directory: &Path,
+) -> Result<Vec<(PathBuf, PathBuf, ConfigFile)>, std::io::Error> {
+    let relative: &Path = directory.strip_prefix(root).map_err(|_| {
+        std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("{:?} is not inside {:?}", directory, root),
+        )
+    })?;
+
+    let mut chain: Vec<(PathBuf, PathBuf, ConfigFile)> = Vec::new();
+    for (config_directory, config_path) in outer_configs(root) {
+        let config: ConfigFile = ConfigFile::load(&config_path)?;
+        chain.push((config_directory, config_path, config));
+    }
+
+    let mut current: PathBuf = root.to_path_buf();
+    for component in std::iter::once(None).chain(relative.components().map(Some)) {
+        if let Some(component) = component {
+            current.push(component);
+        }
+        if let Some(config_path) = find_config(&current) {
+            let config: ConfigFile = ConfigFile::load(&config_path)?;
+            chain.push((current.clone(), config_path, config));
+        }
+    }
+
+    Ok(chain)
+}
+
+/// Contents of a single `pursue.toml` or `.pursue.toml`
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct ConfigFile {
+    /// Extra keywords to track in this subtree, on top of the inherited ones
+    pub keywords: Vec<String>,
+    /// Gitignore style patterns, relative to the directory of the file
+    pub exclude: Vec<String>,
+    /// Most occurrences of each keyword allowed under a path relative to the
+    /// directory of the file, e.g. `[budgets."payments/"] FIXME = 10`
+    pub budgets: BTreeMap<String, BTreeMap<String, usize>>,
+    /// Output format when the file is in the scan root and neither the
+    /// environment nor the command line picks one
+    pub format: Option<String>,
+    /// `false` turns off `DEFAULT_IGNORES` for this directory and everything
+    /// below it
+    pub default_ignores: Option<bool>,
+    /// Worker threads to scan with when the file applies to the scan root
+    /// and neither the environment nor the command line picks a count
+    pub jobs: Option<usize>,
+    /// Languages file, relative to the directory of the file, used when the
+    /// file applies to the scan root and `--languages` isn't given
+    pub languages: Option<PathBuf>,
+    /// Severity of keywords, e.g. `FIXME = "error"`, in the directory of the
+    /// file and everything below it
+    pub severities: BTreeMap<String, String>,
+    /// How keywords are matched when the file applies to the scan root, e.g.
+    /// `[matching.TODO] ignore_case = true`, over `--ignore-case` and
+    /// `--whole-word`
+    pub matching: BTreeMap<String, MatchingOverride>,
+    /// Severity keywords are raised to once their line is old enough, e.g.
+    /// `[escalation.TODO] older_than_days = 365` and `severity = "error"`,
+    /// when the file applies to the scan root
+    pub escalation: BTreeMap<String, Escalation>,
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> Result<Self, std::io::Error> {
+        let contents: String = std::fs::read_to_string(path)?;
+        Self::parse(path, &contents)
+            .map_err(|problems| std::io::Error::new(ErrorKind::InvalidData, problems.join("\n")))
+    }
+
+    /// Validate `contents` against the schema, collecting every problem
+    /// found as a `path:line:column: message` string
+    fn parse(path: &Path, contents: &str) -> Result<Self, Vec<String>> {
+        let describe = |offset: usize, message: &str| -> String {
+            let (line, column) = line_column(contents, offset);
+            format!("{}:{}:{}: {}", path.display(), line, column, message)
+        };
+        let describe_toml_error = |error: toml::de::Error| -> String {
+            describe(
+                error.span().map_or(0, |span| span.start),
+                error.message().trim_end(),
+            )
+        };
+
+        let table: BTreeMap<Spanned<String>, toml::Value> =
+            toml::from_str(contents).map_err(|error| vec![describe_toml_error(error)])?;
+
+        let mut unknown_keys: Vec<&Spanned<String>> = table
+            .keys()
+            .filter(|key| !KNOWN_KEYS.contains(&key.get_ref().as_str()))
+            .collect();
+        unknown_keys.sort_by_key(|key| key.span().start);
+
+        let mut problems: Vec<String> = Vec::new();
+        for key in unknown_keys {
+            let message: String = match suggest(key.get_ref(), &KNOWN_KEYS) {
+                Some(suggestion) => format!(
+                    "unknown key `{}`, did you mean `{}`?",
+                    key.get_ref(),
+                    suggestion
+                ),
+                None => format!(
+                    "unknown key `{}`, expected one of: {}",
+                    key.get_ref(),
+                    KNOWN_KEYS.join(", ")
+                ),
+            };
+            problems.push(describe(key.span().start, &message));
+        }
+
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+
+        let config: ConfigFile =
+            toml::from_str(contents).map_err(|error| vec![describe_toml_error(error)])?;
+
+        if config
+            .keywords
+            .iter()
+            .any(|keyword| keyword.trim().is_empty())
+        {
+            let offset: usize = table
+                .keys()
+                .find(|key| key.get_ref() == "keywords")
+                .map_or(0, |key| key.span().start);
+            problems.push(describe(offset, "keywords must not be empty strings"));
+        }
+
+        if config.jobs == Some(0) {
+            let offset: usize = table
+                .keys()
+                .find(|key| key.get_ref() == "jobs")
+                .map_or(0, |key| key.span().start);
+            problems.push(describe(offset, "jobs must be at least 1"));
+        }
+
+        for severity in config.severities.values() {
+            if let Err(error) = Severity::from_str(severity) {
+                let offset: usize = table
+                    .keys()
+                    .find(|key| key.get_ref() == "severities")
+                    .map_or(0, |key| key.span().start);
+                problems.push(describe(offset, &error));
+            }
+        }
+
+        if let Some(Err(error)) = config.format.as_deref().map(OutputFormat::from_str) {
+            let offset: usize = table
+                .keys()
+                .find(|key| key.get_ref() == "format")
+                .map_or(0, |key| key.span().start);
+            problems.push(describe(offset, &error));
+        }
+
+        match problems.is_empty() {
+            true => Ok(config),
+            false => Err(problems),
+        }
+    }
+}
+
+/// 1-based line and column of a byte offset into `contents`
+fn line_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before: &str = &contents[..offset.min(contents.len())];
+    let line: usize = before.matches('\n').count() + 1;
+    let column: usize = before
+        .rfind('\n')
+        .map_or(before.chars().count(), |newline| {
+            before[newline + 1..].chars().count()
+        })
+        + 1;
+    (line, column)
+}
+
+/// The closest candidate to a misspelled `word`, if any is close enough to
+/// plausibly be what was meant
+fn suggest<'a>(word: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(word, candidate), *candidate))
+        .filter(|(distance, candidate)| *distance <= candidate.len().div_ceil(3))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+
+    for (i, left_char) in left.chars().enumerate() {
+        let mut current: Vec<usize> = vec![i + 1; right.len() + 1];
+        for (j, right_char) in right.iter().enumerate() {
+            let substitution: usize = previous[j] + usize::from(left_char != *right_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[right.len()]
+}
+
+/// Validate every config file applying to a scan of `root` without
+/// scanning anything, returning the checked files along with any problems
+/// found in them
+pub fn check_tree(root: &Path) -> Result<Vec<(PathBuf, Vec<String>)>, std::io::Error> {
+    let mut results: Vec<(PathBuf, Vec<String>)> = Vec::new();
+    for (directory, config_path) in outer_configs(&root.canonicalize()?) {
+        results.push(check_file(&directory, config_path)?);
+    }
+    check_directory(root, &mut results)?;
+    Ok(results)
+}
+
+/// Problems found in `config_path`, which applies to `directory`
+fn check_file(
+    directory: &Path,
+    config_path: PathBuf,
+) -> Result<(PathBuf, Vec<String>), std::io::Error> {
+    let contents: String = std::fs::read_to_string(&config_path)?;
+    let problems: Vec<String> = match ConfigFile::parse(&config_path, &contents) {
+        Ok(config) => config
+            .exclude
+            .iter()
+            .filter_map(|pattern| {
+                GitignoreBuilder::new(directory)
+                    .add_line(None, pattern)
+                    .err()
+                    .map(|error| format!("{}: {}", config_path.display(), error))
+            })
+            .collect(),
+        Err(problems) => problems,
+    };
+    Ok((config_path, problems))
+}
+
+fn check_directory(
+    directory: &Path,
+    results: &mut Vec<(PathBuf, Vec<String>)>,
+) -> Result<(), std::io::Error> {
+    for name in CONFIG_FILE_NAMES {
+        let config_path: PathBuf = directory.join(name);
+        if config_path.is_file() {
+            results.push(check_file(directory, config_path)?);
+        }
+    }
+
+    for entry in directory.read_dir()? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            check_directory(&entry.path(), results)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Limit on how often a keyword may appear under a directory
+#[derive(Clone, Debug)]
+pub struct Budget {
+    pub directory: PathBuf,
+    pub keyword: String,
+    pub max: usize,
+    /// Config file the budget was declared in
+    pub source: PathBuf,
+}
+
+/// Settings in effect for one directory, built from every config file
+/// between the scan root and that directory. Like `.gitignore`, the closest
+/// file wins when two of them disagree about an exclude pattern.
+pub struct Scope {
+    parent: Option<Arc<Scope>>,
+    keywords: Vec<Arc<str>>,
+    /// Severities of keywords here, those of the closest config file winning
+    severities: BTreeMap<String, Severity>,
+    excludes: Option<Gitignore>,
+    /// Budgets declared by this directory's own config file
+    budgets: Vec<Budget>,
+    /// Whether manifests turn on the excludes in `DEFAULT_IGNORES`
+    default_ignores: bool,
+}
+
+/// Default exclude patterns for the manifests found in `directory`
+/// along with the manifest that turned each one on
+fn default_ignores(directory: &Path) -> Vec<(PathBuf, &'static str)> {
+    let mut patterns: Vec<(PathBuf, &'static str)> = Vec::new();
+    for (manifest, manifest_patterns) in DEFAULT_IGNORES {
+        let manifest: PathBuf = directory.join(manifest);
+        if manifest.exists() {
+            for pattern in manifest_patterns {
+                if !patterns.iter().any(|(_, existing)| existing == pattern) {
+                    patterns.push((manifest.clone(), pattern));
+                }
+            }
+        }
+    }
+    patterns
+}
+
+/// The exclude pattern that decided whether a path is profiled
+#[derive(Clone, Debug)]
+pub struct Exclusion {
+    pub pattern: String,
+    /// Config file or manifest the pattern came from
+    pub source: PathBuf,
+    /// False when the pattern re-includes the path with `!`
+    pub excluded: bool,
+}
+
+impl Scope {
+    /// Scope every other one descends from, made of the config files
+    /// applying to `root` from outside of it. `excludes` are gitignore style
+    /// patterns relative to `root`, given with `--exclude`, which apply on
+    /// top of those files
+    pub fn root(
+        keywords: &[Arc<str>],
+        severities: &BTreeMap<String, Severity>,
+        default_ignores: bool,
+        root: &Path,
+        excludes: &[String],
+    ) -> Result<Arc<Self>, std::io::Error> {
+        let invalid = |error: ignore::Error| {
+            std::io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid --exclude pattern: {}", error),
+            )
+        };
+
+        let mut scope: Arc<Self> = Arc::new(Self {
+            parent: None,
+            keywords: keywords.to_vec(),
+            severities: severities.clone(),
+            excludes: None,
+            budgets: Vec::new(),
+            default_ignores,
+        });
+        for (directory, config_path) in outer_configs(root) {
+            scope = scope.layer(&directory, Some(config_path), false)?;
+        }
+
+        let excludes: Gitignore = match excludes.is_empty() {
+            true => return Ok(scope),
+            false => {
+                let mut builder: GitignoreBuilder = GitignoreBuilder::new(root);
+                for pattern in excludes {
+                    builder
+                        .add_line(Some(PathBuf::from("--exclude")), pattern)
+                        .map_err(invalid)?;
+                }
+                builder.build().map_err(invalid)?
+            }
+        };
+
+        Ok(Arc::new(Self {
+            parent: Some(Arc::clone(&scope)),
+            keywords: scope.keywords.clone(),
+            severities: scope.severities.clone(),
+            excludes: Some(excludes),
+            budgets: Vec::new(),
+            default_ignores: scope.default_ignores,
+        }))
+    }
+
+    /// Scope of no directory at all, for source that isn't on disk
+    pub fn detached(keywords: &[Arc<str>], severities: &BTreeMap<String, Severity>) -> Self {
+        Self {
+            parent: None,
+            keywords: keywords.to_vec(),
+            severities: severities.clone(),
+            excludes: None,
+            budgets: Vec::new(),
+            default_ignores: false,
+        }
+    }
+
+    /// Scope for `directory`, a child of the directory this scope belongs to.
+    /// Shares `self` when the directory has no config file, no ignore file,
+    /// and no manifest with default excludes.
+    pub fn descend(self: &Arc<Self>, directory: &Path) -> Result<Arc<Self>, std::io::Error> {
+        self.layer(directory, find_config(directory), true)
+    }
+
+    /// Scope with `config_path` applied to `directory` on top of this one.
+    /// A directory being `walked` also brings its ignore files and the
+    /// default excludes of its manifests
+    fn layer(
+        self: &Arc<Self>,
+        directory: &Path,
+        config_path: Option<PathBuf>,
+        walked: bool,
+    ) -> Result<Arc<Self>, std::io::Error> {
+        let config: ConfigFile = match &config_path {
+            Some(config_path) => ConfigFile::load(config_path)?,
+            None => ConfigFile::default(),
+        };
+
+        let default_ignores_enabled: bool =
+            self.default_ignores && config.default_ignores.unwrap_or(true);
+        let defaults: Vec<(PathBuf, &str)> = match default_ignores_enabled && walked {
+            true => default_ignores(directory),
+            false => Vec::new(),
+        };
+
+        let ignore_files: Vec<PathBuf> = match walked {
+            true => IGNORE_FILE_NAMES
+                .iter()
+                .map(|name| directory.join(name))
+                .filter(|path| path.is_file())
+                .collect(),
+            false => Vec::new(),
+        };
+
+        if config_path.is_none() && defaults.is_empty() && ignore_files.is_empty() {
+            return Ok(Arc::clone(self));
+        }
+
+        let config_path: PathBuf = config_path.unwrap_or_else(|| directory.to_path_buf());
+
+        let mut keywords: Vec<Arc<str>> = self.keywords.clone();
+        for keyword in config.keywords {
+            let keyword: Cow<str> = text::nfc(&keyword);
+            if !keywords.iter().any(|existing| **existing == *keyword) {
+                keywords.push(keyword.into());
+            }
+        }
+
+        let mut severities: BTreeMap<String, Severity> = self.severities.clone();
+        for (keyword, severity) in &config.severities {
+            // Checked when the file was loaded
+            severities.insert(
+                text::nfc(keyword).into_owned(),
+                severity.parse().unwrap_or_default(),
+            );
+        }
+
+        // Defaults come first, then ignore files, then the config file, so
+        // each can re-include what the ones before it exclude with `!`
+        let excludes: Option<Gitignore> = match defaults.is_empty()
+            && ignore_files.is_empty()
+            && config.exclude.is_empty()
+        {
+            true => None,
+            false => {
+                let mut builder: GitignoreBuilder = GitignoreBuilder::new(directory);
+                for (source, pattern) in &defaults {
+                    builder
+                        .add_line(Some(source.clone()), pattern)
+                        .map_err(|error| {
+                            std::io::Error::new(ErrorKind::InvalidData, error.to_string())
+                        })?;
+                }
+                for ignore_file in &ignore_files {
+                    if let Some(error) = builder.add(ignore_file) {
+                        return Err(std::io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("Invalid exclude pattern in {:?}: {}", ignore_file, error),
+                        ));
+                    }
+                }
+                for pattern in &config.exclude {
+                    builder
+                        .add_line(Some(config_path.clone()), pattern)
+                        .map_err(|error| {
+                            std::io::Error::new(
+                                ErrorKind::InvalidData,
+                                format!("Invalid exclude pattern in {:?}: {}", config_path, error),
+                            )
+                        })?;
+                }
+                Some(builder.build().map_err(|error| {
+                    std::io::Error::new(ErrorKind::InvalidData, error.to_string())
+                })?)
+            }
+        };
+
+        let mut budgets: Vec<Budget> = Vec::new();
+        for (path, limits) in config.budgets {
+            let budget_directory: PathBuf = match path.trim_matches('/') {
+                "" | "." => directory.to_path_buf(),
+                relative => directory.join(relative),
+            };
+            for (keyword, max) in limits {
+                budgets.push(Budget {
+                    directory: budget_directory.clone(),
+                    keyword,
+                    max,
+                    source: config_path.clone(),
+                });
+            }
+        }
+
+        Ok(Arc::new(Self {
+            parent: Some(Arc::clone(self)),
+            keywords,
+            severities,
+            excludes,
+            budgets,
+            default_ignores: default_ignores_enabled,
+        }))
+    }
+
+    pub fn keywords(&self) -> &[Arc<str>] {
+        &self.keywords
+    }
+
+    /// Severity of every keyword mapped to one other than its default here
+    pub fn severities(&self) -> &BTreeMap<String, Severity> {
+        &self.severities
+    }
+
+    pub fn budgets(&self) -> &[Budget] {
+        &self.budgets
+    }
+
+    /// Budgets of this scope and every one it descends from
+    pub fn inherited_budgets(&self) -> Vec<Budget> {
+        let mut budgets: Vec<Budget> = Vec::new();
+        let mut scope: Option<&Scope> = Some(self);
+        while let Some(current) = scope {
+            budgets.extend(current.budgets.iter().cloned());
+            scope = current.parent.as_deref();
+        }
+        budgets
+    }
+
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        self.exclusion(path, is_dir)
+            .is_some_and(|exclusion| exclusion.excluded)
+    }
+
+    /// The pattern that decides whether `path` is skipped, if any matches
+    pub fn exclusion(&self, path: &Path, is_dir: bool) -> Option<Exclusion> {
+        let mut scope: Option<&Scope> = Some(self);
+
+        while let Some(current) = scope {
+            if let Some(excludes) = &current.excludes {
+                let (glob, excluded) = match excludes.matched(path, is_dir) {
+                    Match::Ignore(glob) => (glob, true),
+                    Match::Whitelist(glob) => (glob, false),
+                    Match::None => {
+                        scope = current.parent.as_deref();
+                        continue;
+                    }
+                };
+                return Some(Exclusion {
+                    pattern: glob.original().to_string(),
+                    source: glob.from().map(Path::to_path_buf).unwrap_or_default(),
+                    excluded,
+                });
+            }
+            scope = current.parent.as_deref();
+        }
+
+        None
+    }
+
+    /// Directories with exclude patterns in effect, nearest first, along
+    /// with how many patterns each one has
+    pub fn exclude_lists(&self) -> Vec<(PathBuf, usize)> {
+        let mut lists: Vec<(PathBuf, usize)> = Vec::new();
+        let mut scope: Option<&Scope> = Some(self);
+        while let Some(current) = scope {
+            if let Some(excludes) = &current.excludes {
+                lists.push((
+                    excludes.path().to_path_buf(),
+                    excludes.num_ignores() as usize + excludes.num_whitelists() as usize,
+                ));
+            }
+            scope = current.parent.as_deref();
+        }
+        lists
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::Settings;
+
+    #[test]
+    fn effective_settings_show_the_environment() {
+        let root: PathBuf =
+            std::env::temp_dir().join(format!("pursue-config-{}-environment", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join(".pursue.toml"), "jobs = 2\n").unwrap();
+
+        let settings: Settings = Settings::resolve_with("", None, |variable| match variable {
+            "PURSUE_JOBS" => Some("3".to_string()),
+            "PURSUE_KEYWORDS" => Some("TODO,SAFETY".to_string()),
+            _ => None,
+        })
+        .unwrap()
+        .with_config_files(&root)
+        .unwrap();
+        let rendered: String = settings.render_effective(&root, &root).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let line = |start: &str| {
+            rendered
+                .lines()
+                .find(|line| line.trim_start().starts_with(start))
+                .map(|line| line.split_whitespace().collect::<Vec<&str>>().join(" "))
+        };
+        assert_eq!(
+            line("jobs").as_deref(),
+            Some("jobs = 3 # environment ($PURSUE_JOBS)")
+        );
+        assert_eq!(
+            line("\"SAFETY\"").as_deref(),
+            Some("\"SAFETY\", # environment ($PURSUE_KEYWORDS)")
+        );
+    }
+
+    #[test]
+    fn jobs_must_be_a_number() {
+        let error = Settings::resolve_with("", None, |variable| {
+            (variable == "PURSUE_JOBS").then(|| "0".to_string())
+        })
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("$PURSUE_JOBS"), "{}", error);
+    }
+}
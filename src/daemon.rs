@@ -0,0 +1,378 @@
+/*
+ *  daemon.rs - Keep a scan resident and answer queries about it on a socket
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    cache::ResultCache,
+    report::{Occurrence, Report},
+    scanner::Scanner,
+    severity::Severity,
+};
+
+/// A query sent to the daemon, one JSON object per line, named by its
+/// `query` field
+#[derive(Deserialize, Debug)]
+#[serde(tag = "query", rename_all = "kebab-case")]
+pub enum Request {
+    /// When the last scan finished and what it counted
+    Status,
+    /// Findings, only those in files under `path`, of `keyword`, or at
+    /// least as severe as `severity` when given, at most `limit` of them
+    Findings {
+        #[serde(default)]
+        path: Option<PathBuf>,
+        #[serde(default)]
+        keyword: Option<String>,
+        #[serde(default)]
+        severity: Option<Severity>,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// The report of one file, null when it wasn't scanned
+    File { path: PathBuf },
+    /// Scan now instead of at the next change
+    Rescan,
+    /// Stop the daemon once the answer is sent
+    Shutdown,
+}
+
+/// A finding along with the file it is in
+#[derive(Serialize)]
+struct Located<'a> {
+    file: &'a Path,
+    #[serde(flatten)]
+    occurrence: &'a Occurrence,
+}
+
+struct State {
+    /// Scans done so far, so clients can tell when results changed
+    generation: u64,
+    /// Seconds since the epoch when the last scan finished
+    scanned_at: u64,
+    report: Report,
+}
+
+/// A scan of `root` kept up to date, along with the reports of its files
+/// that let a rescan skip the ones that didn't change
+struct Daemon {
+    root: PathBuf,
+    scanner: Scanner,
+    cache: Mutex<ResultCache>,
+    state: RwLock<State>,
+    stopping: AtomicBool,
+}
+
+impl Daemon {
+    /// Scan again. Only one scan runs at a time, while queries keep being
+    /// answered from the last one
+    fn rescan(&self) -> Result<(), std::io::Error> {
+        let mut cache = self.cache.lock().unwrap();
+        let report: Report = self.scanner.scan_with(&mut cache)?;
+        let mut state = self.state.write().unwrap();
+        state.generation += 1;
+        state.scanned_at = now();
+        state.report = report;
+        Ok(())
+    }
+
+    /// `path` of a query, relative to the root unless it is absolute
+    fn resolve(&self, path: &Path) -> PathBuf {
+        let path: PathBuf = self.root.join(path);
+        path.canonicalize().unwrap_or(path)
+    }
+
+    fn status(&self) -> Value {
+        let state = self.state.read().unwrap();
+        json!({
+            "ok": true,
+            "generation": state.generation,
+            "scanned_at": state.scanned_at,
+            "root": self.root,
+            "files": state.report.files.len(),
+            "findings": state.report.occurrences().count(),
+            "keyword_table": state.report.keyword_table,
+        })
+    }
+
+    fn answer(&self, request: Request) -> Result<Value, std::io::Error> {
+        match request {
+            Request::Status => Ok(self.status()),
+            Request::Findings {
+                path,
+                keyword,
+                severity,
+                limit,
+            } => {
+                let path: Option<PathBuf> = path.map(|path| self.resolve(&path));
+                let state = self.state.read().unwrap();
+                let mut matching = state
+                    .report
+                    .occurrences()
+                    .filter(|(file, _)| path.as_ref().is_none_or(|path| file.starts_with(path)))
+                    .filter(|(_, occurrence)| {
+                        keyword
+                            .as_ref()
+                            .is_none_or(|keyword| occurrence.keyword == *keyword)
+                    })
+                    .filter(|(_, occurrence)| {
+                        severity.is_none_or(|severity| occurrence.severity >= severity)
+                    });
+                let findings: Vec<Located> = matching
+                    .by_ref()
+                    .take(limit.unwrap_or(usize::MAX))
+                    .map(|(file, occurrence)| Located { file, occurrence })
+                    .collect();
+                Ok(json!({
+                    "ok": true,
+                    "generation": state.generation,
+                    "findings": findings,
+                    "truncated": matching.next().is_some(),
+                }))
+            }
+            Request::File { path } => {
+                let path: PathBuf = self.resolve(&path);
+                let state = self.state.read().unwrap();
+                Ok(json!({
+                    "ok": true,
+                    "generation": state.generation,
+                    "file": state.report.files.iter().find(|file| file.path == path),
+                }))
+            }
+            Request::Rescan => {
+                self.rescan()?;
+                Ok(self.status())
+            }
+            Request::Shutdown => {
+                self.stopping.store(true, Ordering::SeqCst);
+                Ok(json!({ "ok": true }))
+            }
+        }
+    }
+
+    /// Answer every line `stream` sends until it hangs up
+    fn serve(&self, stream: UnixStream, socket: &Path) -> Result<(), std::io::Error> {
+        let mut writer: &UnixStream = &stream;
+        for line in BufReader::new(&stream).lines() {
+            let line: String = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let response: Value = match serde_json::from_str::<Request>(&line) {
+                Ok(request) => self.answer(request).unwrap_or_else(|error| failure(&error)),
+                Err(error) => failure(&error),
+            };
+            writeln!(writer, "{}", response)?;
+            writer.flush()?;
+
+            if self.stopping.load(Ordering::SeqCst) {
+                // Wakes the listener up so it sees it should stop
+                let _ = UnixStream::connect(socket);
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn failure(error: &dyn std::fmt::Display) -> Value {
+    json!({ "ok": false, "error": error.to_string() })
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// Scan `root` with `scanner`, then answer queries on `socket` until asked
+/// to shut down, scanning again whenever a check every `interval` finds
+/// something the scan walks changed. Files unchanged since the last scan aren't parsed again
+pub fn run(
+    scanner: Scanner,
+    root: &Path,
+    socket: &Path,
+    interval: Duration,
+) -> Result<(), std::io::Error> {
+    // A socket left behind by a daemon that was killed can be reused
+    if socket.exists() {
+        match UnixStream::connect(socket) {
+            Ok(_) => {
+                return Err(std::io::Error::new(
+                    ErrorKind::AddrInUse,
+                    format!("a daemon is already listening on {}", socket.display()),
+                ));
+            }
+            Err(_) => std::fs::remove_file(socket)?,
+        }
+    }
+
+    // Bound before the tree is first looked at, since the socket may be in
+    // it. Clients are answered once the first scan is done
+    let listener: UnixListener = UnixListener::bind(socket)?;
+    let mut cache: ResultCache = ResultCache::resident();
+    // A broken config file fails the scan too, so it is reported there
+    let mut stamp: u64 = scanner.tree_stamp().unwrap_or_default();
+    let report: Report = scanner.scan_with(&mut cache)?;
+    let daemon: Arc<Daemon> = Arc::new(Daemon {
+        root: root.to_path_buf(),
+        scanner,
+        cache: Mutex::new(cache),
+        state: RwLock::new(State {
+            generation: 1,
+            scanned_at: now(),
+            report,
+        }),
+        stopping: AtomicBool::new(false),
+    });
+
+    eprintln!("Listening on {}", socket.display());
+
+    {
+        let daemon: Arc<Daemon> = Arc::clone(&daemon);
+        thread::spawn(move || {
+            loop {
+                thread::sleep(interval);
+                let current: u64 = daemon.scanner.tree_stamp().unwrap_or_default();
+                if current == stamp {
+                    continue;
+                }
+
+                stamp = current;
+                if let Err(error) = daemon.rescan() {
+                    eprintln!("WARNING: Could not scan again: {}", error);
+                }
+            }
+        });
+    }
+
+    for stream in listener.incoming() {
+        if daemon.stopping.load(Ordering::SeqCst) {
+            break;
+        }
+        match stream {
+            Ok(stream) => {
+                let daemon: Arc<Daemon> = Arc::clone(&daemon);
+                let socket: PathBuf = socket.to_path_buf();
+                thread::spawn(move || {
+                    if let Err(error) = daemon.serve(stream, &socket) {
+                        eprintln!("WARNING: Could not answer query: {}", error);
+                    }
+                });
+            }
+            Err(error) => eprintln!("WARNING: Could not accept connection: {}", error),
+        }
+    }
+
+    std::fs::remove_file(socket)
+}
+
+/// Send one `request` line to the daemon listening on `socket` and return
+/// its answer
+pub fn query(socket: &Path, request: &str) -> Result<String, std::io::Error> {
+    let mut stream: UnixStream = UnixStream::connect(socket).map_err(|error| {
+        std::io::Error::new(
+            error.kind(),
+            format!("no daemon is listening on {}: {}", socket.display(), error),
+        )
+    })?;
+    writeln!(stream, "{}", request.trim())?;
+    stream.flush()?;
+
+    let mut answer: String = String::new();
+    BufReader::new(&stream).read_line(&mut answer)?;
+    Ok(answer.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::{Path, PathBuf},
+        thread,
+        time::Duration,
+    };
+
+    use serde_json::Value;
+
+    use super::{query, run};
+    use crate::{Scanner, ScannerBuilder};
+
+    /// Generation of the scan the daemon on `socket` answers from
+    fn generation(socket: &Path) -> u64 {
+        let answer: String = query(socket, r#"{"query": "status"}"#).unwrap();
+        let answer: Value = serde_json::from_str(&answer).unwrap();
+        answer["generation"].as_u64().unwrap()
+    }
+
+    #[test]
+    fn changes_the_scan_skips_are_not_rescanned() {
+        let base: PathBuf =
+            std::env::temp_dir().join(format!("pursue-daemon-{}-ignored", std::process::id()));
+        let root: PathBuf = base.join("crate");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join("Cargo.toml"), "[package]\n").unwrap();
+        std::fs::write(root.join("src/main.rs"), "// TODO: main\n").unwrap();
+        let socket: PathBuf = base.join("pursue.sock");
+
+        let scanner: Scanner = ScannerBuilder::new()
+            .paths([root.clone()])
+            .cache(false)
+            .build()
+            .unwrap();
+        let daemon = {
+            let (root, socket) = (root.clone(), socket.clone());
+            thread::spawn(move || run(scanner, &root, &socket, Duration::from_millis(10)))
+        };
+        while query(&socket, r#"{"query": "status"}"#).is_err() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(generation(&socket), 1);
+
+        // Excluded by default beside Cargo.toml
+        std::fs::write(root.join("target/build.rs"), "// TODO: generated\n").unwrap();
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(generation(&socket), 1);
+
+        std::fs::write(root.join("src/lib.rs"), "// TODO: lib\n").unwrap();
+        let mut waited: u32 = 0;
+        while generation(&socket) == 1 && waited < 500 {
+            thread::sleep(Duration::from_millis(10));
+            waited += 1;
+        }
+        assert_eq!(generation(&socket), 2);
+
+        query(&socket, r#"{"query": "shutdown"}"#).unwrap();
+        daemon.join().unwrap().unwrap();
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
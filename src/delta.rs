@@ -0,0 +1,305 @@
+/*
+ *  delta.rs - Findings added and removed between two scans of a tree
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use crate::{
+    report::{Occurrence, Report},
+    text,
+};
+
+/// Findings listed under a summary before the rest are only counted
+const LISTED_FINDINGS: usize = 10;
+
+/// One finding that appeared or went away
+#[derive(Serialize, Clone, Debug)]
+pub struct Finding {
+    pub path: PathBuf,
+    pub line: usize,
+    pub keyword: String,
+    pub fingerprint: String,
+}
+
+/// What changed between two scans, one line of a delta log
+#[derive(Serialize, Clone, Debug)]
+pub struct Delta {
+    /// Seconds since the epoch when the newer scan finished
+    pub time: u64,
+    pub added: Vec<Finding>,
+    pub removed: Vec<Finding>,
+    /// Net change of every keyword that changed
+    pub keyword_deltas: BTreeMap<String, isize>,
+    /// Findings in the newer scan
+    pub total: usize,
+}
+
+impl Delta {
+    /// Compare scans by fingerprint, so findings that only moved to another
+    /// line are neither added nor removed
+    pub fn between(old: &Report, new: &Report) -> Self {
+        let finding = |path: &Path, occurrence: &Occurrence| Finding {
+            path: path.to_path_buf(),
+            line: occurrence.line,
+            keyword: occurrence.keyword.clone(),
+            fingerprint: occurrence.fingerprint.clone(),
+        };
+
+        let mut remaining: HashMap<&str, usize> = HashMap::new();
+        for (_, occurrence) in old.occurrences() {
+            *remaining.entry(&occurrence.fingerprint).or_insert(0) += 1;
+        }
+
+        let mut added: Vec<Finding> = Vec::new();
+        let mut total: usize = 0;
+        for (path, occurrence) in new.occurrences() {
+            total += 1;
+            match remaining.get_mut(occurrence.fingerprint.as_str()) {
+                Some(count) if *count > 0 => *count -= 1,
+                _ => added.push(finding(path, occurrence)),
+            }
+        }
+
+        // Whatever wasn't matched by the newer scan is gone
+        let mut removed: Vec<Finding> = Vec::new();
+        for (path, occurrence) in old.occurrences() {
+            if let Some(count) = remaining.get_mut(occurrence.fingerprint.as_str())
+                && *count > 0
+            {
+                *count -= 1;
+                removed.push(finding(path, occurrence));
+            }
+        }
+
+        let mut keyword_deltas: BTreeMap<String, isize> = BTreeMap::new();
+        for finding in &added {
+            *keyword_deltas.entry(finding.keyword.clone()).or_insert(0) += 1;
+        }
+        for finding in &removed {
+            *keyword_deltas.entry(finding.keyword.clone()).or_insert(0) -= 1;
+        }
+        keyword_deltas.retain(|_, delta| *delta != 0);
+
+        Self {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            added,
+            removed,
+            keyword_deltas,
+            total,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// Findings added and removed, per keyword that changed
+    pub fn keyword_changes(&self) -> BTreeMap<&str, (usize, usize)> {
+        let mut changes: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+        for finding in &self.added {
+            changes.entry(&finding.keyword).or_default().0 += 1;
+        }
+        for finding in &self.removed {
+            changes.entry(&finding.keyword).or_default().1 += 1;
+        }
+        changes
+    }
+
+    /// Findings added and removed, per file that changed
+    pub fn file_changes(&self) -> BTreeMap<&Path, (usize, usize)> {
+        let mut changes: BTreeMap<&Path, (usize, usize)> = BTreeMap::new();
+        for finding in &self.added {
+            changes.entry(&finding.path).or_default().0 += 1;
+        }
+        for finding in &self.removed {
+            changes.entry(&finding.path).or_default().1 += 1;
+        }
+        changes
+    }
+
+    /// Every change since a saved report, per keyword, per file, and one by
+    /// one, for `--diff`
+    pub fn render(&self, root: &Path) -> String {
+        let relative = |path: &Path| -> String {
+            path.strip_prefix(root)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+
+        let mut text: String = format!(
+            "+{} -{} findings since the baseline, {} total\n",
+            self.added.len(),
+            self.removed.len(),
+            self.total
+        );
+        if self.is_empty() {
+            return text;
+        }
+
+        text += "\n---------------------------------------------------------\n";
+        let _ = writeln!(
+            text,
+            "{: <20} | {: <10} | {: <10} | {: <10}",
+            "Keyword", "Added", "Removed", "Net"
+        );
+        text += "---------------------------------------------------------\n";
+        for (keyword, (added, removed)) in self.keyword_changes() {
+            let _ = writeln!(
+                text,
+                "{} | {: <10} | {: <10} | {:+}",
+                text::pad(keyword, 20),
+                added,
+                removed,
+                added as isize - removed as isize
+            );
+        }
+
+        text += "\n---------------------------------------------------------\n";
+        let _ = writeln!(
+            text,
+            "{: <30} | {: <10} | {: <10}",
+            "File", "Added", "Removed"
+        );
+        text += "---------------------------------------------------------\n";
+        for (path, (added, removed)) in self.file_changes() {
+            let _ = writeln!(
+                text,
+                "{} | {: <10} | {: <10}",
+                text::pad(&relative(path), 30),
+                added,
+                removed
+            );
+        }
+
+        for (heading, findings) in [("Added", &self.added), ("Removed", &self.removed)] {
+            if findings.is_empty() {
+                continue;
+            }
+            let _ = writeln!(text, "\n{}:", heading);
+            for finding in findings {
+                let _ = writeln!(
+                    text,
+                    "  {}:{} {}",
+                    relative(&finding.path),
+                    finding.line,
+                    finding.keyword
+                );
+            }
+        }
+        text
+    }
+
+    /// A line with the counts, followed by a line per finding up to a limit.
+    /// The time is UTC
+    pub fn summary(&self, root: &Path) -> String {
+        let seconds_of_day: u64 = self.time % (24 * 60 * 60);
+        let mut summary: String = format!(
+            "[{:02}:{:02}:{:02}] +{} -{} findings, {} total",
+            seconds_of_day / 3600,
+            seconds_of_day / 60 % 60,
+            seconds_of_day % 60,
+            self.added.len(),
+            self.removed.len(),
+            self.total
+        );
+        if !self.keyword_deltas.is_empty() {
+            let deltas: Vec<String> = self
+                .keyword_deltas
+                .iter()
+                .map(|(keyword, delta)| format!("{} {:+}", keyword, delta))
+                .collect();
+            let _ = write!(summary, " ({})", deltas.join(", "));
+        }
+        summary.push('\n');
+
+        let changes = self
+            .added
+            .iter()
+            .map(|finding| ('+', finding))
+            .chain(self.removed.iter().map(|finding| ('-', finding)));
+        for (sign, finding) in changes.clone().take(LISTED_FINDINGS) {
+            let _ = writeln!(
+                summary,
+                "  {} {}:{} {}",
+                sign,
+                finding
+                    .path
+                    .strip_prefix(root)
+                    .unwrap_or(&finding.path)
+                    .display(),
+                finding.line,
+                finding.keyword
+            );
+        }
+        let unlisted: usize = changes.count().saturating_sub(LISTED_FINDINGS);
+        if unlisted > 0 {
+            let _ = writeln!(summary, "  and {} more", unlisted);
+        }
+        summary
+    }
+}
+
+/// Remembers the last scan of a watched tree to print, and optionally log,
+/// what each new scan changed
+pub struct Tracker {
+    root: PathBuf,
+    previous: Option<Report>,
+    log: Option<File>,
+}
+
+impl Tracker {
+    /// `log` is appended to as NDJSON, one delta per line
+    pub fn new(root: &Path, log: Option<&Path>) -> Result<Self, std::io::Error> {
+        Ok(Self {
+            root: root.to_path_buf(),
+            previous: None,
+            log: match log {
+                Some(log) => Some(File::options().create(true).append(true).open(log)?),
+                None => None,
+            },
+        })
+    }
+
+    /// Print and log the delta since the last report. The first report only
+    /// becomes the starting point
+    pub fn record(&mut self, report: Report) -> Result<(), std::io::Error> {
+        if let Some(previous) = &self.previous {
+            let delta: Delta = Delta::between(previous, &report);
+            if !delta.is_empty() {
+                print!("{}", delta.summary(&self.root));
+                if let Some(log) = &mut self.log {
+                    writeln!(log, "{}", serde_json::to_string(&delta)?)?;
+                }
+            }
+        }
+        self.previous = Some(report);
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+/*
+ *  dismissed.rs - Findings triaged as false positives, kept out of counts
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    collections::HashSet,
+    fs::OpenOptions,
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{config, repository};
+
+/// Lives in the anchor of the scan, one fingerprint per line with an
+/// optional `# reason` after it
+pub const DISMISSED_FILE_NAME: &str = ".pursue-dismissed";
+
+const FINGERPRINT_LENGTH: usize = 16;
+
+/// Starting value of an FNV-1a hash, see `fnv1a`
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+
+/// Fold `bytes` into an FNV-1a hash. Used for anything that has to hash the
+/// same way across runs and releases, which std's hashers don't promise
+pub(crate) fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    const PRIME: u64 = 0x100000001b3;
+
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(PRIME);
+    }
+    hash
+}
+
+pub(crate) fn to_hex(hash: u64) -> String {
+    format!("{:0width$x}", hash, width = FINGERPRINT_LENGTH)
+}
+
+/// Directory a scan of `root` keeps the dismissed list and the baseline in
+/// and fingerprints findings relative to, so both hold whichever directory
+/// of the project the scan starts from: the top of the git repository
+/// holding `root`, or else the outermost directory at or above it with a
+/// config file, or else `root` itself
+pub fn anchor(root: &Path) -> PathBuf {
+    if let Ok(top) = repository::top_level(root) {
+        return top.canonicalize().unwrap_or(top);
+    }
+    root.ancestors()
+        .filter(|directory| config::find_config(directory).is_some())
+        .last()
+        .unwrap_or(root)
+        .to_path_buf()
+}
+
+/// Stable identity of a finding that survives the line moving around in its
+/// file
+pub fn fingerprint(relative_path: &Path, keyword: &str, line: &str) -> String {
+    let path: String = relative_path.to_string_lossy().replace('\\', "/");
+    let normalized_line: String = line.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    let mut hash: u64 = FNV_OFFSET_BASIS;
+    for part in [path.as_str(), keyword, normalized_line.as_str()] {
+        hash = fnv1a(hash, part.as_bytes());
+        hash = fnv1a(hash, &[0]);
+    }
+
+    to_hex(hash)
+}
+
+pub fn is_fingerprint(candidate: &str) -> bool {
+    candidate.len() == FINGERPRINT_LENGTH && candidate.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Fingerprints dismissed in `anchor`. A missing file dismisses nothing
+pub fn load(anchor: &Path) -> Result<HashSet<String>, std::io::Error> {
+    let contents: String = match std::fs::read_to_string(anchor.join(DISMISSED_FILE_NAME)) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(HashSet::new()),
+        Err(error) => return Err(error),
+    };
+
+    Ok(contents
+        .lines()
+        .filter_map(|line| line.split('#').next())
+        .map(str::trim)
+        .filter(|fingerprint| is_fingerprint(fingerprint))
+        .map(|fingerprint| fingerprint.to_ascii_lowercase())
+        .collect())
+}
+
+/// Append `fingerprint` to the dismissed file in `anchor`, returning the
+/// path of the file
+pub fn dismiss(anchor: &Path, fingerprint: &str, reason: &str) -> Result<PathBuf, std::io::Error> {
+    if !is_fingerprint(fingerprint) {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{:?} is not a fingerprint, expected {} hex digits",
+                fingerprint, FINGERPRINT_LENGTH
+            ),
+        ));
+    }
+
+    let path: PathBuf = anchor.join(DISMISSED_FILE_NAME);
+    if load(anchor)?.contains(&fingerprint.to_ascii_lowercase()) {
+        return Ok(path);
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    match reason.trim().is_empty() {
+        true => writeln!(file, "{}", fingerprint.to_ascii_lowercase())?,
+        false => writeln!(
+            file,
+            "{} # {}",
+            fingerprint.to_ascii_lowercase(),
+            reason.trim()
+        )?,
+    }
+
+    Ok(path)
+}
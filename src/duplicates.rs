@@ -0,0 +1,56 @@
+/*
+ *  duplicates.rs - Find files with the same contents
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::report::{DuplicateSet, FileReport, Report};
+
+/// Group the files of `report` whose lines are the same, going by the
+/// content hash every fully read file gets while it is scanned. Empty files
+/// are all alike, so they are left out. The sets wasting the most bytes
+/// come first
+pub fn annotate(report: &mut Report) {
+    let mut sets: BTreeMap<(&str, u64), Vec<&FileReport>> = BTreeMap::new();
+    for file in &report.files {
+        if let Some(hash) = &file.content_hash
+            && file.line_count > 0
+        {
+            sets.entry((hash, file.bytes)).or_default().push(file);
+        }
+    }
+
+    let mut duplicates: Vec<DuplicateSet> = sets
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .map(|((hash, bytes), files)| DuplicateSet {
+            hash: hash.to_string(),
+            bytes,
+            paths: files
+                .iter()
+                .map(|file| file.path.clone())
+                .collect::<Vec<PathBuf>>(),
+        })
+        .collect();
+    duplicates.sort_by(|left, right| {
+        right
+            .wasted_bytes()
+            .cmp(&left.wasted_bytes())
+            .then_with(|| left.paths.cmp(&right.paths))
+    });
+    report.duplicates = duplicates;
+}
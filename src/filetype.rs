@@ -1,5 +1,5 @@
 /*
- *  filetype.rs - Store grammar information about different filetypes
+ *  filetype.rs - Registry of languages and the comment syntax of each
  *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
  *
  *  This program is free software; you can redistribute it and/or modify
@@ -16,307 +16,575 @@
  *  with this program. If not, see <https://www.gnu.org/licenses/>
  */
 
-macro_rules! stringify_filetype {
-    ($ft: ident) => {
-        match $ft {
-            FileType::C {
-                inline_comment_format: _,
-                multiline_comment_start_format: _,
-                multiline_comment_end_format: _,
-            } => "C",
-            FileType::CHeader {
-                inline_comment_format: _,
-                multiline_comment_start_format: _,
-                multiline_comment_end_format: _,
-            } => "C Header",
-            FileType::Cpp {
-                inline_comment_format: _,
-                multiline_comment_start_format: _,
-                multiline_comment_end_format: _,
-            } => "C++",
-            FileType::CppHeader {
-                inline_comment_format: _,
-                multiline_comment_start_format: _,
-                multiline_comment_end_format: _,
-            } => "C++ Header",
-            FileType::CSharp {
-                inline_comment_format: _,
-                multiline_comment_start_format: _,
-                multiline_comment_end_format: _,
-            } => "C#",
-            FileType::Java {
-                inline_comment_format: _,
-                multiline_comment_start_format: _,
-                multiline_comment_end_format: _,
-            } => "Java",
-            FileType::Python {
-                inline_comment_format: _,
-                multiline_comment_start_format: _,
-                multiline_comment_end_format: _,
-            } => "Python",
-            FileType::Go {
-                inline_comment_format: _,
-                multiline_comment_start_format: _,
-                multiline_comment_end_format: _,
-            } => "Go",
-            FileType::Rust {
-                inline_comment_format: _,
-                multiline_comment_start_format: _,
-                multiline_comment_end_format: _,
-            } => "Rust",
-            FileType::Zig {
-                inline_comment_format: _,
-                multiline_comment_start_format: _,
-                multiline_comment_end_format: _,
-            } => "Zig",
-            FileType::Javascript {
-                inline_comment_format: _,
-                multiline_comment_start_format: _,
-                multiline_comment_end_format: _,
-            } => "JavaScript",
-            FileType::Typescript {
-                inline_comment_format: _,
-                multiline_comment_start_format: _,
-                multiline_comment_end_format: _,
-            } => "TypeScript",
-            FileType::Makefile {
-                inline_comment_format: _,
-                multiline_comment_start_format: _,
-                multiline_comment_end_format: _,
-            } => "Makefile",
-            FileType::Json {
-                inline_comment_format: _,
-                multiline_comment_start_format: _,
-                multiline_comment_end_format: _,
-            } => "JSON",
-            FileType::VB {
-                inline_comment_format: _,
-                multiline_comment_start_format: _,
-                multiline_comment_end_format: _,
-            } => "Visual Basic",
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{ErrorKind, Read},
+    path::Path,
+};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    dismissed::{FNV_OFFSET_BASIS, fnv1a},
+    text::Encoding,
+};
+
+/// Definitions of the languages known without a languages file
+const BUILTIN_LANGUAGES: &str = include_str!("languages.toml");
+
+/// Bytes read from the start of a file whose name doesn't give away its
+/// language
+const SNIFF_LENGTH: u64 = 1024;
+
+/// Bytes read from the start of a file to tell whether it is binary
+const BINARY_SNIFF_LENGTH: u64 = 8 * 1024;
+
+/// Extensions of files that are never text, skipped without being read
+const BINARY_EXTENSIONS: [&str; 44] = [
+    "png", "jpg", "jpeg", "gif", "bmp", "ico", "webp", "tif", "tiff", "psd", "pdf", "zip", "gz",
+    "tgz", "bz2", "xz", "zst", "7z", "rar", "jar", "war", "class", "o", "obj", "a", "lib", "so",
+    "dylib", "dll", "exe", "pyc", "wasm", "mp3", "mp4", "mov", "avi", "wav", "flac", "woff",
+    "woff2", "ttf", "otf", "eot", "sqlite",
+];
+
+/// Lines at the start of a file searched for an editor mode line
+const MODE_LINE_SEARCH: usize = 5;
+
+/// How to recognize a language and read its comments
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Language {
+    pub name: String,
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Whole file names, e.g. `Makefile`
+    #[serde(default)]
+    pub file_names: Vec<String>,
+    /// File name endings made of more than one extension, e.g. `.gradle.kts`
+    #[serde(default)]
+    pub suffixes: Vec<String>,
+    /// Match `extensions` regardless of case
+    #[serde(default)]
+    pub ignore_case: bool,
+    /// Programs that run scripts of the language from a `#!` line, e.g.
+    /// `python` for `#!/usr/bin/env python3`
+    #[serde(default)]
+    pub interpreters: Vec<String>,
+    #[serde(default)]
+    pub inline_comments: Vec<String>,
+    /// Openers and closers of comments that may span lines. Written as
+    /// `block_comment`, either one pair or a list of them
+    #[serde(
+        default,
+        rename = "block_comment",
+        deserialize_with = "one_or_more_pairs"
+    )]
+    pub block_comments: Vec<(String, String)>,
+    /// Block comments that nest, each opener inside one needing its own
+    /// closer, e.g. `/* /* */ */` in Rust
+    #[serde(default)]
+    pub supports_nesting: Nesting,
+    /// Comment openers that mark documentation rather than a plain comment.
+    /// These are always a longer form of the inline or block openers
+    #[serde(default)]
+    pub doc_comments: Vec<String>,
+    /// Starts of declaration lines that make the inline comments right
+    /// above them documentation, e.g. `func ` in Go
+    #[serde(default)]
+    pub doc_comment_before: Vec<String>,
+    /// Keywords that define a function, e.g. `fn` in Rust
+    #[serde(default)]
+    pub functions: Vec<String>,
+    /// Opener and closer of string literals in which a backslash escapes
+    /// the next character
+    #[serde(default)]
+    pub strings: Vec<(String, String)>,
+    /// Opener and closer of string literals without escapes, e.g. `r"` and
+    /// `"` for a Rust raw string
+    #[serde(default)]
+    pub raw_strings: Vec<(String, String)>,
+    /// `'` starts a character literal, `'"'` or `'\n'`, but only when it
+    /// closes right after one character or escape, so a lifetime like `'a`
+    /// in Rust is code
+    #[serde(default)]
+    pub char_literals: bool,
+    /// Inline comments only start at the beginning of a line or after
+    /// whitespace, so the `#` of `url: http://x/#frag` in YAML is text
+    #[serde(default)]
+    pub inline_comments_after_whitespace: bool,
+    /// Block comments only open and close at the very start of a line, as
+    /// POD does in Perl and `=begin` in Ruby
+    #[serde(default)]
+    pub block_comments_at_line_start: bool,
+    /// Inline comments only start a line, after nothing but whitespace and
+    /// `line_prefixes`, as `REM` and `::` in Batch
+    #[serde(default)]
+    pub inline_comments_at_line_start: bool,
+    /// What may come before an inline comment that starts a line, e.g. the
+    /// `@` that keeps Batch from echoing the line
+    #[serde(default)]
+    pub line_prefixes: Vec<String>,
+    /// Comment markers match whatever their case, so `rem` and `Rem` are
+    /// Batch's `REM` too
+    #[serde(default)]
+    pub comments_ignore_case: bool,
+}
+
+impl Language {
+    /// Whether the block comment at `block` of `block_comments` nests
+    pub fn nests(&self, block: usize) -> bool {
+        match &self.supports_nesting {
+            Nesting::Every(nests) => *nests,
+            Nesting::Only(openers) => self
+                .block_comments
+                .get(block)
+                .is_some_and(|(opener, _)| openers.contains(opener)),
+        }
+    }
+
+    /// Openers of the block comments that nest
+    pub fn nesting_openers(&self) -> Vec<String> {
+        self.block_comments
+            .iter()
+            .enumerate()
+            .filter(|(block, _)| self.nests(*block))
+            .map(|(_, (opener, _))| opener.clone())
+            .collect()
+    }
+
+    /// Whether a comment starting at the beginning of `comment` is a doc
+    /// comment. `////` and `/**/` style comments are regular comments.
+    pub fn is_doc_comment(&self, comment: &str) -> bool {
+        self.doc_comments.iter().any(|format| {
+            comment.starts_with(format.as_str())
+                && !matches!(
+                    comment[format.len()..].chars().next(),
+                    Some(c) if c == '/' || format.ends_with(c)
+                )
+        })
+    }
+
+    /// Whether an inline comment may start after `before`, the part of its
+    /// line ahead of it
+    pub fn opens_inline_comment_after(&self, before: &str) -> bool {
+        match (
+            self.inline_comments_at_line_start,
+            self.inline_comments_after_whitespace,
+        ) {
+            (true, _) => {
+                let mut rest: &str = before.trim_start();
+                while let Some(stripped) = self
+                    .line_prefixes
+                    .iter()
+                    .find_map(|prefix| rest.strip_prefix(prefix.as_str()))
+                {
+                    rest = stripped.trim_start();
+                }
+                rest.is_empty()
+            }
+            (false, true) => before.chars().next_back().is_none_or(char::is_whitespace),
+            (false, false) => true,
+        }
+    }
+
+    /// Whether `line` declares something the comments above it document
+    pub fn is_documented_declaration(&self, line: &str) -> bool {
+        self.doc_comment_before
+            .iter()
+            .any(|declaration| line.starts_with(declaration.as_str()))
+    }
+
+    /// Whether `code` defines a function, by one of the `functions`
+    /// keywords standing on its own and followed by more than whitespace.
+    /// `Fn(u8)` and `fn(u8)` types aren't definitions
+    pub fn declares_function(&self, code: &str) -> bool {
+        self.functions.iter().any(|keyword| {
+            code.match_indices(keyword.as_str()).any(|(start, _)| {
+                let after: &str = &code[start + keyword.len()..];
+                !code[..start]
+                    .chars()
+                    .next_back()
+                    .is_some_and(|c| c.is_alphanumeric() || c == '_')
+                    && after.starts_with(char::is_whitespace)
+                    && !after.trim().is_empty()
+            })
+        })
+    }
+
+    /// Empty comment or string markers would match every line
+    fn validate(&self) -> Result<(), String> {
+        let markers = self
+            .inline_comments
+            .iter()
+            .chain(&self.doc_comments)
+            .chain(&self.line_prefixes)
+            .chain(
+                self.block_comments
+                    .iter()
+                    .chain(&self.strings)
+                    .chain(&self.raw_strings)
+                    .flat_map(|(start, end)| [start, end]),
+            );
+        let unknown_nesting: Option<&String> = match &self.supports_nesting {
+            Nesting::Every(_) => None,
+            Nesting::Only(openers) => openers.iter().find(|opener| {
+                !self
+                    .block_comments
+                    .iter()
+                    .any(|(block_opener, _)| block_opener == *opener)
+            }),
+        };
+
+        match (
+            self.name.trim().is_empty(),
+            markers.into_iter().any(String::is_empty),
+            unknown_nesting,
+        ) {
+            (true, _, _) => Err("every language needs a name".to_string()),
+            (false, false, None)
+                if self
+                    .block_comments
+                    .iter()
+                    .enumerate()
+                    .any(|(block, (start, end))| self.nests(block) && start == end) =>
+            {
+                Err(format!(
+                    "{} nests block comments, but one opens and closes with the same marker",
+                    self.name
+                ))
+            }
+            (false, true, _) => Err(format!(
+                "{} has an empty comment or string marker",
+                self.name
+            )),
+            (false, false, Some(opener)) => Err(format!(
+                "{} nests {:?}, which opens none of its block comments",
+                self.name, opener
+            )),
+            (false, false, None) => Ok(()),
         }
-    };
+    }
 }
 
-macro_rules! destructure_filetype {
-    ($ft: ident) => {
-        match $ft {
-            FileType::C {
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            } => (
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            ),
-            FileType::CHeader {
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            } => (
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            ),
-            FileType::Cpp {
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            } => (
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            ),
-            FileType::CppHeader {
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            } => (
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            ),
-            FileType::CSharp {
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            } => (
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            ),
-            FileType::Java {
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            } => (
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            ),
-            FileType::Python {
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            } => (
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            ),
-            FileType::Go {
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            } => (
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            ),
-            FileType::Rust {
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            } => (
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            ),
-            FileType::Zig {
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            } => (
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            ),
-            FileType::Javascript {
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            } => (
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            ),
-            FileType::Typescript {
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            } => (
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            ),
-            FileType::Makefile {
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            } => (
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            ),
-            FileType::Json {
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            } => (
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            ),
-            FileType::VB {
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            } => (
-                inline_comment_format,
-                multiline_comment_start_format,
-                multiline_comment_end_format,
-            ),
+/// Which block comments nest: `true` for every one, or the openers of those
+/// that do, e.g. `["/+"]` in D, where `/* */` doesn't
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Nesting {
+    Every(bool),
+    Only(Vec<String>),
+}
+
+impl Default for Nesting {
+    fn default() -> Self {
+        Nesting::Every(false)
+    }
+}
+
+/// A single `[start, end]` pair, or a list of them
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Pairs {
+    One((String, String)),
+    More(Vec<(String, String)>),
+}
+
+fn one_or_more_pairs<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<(String, String)>, D::Error> {
+    Ok(match Pairs::deserialize(deserializer)? {
+        Pairs::One(pair) => vec![pair],
+        Pairs::More(pairs) => pairs,
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LanguagesFile {
+    #[serde(default)]
+    language: Vec<Language>,
+}
+
+/// Every known language, indexed by what identifies its files
+#[derive(Clone)]
+pub struct Registry {
+    languages: Vec<Language>,
+    file_names: HashMap<String, usize>,
+    /// Longest first, so the most specific suffix wins
+    suffixes: Vec<(String, usize)>,
+    extensions: HashMap<String, usize>,
+    /// Lowercased extensions of `ignore_case` languages
+    folded_extensions: HashMap<String, usize>,
+    interpreters: HashMap<String, usize>,
+    /// Hash of every definition, so decisions remembered between runs can
+    /// be thrown out when the definitions change
+    signature: u64,
+}
+
+impl Registry {
+    /// The built in languages plus those of `file`. A language of the file
+    /// replaces a built in one of the same name, and claims any extension or
+    /// file name it shares with another
+    pub fn load(file: Option<&Path>) -> Result<Self, std::io::Error> {
+        let mut languages: Vec<Language> =
+            Self::parse(BUILTIN_LANGUAGES).expect("built in languages are valid");
+        let mut signature: u64 = fnv1a(FNV_OFFSET_BASIS, BUILTIN_LANGUAGES.as_bytes());
+
+        if let Some(file) = file {
+            let contents: String = std::fs::read_to_string(file)?;
+            signature = fnv1a(signature, contents.as_bytes());
+            let extra: Vec<Language> = Self::parse(&contents).map_err(|error| {
+                std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{}: {}", file.display(), error),
+                )
+            })?;
+            for language in extra {
+                match languages
+                    .iter()
+                    .position(|known| known.name == language.name)
+                {
+                    Some(index) => languages[index] = language,
+                    None => languages.push(language),
+                }
+            }
+        }
+
+        Ok(Self::new(languages, signature))
+    }
+
+    fn parse(contents: &str) -> Result<Vec<Language>, String> {
+        let file: LanguagesFile =
+            toml::from_str(contents).map_err(|error| error.message().trim_end().to_string())?;
+        for language in &file.language {
+            language.validate()?;
+        }
+        Ok(file.language)
+    }
+
+    fn new(languages: Vec<Language>, signature: u64) -> Self {
+        let mut registry: Self = Self {
+            languages: Vec::new(),
+            file_names: HashMap::new(),
+            suffixes: Vec::new(),
+            extensions: HashMap::new(),
+            folded_extensions: HashMap::new(),
+            interpreters: HashMap::new(),
+            signature,
+        };
+
+        // Later languages take over the extensions of earlier ones
+        for (index, language) in languages.iter().enumerate() {
+            for name in &language.file_names {
+                registry.file_names.insert(name.clone(), index);
+            }
+            for suffix in &language.suffixes {
+                registry.suffixes.retain(|(existing, _)| existing != suffix);
+                registry.suffixes.push((suffix.clone(), index));
+            }
+            for interpreter in &language.interpreters {
+                registry.interpreters.insert(interpreter.clone(), index);
+            }
+            for extension in &language.extensions {
+                match language.ignore_case {
+                    true => registry
+                        .folded_extensions
+                        .insert(extension.to_lowercase(), index),
+                    false => registry.extensions.insert(extension.clone(), index),
+                };
+            }
+        }
+        registry
+            .suffixes
+            .sort_by_key(|(suffix, _)| std::cmp::Reverse(suffix.len()));
+        registry.languages = languages;
+        registry
+    }
+
+    /// Every language, built in ones first, in the order they are defined
+    pub fn languages(&self) -> &[Language] {
+        &self.languages
+    }
+
+    pub fn signature(&self) -> u64 {
+        self.signature
+    }
+
+    pub fn language(&self, name: &str) -> Option<&Language> {
+        self.languages.iter().find(|language| language.name == name)
+    }
+
+    /// Language of `file` by its name, then by its contents
+    pub fn classify_file(&self, file: &Path) -> Option<(&Language, String)> {
+        self.classify(file)
+            .or_else(|| self.classify_content(&sniff(file)?))
+    }
+
+    /// Language of `file` the way `classify_file` finds it, with its
+    /// `contents` already read
+    pub fn classify_bytes(&self, file: &Path, contents: &[u8]) -> Option<(&Language, String)> {
+        self.classify(file).or_else(|| {
+            let head: &[u8] = &contents[..contents.len().min(SNIFF_LENGTH as usize)];
+            self.classify_content(&String::from_utf8_lossy(head))
+        })
+    }
+
+    /// Language of `file` along with a description of the rule that picked
+    /// it, going by its name alone
+    pub fn classify(&self, file: &Path) -> Option<(&Language, String)> {
+        let name: &str = file.file_name()?.to_str()?;
+
+        if let Some(&index) = self.file_names.get(name) {
+            return Some((&self.languages[index], format!("file name {:?}", name)));
+        }
+
+        if let Some((suffix, index)) = self
+            .suffixes
+            .iter()
+            .find(|(suffix, _)| name.len() > suffix.len() && name.ends_with(suffix.as_str()))
+        {
+            return Some((
+                &self.languages[*index],
+                format!("file name ending in {:?}", suffix),
+            ));
+        }
+
+        let extension: &str = file.extension()?.to_str()?;
+        self.extensions
+            .get(extension)
+            .or_else(|| self.folded_extensions.get(&extension.to_lowercase()))
+            .map(|&index| (&self.languages[index], format!("extension {:?}", extension)))
+    }
+
+    /// Language named by the `#!` line of a script, or by an editor mode
+    /// line at the start of a file, as in `-*- mode: python -*-` or
+    /// `vim: set ft=python:`
+    pub fn classify_content(&self, head: &str) -> Option<(&Language, String)> {
+        let first_line: &str = head.lines().next()?;
+        if let Some(language) = interpreter(first_line).and_then(|program| {
+            // python3.12 is python, as far as comments go
+            self.interpreters.get(program).or_else(|| {
+                self.interpreters
+                    .get(program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.'))
+            })
+        }) {
+            return Some((
+                &self.languages[*language],
+                format!("shebang {:?}", first_line.trim()),
+            ));
         }
-    };
+
+        head.lines().take(MODE_LINE_SEARCH).find_map(|line| {
+            let mode: &str = emacs_mode(line).or_else(|| vim_file_type(line))?;
+            self.by_mode(mode)
+                .map(|language| (language, format!("mode line {:?}", line.trim())))
+        })
+    }
+
+    /// Editors name languages after them or after their usual extension,
+    /// `python` or `py`, `c++` or `cpp`
+    fn by_mode(&self, mode: &str) -> Option<&Language> {
+        let mode: String = mode.to_lowercase();
+        self.languages
+            .iter()
+            .find(|language| language.name.to_lowercase() == mode)
+            .or_else(|| {
+                self.extensions
+                    .get(&mode)
+                    .or_else(|| self.folded_extensions.get(&mode))
+                    .map(|&index| &self.languages[index])
+            })
+    }
 }
 
-pub(crate) use destructure_filetype;
-pub(crate) use stringify_filetype;
-
-pub enum FileType<'b> {
-    C {
-        inline_comment_format: Option<&'b str>,
-        multiline_comment_start_format: Option<&'b str>,
-        multiline_comment_end_format: Option<&'b str>,
-    },
-    CHeader {
-        inline_comment_format: Option<&'b str>,
-        multiline_comment_start_format: Option<&'b str>,
-        multiline_comment_end_format: Option<&'b str>,
-    },
-    Cpp {
-        inline_comment_format: Option<&'b str>,
-        multiline_comment_start_format: Option<&'b str>,
-        multiline_comment_end_format: Option<&'b str>,
-    },
-    CppHeader {
-        inline_comment_format: Option<&'b str>,
-        multiline_comment_start_format: Option<&'b str>,
-        multiline_comment_end_format: Option<&'b str>,
-    },
-    CSharp {
-        inline_comment_format: Option<&'b str>,
-        multiline_comment_start_format: Option<&'b str>,
-        multiline_comment_end_format: Option<&'b str>,
-    },
-    Java {
-        inline_comment_format: Option<&'b str>,
-        multiline_comment_start_format: Option<&'b str>,
-        multiline_comment_end_format: Option<&'b str>,
-    },
-    Python {
-        inline_comment_format: Option<&'b str>,
-        multiline_comment_start_format: Option<&'b str>,
-        multiline_comment_end_format: Option<&'b str>,
-    },
-    Go {
-        inline_comment_format: Option<&'b str>,
-        multiline_comment_start_format: Option<&'b str>,
-        multiline_comment_end_format: Option<&'b str>,
-    },
-    Rust {
-        inline_comment_format: Option<&'b str>,
-        multiline_comment_start_format: Option<&'b str>,
-        multiline_comment_end_format: Option<&'b str>,
-    },
-    Zig {
-        inline_comment_format: Option<&'b str>,
-        multiline_comment_start_format: Option<&'b str>,
-        multiline_comment_end_format: Option<&'b str>,
-    },
-    Javascript {
-        inline_comment_format: Option<&'b str>,
-        multiline_comment_start_format: Option<&'b str>,
-        multiline_comment_end_format: Option<&'b str>,
-    },
-    Typescript {
-        inline_comment_format: Option<&'b str>,
-        multiline_comment_start_format: Option<&'b str>,
-        multiline_comment_end_format: Option<&'b str>,
-    },
-    Makefile {
-        inline_comment_format: Option<&'b str>,
-        multiline_comment_start_format: Option<&'b str>,
-        multiline_comment_end_format: Option<&'b str>,
-    },
-    Json {
-        inline_comment_format: Option<&'b str>,
-        multiline_comment_start_format: Option<&'b str>,
-        multiline_comment_end_format: Option<&'b str>,
-    },
-    VB {
-        inline_comment_format: Option<&'b str>,
-        multiline_comment_start_format: Option<&'b str>,
-        multiline_comment_end_format: Option<&'b str>,
-    },
+/// Up to `SNIFF_LENGTH` bytes from the start of `file`, for content based
+/// classification
+pub fn sniff(file: &Path) -> Option<String> {
+    let mut head: Vec<u8> = Vec::new();
+    File::open(file)
+        .ok()?
+        .take(SNIFF_LENGTH)
+        .read_to_end(&mut head)
+        .ok()?;
+    Some(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Whether `file` is binary going by its extension alone
+pub fn has_binary_extension(file: &Path) -> bool {
+    file.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            BINARY_EXTENSIONS
+                .iter()
+                .any(|binary| binary.eq_ignore_ascii_case(extension))
+        })
+}
+
+/// Encoding of `file` and the length of its byte order mark, or None when
+/// it is binary: its start holds a null byte, which text only does in
+/// UTF-16, and UTF-16 files are only recognized by their byte order mark
+pub fn text_encoding(file: &Path) -> Option<(Encoding, usize)> {
+    let mut head: Vec<u8> = Vec::new();
+    // Unreadable files are left for reading them to report
+    if File::open(file)
+        .and_then(|file| file.take(BINARY_SNIFF_LENGTH).read_to_end(&mut head))
+        .is_err()
+    {
+        return Some((Encoding::Utf8, 0));
+    }
+    bytes_encoding(&head)
+}
+
+/// Encoding of `contents` the way `text_encoding` finds it, going by the
+/// same number of bytes from their start
+pub fn bytes_encoding(contents: &[u8]) -> Option<(Encoding, usize)> {
+    let head: &[u8] = &contents[..contents.len().min(BINARY_SNIFF_LENGTH as usize)];
+    match Encoding::detect(head) {
+        (Encoding::Utf8, _) if head.contains(&0) => None,
+        detected => Some(detected),
+    }
+}
+
+/// `python3` out of `#!/usr/bin/python3 -u` or `#!/usr/bin/env -S python3 -u`
+fn interpreter(first_line: &str) -> Option<&str> {
+    let mut words = first_line.strip_prefix("#!")?.split_whitespace();
+    let program: &str = words.next()?.rsplit('/').next()?;
+
+    match program {
+        "env" => words.find(|word| !word.starts_with('-') && !word.contains('=')),
+        _ => Some(program),
+    }
+}
+
+/// `python` out of `-*- mode: python -*-` or `-*- python -*-`
+fn emacs_mode(line: &str) -> Option<&str> {
+    let (_, rest) = line.split_once("-*-")?;
+    let (variables, _) = rest.split_once("-*-")?;
+
+    match variables.contains(':') {
+        true => variables.split(';').find_map(|variable| {
+            let (name, value) = variable.split_once(':')?;
+            (name.trim().eq_ignore_ascii_case("mode")).then(|| value.trim())
+        }),
+        false => Some(variables.trim()),
+    }
+    .filter(|mode| !mode.is_empty())
+}
+
+/// `python` out of `vim: set ft=python:` or `vi: filetype=python`
+fn vim_file_type(line: &str) -> Option<&str> {
+    let start: usize = ["vim:", "vi:", "ex:"]
+        .iter()
+        .filter_map(|marker| line.find(marker))
+        .min()?;
+
+    line[start..]
+        .split([' ', '\t', ':'])
+        .find_map(|option| {
+            option
+                .strip_prefix("ft=")
+                .or_else(|| option.strip_prefix("filetype="))
+        })
+        .filter(|file_type| !file_type.is_empty())
 }
@@ -0,0 +1,297 @@
+/*
+ *  grammar.rs - Check language definitions against annotated snippets
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! A case is a snippet plus a sidecar file named after it with `.expected`
+//! appended. The sidecar has a line for every line of the snippet: how the
+//! line is counted, `code`, `comment`, or `blank`, followed by the comments
+//! on it and the keywords found in them. Each comment is written as the
+//! characters it starts and ends at, counting from 1, like `@5-18`, and
+//! keywords found in a doc comment are written `doc:TODO`.
+
+use std::{
+    collections::BTreeMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    Options,
+    filetype::Registry,
+    logger::{LineKind, Logger, SourceLine},
+    report::FileReport,
+};
+
+/// Appended to a snippet's name to name its expectations
+pub const EXPECTED_SUFFIX: &str = ".expected";
+
+macro_rules! bundled_case {
+    ($name:literal) => {
+        (
+            $name,
+            include_str!(concat!("../corpus/", $name)),
+            include_str!(concat!("../corpus/", $name, ".expected")),
+        )
+    };
+}
+
+/// The corpus in `corpus/`, built into the binary
+const BUNDLED: [(&str, &str, &str); 20] = [
+    bundled_case!("c/comments.c"),
+    bundled_case!("c/same_line.c"),
+    bundled_case!("c/unicode.c"),
+    bundled_case!("cmake/CMakeLists.txt"),
+    bundled_case!("d/nested.d"),
+    bundled_case!("go/doc.go"),
+    bundled_case!("go/raw.go"),
+    bundled_case!("html/comments.html"),
+    bundled_case!("javascript/template.js"),
+    bundled_case!("kotlin/nested.kt"),
+    bundled_case!("lua/comments.lua"),
+    bundled_case!("markdown/notes.md"),
+    bundled_case!("python/docstrings.py"),
+    bundled_case!("python/unicode.py"),
+    bundled_case!("rust/chars.rs"),
+    bundled_case!("rust/nested.rs"),
+    bundled_case!("rust/strings.rs"),
+    bundled_case!("rust/unicode.rs"),
+    bundled_case!("shell/script.sh"),
+    bundled_case!("yaml/config.yaml"),
+];
+
+/// An annotated snippet. The language is recognized from its name the way
+/// a file's would be
+pub struct Case {
+    pub name: PathBuf,
+    pub source: String,
+    pub expected: String,
+}
+
+/// How a case fared
+pub struct Outcome {
+    pub name: PathBuf,
+    /// None when no language recognizes the snippet
+    pub language: Option<String>,
+    pub failures: Vec<String>,
+}
+
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        self.language.is_some() && self.failures.is_empty()
+    }
+}
+
+pub fn bundled() -> Vec<Case> {
+    BUNDLED
+        .iter()
+        .map(|(name, source, expected)| Case {
+            name: PathBuf::from(name),
+            source: source.to_string(),
+            expected: expected.to_string(),
+        })
+        .collect()
+}
+
+/// Every snippet under `directory` with an expectations file next to it
+pub fn load(directory: &Path) -> Result<Vec<Case>, std::io::Error> {
+    fn walk(directory: &Path, found: &mut Vec<PathBuf>) -> Result<(), std::io::Error> {
+        for entry in directory.read_dir()? {
+            let path: PathBuf = entry?.path();
+            match path.is_dir() {
+                true => walk(&path, found)?,
+                false => found.push(path),
+            }
+        }
+        Ok(())
+    }
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk(directory, &mut files)?;
+    files.sort();
+
+    let mut cases: Vec<Case> = Vec::new();
+    for file in &files {
+        let mut expected_path = file.clone().into_os_string();
+        expected_path.push(EXPECTED_SUFFIX);
+        let expected_path: PathBuf = PathBuf::from(expected_path);
+        if !files.contains(&expected_path) {
+            continue;
+        }
+
+        cases.push(Case {
+            name: file.strip_prefix(directory).unwrap_or(file).to_path_buf(),
+            source: std::fs::read_to_string(file)?,
+            expected: std::fs::read_to_string(&expected_path)?,
+        });
+    }
+
+    match cases.is_empty() {
+        true => Err(std::io::Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "{} holds no snippets with a {} file next to them",
+                directory.display(),
+                EXPECTED_SUFFIX
+            ),
+        )),
+        false => Ok(cases),
+    }
+}
+
+/// `comment @1-12 @14-30 TODO doc:FIXME` with the comments in order and
+/// the keywords sorted, so lines compare regardless of the order keywords
+/// were written or found in
+fn describe(kind: &str, comments: &[String], keywords: &mut Vec<String>) -> String {
+    keywords.sort();
+    std::iter::once(kind.to_string())
+        .chain(comments.iter().cloned())
+        .chain(keywords.drain(..))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// What scanning found on every line, described like an expectations file
+fn findings(report: &FileReport, lines: &[SourceLine]) -> Vec<String> {
+    let mut keywords: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    for occurrence in &report.occurrences {
+        keywords
+            .entry(occurrence.line)
+            .or_default()
+            .push(match occurrence.in_doc_comment {
+                true => format!("doc:{}", occurrence.keyword),
+                false => occurrence.keyword.clone(),
+            });
+    }
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let kind: &str = match line.kind {
+                LineKind::Code => "code",
+                LineKind::Comment => "comment",
+                LineKind::Blank => "blank",
+            };
+            let comments: Vec<String> = line
+                .comments
+                .iter()
+                .map(|(start, end)| format!("@{}-{}", start, end))
+                .collect();
+            describe(kind, &comments, keywords.entry(index + 1).or_default())
+        })
+        .collect()
+}
+
+/// Scan every case with the built in languages plus those in `languages`,
+/// and compare what was found line by line with what was expected
+pub fn run(cases: &[Case], languages: Option<&Path>) -> Result<Vec<Outcome>, std::io::Error> {
+    let logger: Logger = Logger::new(Vec::new(), &Options::default(), Registry::load(languages)?);
+
+    Ok(cases
+        .iter()
+        .map(|case| {
+            let (language, report, lines) = match logger.scan_source(&case.name, &case.source) {
+                Some(scanned) => scanned,
+                None => {
+                    return Outcome {
+                        name: case.name.clone(),
+                        language: None,
+                        failures: vec!["no language recognizes this file".to_string()],
+                    };
+                }
+            };
+
+            let found: Vec<String> = findings(&report, &lines);
+            let expected: Vec<String> = case
+                .expected
+                .lines()
+                .map(|line| {
+                    let mut words = line.split_whitespace();
+                    let kind: &str = words.next().unwrap_or("blank");
+                    let (comments, mut keywords): (Vec<String>, Vec<String>) = words
+                        .map(str::to_string)
+                        .partition(|word| word.starts_with('@'));
+                    describe(kind, &comments, &mut keywords)
+                })
+                .collect();
+
+            let mut failures: Vec<String> = Vec::new();
+            if expected.len() != found.len() {
+                failures.push(format!(
+                    "{} lines are expected but the snippet has {}",
+                    expected.len(),
+                    found.len()
+                ));
+            }
+            for (index, (expected, found)) in expected.iter().zip(&found).enumerate() {
+                if expected != found {
+                    failures.push(format!(
+                        "line {}: expected {:?}, found {:?}",
+                        index + 1,
+                        expected,
+                        found
+                    ));
+                }
+            }
+
+            Outcome {
+                name: case.name.clone(),
+                language: Some(language.name.clone()),
+                failures,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{Case, Outcome, bundled, run};
+
+    #[test]
+    fn bundled_corpus_passes() {
+        let outcomes: Vec<Outcome> = run(&bundled(), None).unwrap();
+
+        for outcome in &outcomes {
+            assert!(
+                outcome.passed(),
+                "{}: {:?}",
+                outcome.name.display(),
+                outcome.failures
+            );
+        }
+    }
+
+    #[test]
+    fn comment_columns_are_compared() {
+        let case = |expected: &str| Case {
+            name: PathBuf::from("spans.rs"),
+            source: "let x = 1; /* a */ // TODO\n".to_string(),
+            expected: expected.to_string(),
+        };
+
+        let outcomes: Vec<Outcome> = run(&[case("code @12-18 @20-26 TODO\n")], None).unwrap();
+        assert_eq!(outcomes[0].failures, Vec::<String>::new());
+
+        let outcomes: Vec<Outcome> = run(&[case("code @12-18 @21-26 TODO\n")], None).unwrap();
+        assert_eq!(
+            outcomes[0].failures,
+            ["line 1: expected \"code @12-18 @21-26 TODO\", found \"code @12-18 @20-26 TODO\""]
+        );
+    }
+}
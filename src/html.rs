@@ -0,0 +1,376 @@
+/*
+ *  html.rs - Render a report as a set of static HTML pages
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::report::{FileReport, Report};
+
+/// Rows per page of a file table. Big enough to be useful, small enough that
+/// a browser opens every page instantly no matter how large the repo is
+pub const DEFAULT_PAGE_SIZE: usize = 500;
+
+pub const INDEX_PAGE: &str = "index.html";
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em}\
+table{border-collapse:collapse;margin-bottom:1em}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:left}\
+th{background:#eee}nav a{margin-right:.5em}";
+
+/// Extra style of the single page report: bar charts and clickable headers
+const STANDALONE_STYLE: &str = ".bar{display:flex;width:30em;height:1em;background:#eee}\
+.code{background:#4a7}.comment{background:#48c}.blank{background:#ccc}\
+th.sortable{cursor:pointer}th.sortable:after{content:' \\2195';color:#999}\
+pre{margin:0;white-space:pre-wrap}";
+
+/// Sorts a table by the column whose header is clicked, numerically when
+/// every cell of the column is a number. Clicking again reverses the order
+const SORT_SCRIPT: &str = "document.querySelectorAll('th.sortable').forEach(function(th){\
+th.addEventListener('click',function(){\
+var table=th.closest('table'),column=th.cellIndex,ascending=th.dataset.order!=='asc';\
+var rows=Array.from(table.querySelectorAll('tr')).slice(1);\
+var value=function(row){return row.cells[column].textContent.trim();};\
+var numeric=rows.every(function(row){return value(row)!==''&&!isNaN(value(row));});\
+rows.sort(function(a,b){var x=value(a),y=value(b);\
+var order=numeric?x-y:x.localeCompare(y);return ascending?order:-order;});\
+th.dataset.order=ascending?'asc':'desc';\
+rows.forEach(function(row){table.appendChild(row);});});});";
+
+/// One rendered page of the report
+pub struct Page {
+    pub name: String,
+    pub contents: String,
+}
+
+pub fn escape(text: &str) -> String {
+    let mut escaped: String = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// File name friendly version of a language name, e.g. `C++` -> `c--`
+fn slug(language: &str) -> String {
+    language
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_lowercase(),
+            false => '-',
+        })
+        .collect()
+}
+
+fn page_name(language: &str, page: usize) -> String {
+    format!("{}-{}.html", slug(language), page + 1)
+}
+
+fn document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title>\
+         <style>{}</style></head><body>\n<h1>{}</h1>\n{}</body></html>\n",
+        escape(title),
+        STYLE,
+        escape(title),
+        body
+    )
+}
+
+fn count_table(heading: &str, table: &BTreeMap<String, usize>, link: bool) -> String {
+    let mut html: String = format!("<table><tr><th>{}</th><th>Frequency</th></tr>\n", heading);
+    for (key, frequency) in table {
+        let name: String = match link {
+            true => format!("<a href=\"{}\">{}</a>", page_name(key, 0), escape(key)),
+            false => escape(key),
+        };
+        let _ = writeln!(html, "<tr><td>{}</td><td>{}</td></tr>", name, frequency);
+    }
+    html + "</table>\n"
+}
+
+fn pagination(language: &str, page: usize, page_count: usize) -> String {
+    let mut html: String = format!("<nav><a href=\"{}\">Summary</a>", INDEX_PAGE);
+    for other in 0..page_count {
+        match other == page {
+            true => {
+                let _ = write!(html, "<strong>{}</strong> ", other + 1);
+            }
+            false => {
+                let _ = write!(
+                    html,
+                    "<a href=\"{}\">{}</a>",
+                    page_name(language, other),
+                    other + 1
+                );
+            }
+        }
+    }
+    html + "</nav>\n"
+}
+
+/// Pages of one language's files, rendered one at a time as they are asked
+/// for
+fn language_pages<'a>(
+    language: &'a str,
+    files: Vec<&'a FileReport>,
+    keywords: Vec<&'a String>,
+    page_size: usize,
+) -> impl Iterator<Item = Page> + 'a {
+    let page_count: usize = files.len().div_ceil(page_size).max(1);
+
+    (0..page_count).map(move |page| {
+        let mut body: String = pagination(language, page, page_count);
+        body += "<table><tr><th>File</th><th>Lines</th>";
+        for keyword in &keywords {
+            let _ = write!(body, "<th>{}</th>", escape(keyword));
+        }
+        body += "</tr>\n";
+
+        for file in files.iter().skip(page * page_size).take(page_size) {
+            let _ = write!(
+                body,
+                "<tr><td>{}</td><td>{}</td>",
+                escape(&file.path.to_string_lossy()),
+                file.line_count
+            );
+            for keyword in &keywords {
+                let _ = write!(
+                    body,
+                    "<td>{}</td>",
+                    file.keyword_table.get(*keyword).copied().unwrap_or(0)
+                );
+            }
+            body += "</tr>\n";
+        }
+        body += "</table>\n";
+        body += &pagination(language, page, page_count);
+
+        Page {
+            name: page_name(language, page),
+            contents: document(
+                &format!("{} files ({} of {})", language, page + 1, page_count),
+                &body,
+            ),
+        }
+    })
+}
+
+/// Render `report` as an index page plus paginated per-language pages of
+/// files, busiest files first
+pub fn render(report: &Report, page_size: usize) -> Vec<Page> {
+    pages(report, page_size).collect()
+}
+
+/// Pages of `render`, each one only rendered when the previous one is done
+/// with, so a report of any size never has more than a page of HTML in
+/// memory
+pub fn pages(report: &Report, page_size: usize) -> impl Iterator<Item = Page> + '_ {
+    let page_size: usize = page_size.max(1);
+
+    let mut body: String = String::from("<p>");
+    for (name, value) in report.metrics.entries() {
+        let _ = write!(body, "{}: {}<br>", name, value);
+    }
+    let _ = writeln!(
+        body,
+        "dismissed findings: {}<br>suppressed findings: {}</p>",
+        report.dismissed_count, report.suppressed_count
+    );
+    body += &count_table("Key Comment", &report.keyword_table, false);
+    body += &count_table("Doc Key Comment", &report.doc_keyword_table, false);
+    body += "<table><tr><th>Language</th><th>Files</th><th>Lines</th><th>Code</th><th>Comment</th><th>Blank</th><th>Doc</th><th>Doc per 100 LOC</th><th>Bytes</th><th>Avg Bytes</th></tr>\n";
+    for language in report.language_breakdown() {
+        let _ = writeln!(
+            body,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(language.name),
+            language.files,
+            language.lines.total(),
+            language.lines.code,
+            language.lines.comment,
+            language.lines.blank,
+            language.doc_lines,
+            language.documentation_label(),
+            language.bytes,
+            report.average_file_size(language.name)
+        );
+    }
+    body += "</table>\n";
+
+    let index: Page = Page {
+        name: INDEX_PAGE.to_string(),
+        contents: document("Pursue Report", &body),
+    };
+
+    let mut languages: BTreeMap<&str, Vec<&FileReport>> = BTreeMap::new();
+    for file in &report.files {
+        languages.entry(&file.file_type).or_default().push(file);
+    }
+
+    let keywords: Vec<&String> = report.keyword_table.keys().collect();
+    std::iter::once(index).chain(
+        languages
+            .into_iter()
+            .flat_map(move |(language, mut files)| {
+                files.sort_by_key(|file| {
+                    std::cmp::Reverse(file.keyword_table.values().sum::<usize>())
+                });
+                language_pages(language, files, keywords.clone(), page_size)
+            }),
+    )
+}
+
+/// Header row whose columns sort the table when clicked
+fn sortable_header(columns: &[&str]) -> String {
+    let mut html: String = String::from("<tr>");
+    for column in columns {
+        let _ = write!(html, "<th class=\"sortable\">{}</th>", escape(column));
+    }
+    html + "</tr>\n"
+}
+
+/// The whole report as one HTML page with everything it needs inlined, for
+/// publishing as a single CI artifact. Tables sort by any column, and every
+/// file links to the list of its findings
+pub fn standalone(report: &Report) -> String {
+    let mut body: String = String::from("<table>");
+    body += &sortable_header(&["Metric", "Value"]);
+    for (name, value) in report.metrics.entries() {
+        let _ = writeln!(body, "<tr><td>{}</td><td>{}</td></tr>", name, value);
+    }
+    for (name, value) in [
+        ("dismissed findings", report.dismissed_count),
+        ("baselined findings", report.baselined_count),
+        ("suppressed findings", report.suppressed_count),
+        ("skipped files", report.skipped_file_count()),
+    ] {
+        let _ = writeln!(body, "<tr><td>{}</td><td>{}</td></tr>", name, value);
+    }
+    body += "</table>\n<h2>Keywords</h2>\n<table>";
+    body += &sortable_header(&["Keyword", "Findings", "In doc comments"]);
+    for (keyword, count) in &report.keyword_table {
+        let _ = writeln!(
+            body,
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(keyword),
+            count,
+            report.doc_keyword_table.get(keyword).copied().unwrap_or(0)
+        );
+    }
+
+    // Bars are as long as the language's share of the biggest language
+    let longest: usize = report
+        .language_line_counts
+        .values()
+        .map(|counts| counts.code + counts.comment + counts.blank)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    body += "</table>\n<h2>Languages</h2>\n<table>";
+    body += &sortable_header(&["Language", "Files", "Code", "Comment", "Blank", "Lines"]);
+    for (language, counts) in &report.language_line_counts {
+        let share = |lines: usize| lines as f64 * 100.0 / longest as f64;
+        let _ = writeln!(
+            body,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>\
+             <div class=\"bar\" title=\"code, comment, blank\">\
+             <div class=\"code\" style=\"width:{:.1}%\"></div>\
+             <div class=\"comment\" style=\"width:{:.1}%\"></div>\
+             <div class=\"blank\" style=\"width:{:.1}%\"></div></div></td></tr>",
+            escape(language),
+            report.filetype_table.get(language).copied().unwrap_or(0),
+            counts.code,
+            counts.comment,
+            counts.blank,
+            share(counts.code),
+            share(counts.comment),
+            share(counts.blank)
+        );
+    }
+
+    let files: Vec<(usize, &FileReport)> = report
+        .files
+        .iter()
+        .filter(|file| !file.occurrences.is_empty())
+        .enumerate()
+        .collect();
+    body += "</table>\n<h2>Files with findings</h2>\n<table>";
+    body += &sortable_header(&["File", "Language", "Lines", "Findings"]);
+    for (index, file) in &files {
+        let _ = writeln!(
+            body,
+            "<tr><td><a href=\"#file-{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            index,
+            escape(&file.path.to_string_lossy()),
+            escape(&file.file_type),
+            file.line_count,
+            file.occurrences.len()
+        );
+    }
+    body += "</table>\n";
+
+    for (index, file) in &files {
+        let _ = write!(
+            body,
+            "<h3 id=\"file-{}\">{}</h3>\n<table>",
+            index,
+            escape(&file.path.to_string_lossy())
+        );
+        body += &sortable_header(&["Line", "Keyword", "Comment"]);
+        for occurrence in &file.occurrences {
+            let _ = writeln!(
+                body,
+                "<tr><td>{}</td><td>{}</td><td><pre>{}</pre></td></tr>",
+                occurrence.line,
+                escape(&occurrence.keyword),
+                escape(&occurrence.snippet)
+            );
+        }
+        body += "</table>\n";
+    }
+
+    let _ = write!(
+        body,
+        "<style>{}</style>\n<script>{}</script>\n",
+        STANDALONE_STYLE, SORT_SCRIPT
+    );
+    document("Pursue Report", &body)
+}
+
+/// Write the rendered report into `directory`, returning the index page
+pub fn write(
+    report: &Report,
+    directory: &Path,
+    page_size: usize,
+) -> Result<PathBuf, std::io::Error> {
+    std::fs::create_dir_all(directory)?;
+    for page in pages(report, page_size) {
+        std::fs::write(directory.join(&page.name), page.contents)?;
+    }
+    Ok(directory.join(INDEX_PAGE))
+}
@@ -0,0 +1,331 @@
+/*
+ *  import.rs - Bring the findings of other tools into a report
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    baseline, dismissed,
+    report::{FileReport, LineCounts, Occurrence, Report},
+    severity::Severity,
+};
+
+/// Formats findings can be imported from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// Static Analysis Results Interchange Format, written by clippy
+    /// (through clippy-sarif), eslint, and most code scanners
+    Sarif,
+}
+
+impl FromStr for Source {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sarif" => Ok(Source::Sarif),
+            _ => Err(format!("Unknown import format {:?}, expected: sarif", s)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SarifLog {
+    #[serde(default)]
+    runs: Vec<Run>,
+}
+
+#[derive(Deserialize)]
+struct Run {
+    tool: Tool,
+    #[serde(default)]
+    results: Vec<SarifResult>,
+}
+
+#[derive(Deserialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Deserialize)]
+struct Driver {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: Option<String>,
+    level: Option<String>,
+    #[serde(default)]
+    message: Message,
+    #[serde(default)]
+    locations: Vec<Location>,
+}
+
+#[derive(Deserialize, Default)]
+struct Message {
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: Option<PhysicalLocation>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: Option<ArtifactLocation>,
+    region: Option<Region>,
+}
+
+#[derive(Deserialize)]
+struct ArtifactLocation {
+    uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: Option<usize>,
+    start_column: Option<usize>,
+}
+
+/// Path named by a SARIF artifact URI, either a `file://` URI or a path
+/// relative to `root`, with percent escapes decoded
+fn uri_path(uri: &str, root: &Path) -> PathBuf {
+    let path: &str = uri.strip_prefix("file://").unwrap_or(uri);
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(path.len());
+    let mut rest: &[u8] = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped: Option<u8> = match (byte, tail) {
+            (b'%', [high, low, ..]) => std::str::from_utf8(&[*high, *low])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match escaped {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    let path: String = String::from_utf8_lossy(&bytes).into_owned();
+
+    // file:///C:/src on Windows
+    let path: &str = match path.as_bytes() {
+        [b'/', _, b':', ..] => &path[1..],
+        _ => &path,
+    };
+    root.join(path)
+}
+
+/// Findings of every run in a SARIF log, one file report per file they are
+/// in. The rule that fired stands in for the keyword, or the tool's name
+/// when it gives none
+pub fn sarif(contents: &str, root: &Path) -> Result<Vec<FileReport>, std::io::Error> {
+    let log: SarifLog = serde_json::from_str(contents).map_err(|error| {
+        std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Not a SARIF log: {}", error),
+        )
+    })?;
+
+    let anchor: PathBuf = dismissed::anchor(root);
+    let mut files: BTreeMap<PathBuf, FileReport> = BTreeMap::new();
+    for run in log.runs {
+        for result in run.results {
+            let Some(physical) = result
+                .locations
+                .into_iter()
+                .find_map(|location| location.physical_location)
+            else {
+                continue;
+            };
+            let Some(uri) = physical.artifact_location.and_then(|artifact| artifact.uri) else {
+                continue;
+            };
+
+            let path: PathBuf = uri_path(&uri, root);
+            let keyword: String = result
+                .rule_id
+                .unwrap_or_else(|| run.tool.driver.name.clone());
+            let snippet: String = result
+                .message
+                .text
+                .unwrap_or_default()
+                .lines()
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_string();
+
+            let file: &mut FileReport = files.entry(path.clone()).or_insert_with(|| FileReport {
+                path: path.clone(),
+                file_type: run.tool.driver.name.clone(),
+                classified_by: "SARIF import".to_string(),
+                line_count: 0,
+                line_counts: LineCounts::default(),
+                bytes: 0,
+                doc_comment_line_count: 0,
+                keyword_table: BTreeMap::new(),
+                occurrences: Vec::new(),
+                content_hash: None,
+                partial: false,
+                health: None,
+                license_header: None,
+                hygiene: None,
+                archive: None,
+            });
+            *file.keyword_table.entry(keyword.clone()).or_insert(0) += 1;
+            let region: Option<Region> = physical.region;
+            file.occurrences.push(Occurrence {
+                line: region
+                    .as_ref()
+                    .and_then(|region| region.start_line)
+                    .unwrap_or(1),
+                column: region
+                    .as_ref()
+                    .and_then(|region| region.start_column)
+                    .unwrap_or(1),
+                byte_offset: 0,
+                fingerprint: dismissed::fingerprint(
+                    path.strip_prefix(&anchor).unwrap_or(&path),
+                    &keyword,
+                    &snippet,
+                ),
+                keyword,
+                // Results without a level are warnings
+                severity: result
+                    .level
+                    .as_deref()
+                    .and_then(|level| level.parse().ok())
+                    .unwrap_or(Severity::Warning),
+                snippet: snippet.clone(),
+                in_doc_comment: false,
+                author: None,
+                authored: None,
+                escalated_from: None,
+                assignee: None,
+                message: snippet.clone(),
+                language: run.tool.driver.name.clone(),
+                file_bytes: 0,
+                classified_by: "SARIF import".to_string(),
+            });
+        }
+    }
+
+    Ok(files.into_values().collect())
+}
+
+/// Read `path` as `source`
+pub fn load(source: Source, path: &Path, root: &Path) -> Result<Vec<FileReport>, std::io::Error> {
+    let contents: String = std::fs::read_to_string(path)?;
+    match source {
+        Source::Sarif => sarif(&contents, root).map_err(|error| {
+            std::io::Error::new(error.kind(), format!("{}: {}", path.display(), error))
+        }),
+    }
+}
+
+impl Report {
+    /// Add imported findings to this report of `root`, as if the scan had
+    /// found them. Findings dismissed or in the baseline of the scan are
+    /// only counted as such, and budgets count the rest
+    pub fn absorb(
+        &mut self,
+        root: &Path,
+        imported: Vec<FileReport>,
+        use_baseline: bool,
+    ) -> Result<(), std::io::Error> {
+        let anchor: PathBuf = dismissed::anchor(root);
+        let dismissed: HashSet<String> = dismissed::load(&anchor)?;
+        let baseline: HashSet<String> = match use_baseline {
+            true => baseline::fingerprints(&anchor)?,
+            false => HashSet::new(),
+        };
+
+        for mut file in imported {
+            file.occurrences.retain(|occurrence| {
+                if dismissed.contains(&occurrence.fingerprint) {
+                    self.dismissed_count += 1;
+                    return false;
+                }
+                if baseline.contains(&occurrence.fingerprint) {
+                    self.baselined_count += 1;
+                    return false;
+                }
+                true
+            });
+            if file.occurrences.is_empty() {
+                continue;
+            }
+
+            let mut keyword_table: BTreeMap<String, usize> = BTreeMap::new();
+            for occurrence in &file.occurrences {
+                *keyword_table.entry(occurrence.keyword.clone()).or_insert(0) += 1;
+            }
+            for budget in &mut self.budgets {
+                if file.path.starts_with(&budget.directory) {
+                    budget.count += keyword_table.get(&budget.keyword).copied().unwrap_or(0);
+                }
+            }
+            for (keyword, count) in &keyword_table {
+                *self.keyword_table.entry(keyword.clone()).or_insert(0) += count;
+            }
+            self.metrics.findings += file.occurrences.len();
+
+            match self
+                .files
+                .iter_mut()
+                .find(|scanned| scanned.path == file.path)
+            {
+                Some(scanned) => {
+                    for (keyword, count) in keyword_table {
+                        *scanned.keyword_table.entry(keyword).or_insert(0) += count;
+                    }
+                    scanned.occurrences.append(&mut file.occurrences);
+                    scanned
+                        .occurrences
+                        .sort_by_key(|occurrence| occurrence.line);
+                }
+                None => {
+                    file.keyword_table = keyword_table;
+                    self.files.push(file);
+                }
+            }
+        }
+
+        self.files.sort_by(|left, right| left.path.cmp(&right.path));
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+/*
+ *  jobs.rs - Work out how many threads the environment actually allows
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{io::ErrorKind, num::NonZero, path::Path};
+
+/// Thread count asked for outright, read when `--jobs` isn't given
+pub const JOBS_ENV_VAR: &str = "PURSUE_JOBS";
+
+/// Variables build tools and CI systems use to say how parallel a job may
+/// be, most specific first
+const JOB_ENV_VARS: [&str; 4] = [
+    "CARGO_BUILD_JOBS",
+    "CMAKE_BUILD_PARALLEL_LEVEL",
+    "NIX_BUILD_CORES",
+    "MAKEFLAGS",
+];
+
+/// A thread count along with where it came from
+#[derive(Clone, Debug)]
+pub struct Jobs {
+    pub count: NonZero<usize>,
+    pub source: String,
+}
+
+/// `-j N`, `-jN`, or `--jobs=N` inside a MAKEFLAGS style value, or the
+/// value itself when it is a plain number
+fn parse_jobs(value: &str) -> Option<NonZero<usize>> {
+    if let Ok(count) = value.trim().parse::<usize>() {
+        return NonZero::new(count);
+    }
+
+    let mut words = value.split_whitespace();
+    while let Some(word) = words.next() {
+        let count: Option<&str> = match word {
+            "-j" | "--jobs" => words.next(),
+            _ => word
+                .strip_prefix("--jobs=")
+                .or_else(|| word.strip_prefix("-j")),
+        };
+        if let Some(count) = count.and_then(|count| count.parse::<usize>().ok()) {
+            return NonZero::new(count);
+        }
+    }
+    None
+}
+
+fn from_variables() -> Option<Jobs> {
+    JOB_ENV_VARS.iter().find_map(|variable| {
+        let count: NonZero<usize> = parse_jobs(&std::env::var(variable).ok()?)?;
+        Some(Jobs {
+            count,
+            source: format!("${}", variable),
+        })
+    })
+}
+
+/// CPUs allowed by a cgroup CPU quota, rounded up. cgroup v2 keeps the quota
+/// and period together in `cpu.max`, v1 keeps them in two files
+fn from_cgroup() -> Option<Jobs> {
+    let read = |path: &str| std::fs::read_to_string(Path::new(path)).ok();
+
+    let (quota, period, source): (String, String, &str) = match read("/sys/fs/cgroup/cpu.max") {
+        Some(max) => {
+            let mut parts = max.split_whitespace();
+            (
+                parts.next()?.to_string(),
+                parts.next()?.to_string(),
+                "cgroup v2 cpu.max",
+            )
+        }
+        None => (
+            read("/sys/fs/cgroup/cpu/cpu.cfs_quota_us")?,
+            read("/sys/fs/cgroup/cpu/cpu.cfs_period_us")?,
+            "cgroup v1 cpu.cfs_quota_us",
+        ),
+    };
+
+    // "max" and -1 both mean there is no quota
+    let quota: u64 = quota.trim().parse().ok()?;
+    let period: u64 = period.trim().parse().ok()?;
+    let count: usize = usize::try_from(quota.div_ceil(period)).ok()?;
+    Some(Jobs {
+        count: NonZero::new(count)?,
+        source: source.to_string(),
+    })
+}
+
+/// Thread count asked for with `--jobs`. None when it isn't given, so the
+/// count is up to `$PURSUE_JOBS` and the other sources
+pub fn requested(jobs: Option<usize>) -> Result<Option<Jobs>, std::io::Error> {
+    let Some(jobs) = jobs else {
+        return Ok(None);
+    };
+    match NonZero::new(jobs) {
+        Some(count) => Ok(Some(Jobs {
+            count,
+            source: "--jobs".to_string(),
+        })),
+        None => Err(std::io::Error::new(
+            ErrorKind::InvalidInput,
+            "--jobs must be a whole number of at least 1, not 0",
+        )),
+    }
+}
+
+/// Thread count for CI runners and containers, where the host's CPU count
+/// overstates what the job may use. Never more than the CPUs available
+pub fn from_env() -> Jobs {
+    let cpus: NonZero<usize> = NonZero::new(num_cpus::get()).unwrap_or(NonZero::<usize>::MIN);
+    let detected: Option<Jobs> = from_variables().or_else(from_cgroup);
+
+    match detected {
+        Some(jobs) if jobs.count <= cpus => jobs,
+        _ => Jobs {
+            count: cpus,
+            source: "available CPUs".to_string(),
+        },
+    }
+}
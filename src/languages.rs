@@ -0,0 +1,196 @@
+/*
+ *  languages.rs - Lists the languages files are recognized as
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{collections::BTreeMap, path::Path};
+
+use serde::Serialize;
+
+use crate::filetype::{Language, Registry};
+
+/// Where the definition of a language comes from
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Origin {
+    BuiltIn,
+    /// A languages file defines it and no built in language has its name
+    Added,
+    /// A languages file defines it in place of the built in one
+    Replaced,
+}
+
+/// How files are recognized as a language, and how its comments look
+#[derive(Serialize, Clone, Debug)]
+pub struct LanguageInfo {
+    pub name: String,
+    pub origin: Origin,
+    /// Without the dot
+    pub extensions: Vec<String>,
+    pub file_names: Vec<String>,
+    pub suffixes: Vec<String>,
+    pub interpreters: Vec<String>,
+    /// Extensions, file names, suffixes, and interpreters the language
+    /// lists that files are classified as another language by instead,
+    /// since a later definition took them over
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub taken_by: BTreeMap<String, String>,
+    pub inline_comments: Vec<String>,
+    pub block_comments: Vec<(String, String)>,
+    /// Openers of the block comments that nest
+    pub nested: Vec<String>,
+    pub doc_comments: Vec<String>,
+}
+
+impl LanguageInfo {
+    fn new(language: &Language, origin: Origin, registry: &Registry) -> Self {
+        let mut taken_by: BTreeMap<String, String> = BTreeMap::new();
+        let mut check = |shown: String, winner: Option<&Language>| {
+            if let Some(winner) = winner.filter(|winner| winner.name != language.name) {
+                taken_by.insert(shown, winner.name.clone());
+            }
+        };
+        let classified = |file: String| {
+            registry
+                .classify(Path::new(&file))
+                .map(|(language, _)| language)
+        };
+
+        for extension in &language.extensions {
+            check(
+                format!(".{}", extension),
+                classified(format!("file.{}", extension)),
+            );
+        }
+        for name in &language.file_names {
+            check(name.clone(), classified(name.clone()));
+        }
+        for suffix in &language.suffixes {
+            check(suffix.clone(), classified(format!("file{}", suffix)));
+        }
+        for interpreter in &language.interpreters {
+            check(
+                format!("#!{}", interpreter),
+                registry
+                    .classify_content(&format!("#!/usr/bin/env {}\n", interpreter))
+                    .map(|(language, _)| language),
+            );
+        }
+
+        Self {
+            name: language.name.clone(),
+            origin,
+            extensions: language.extensions.clone(),
+            file_names: language.file_names.clone(),
+            suffixes: language.suffixes.clone(),
+            interpreters: language.interpreters.clone(),
+            taken_by,
+            inline_comments: language.inline_comments.clone(),
+            block_comments: language.block_comments.clone(),
+            nested: language.nesting_openers(),
+            doc_comments: language.doc_comments.clone(),
+        }
+    }
+
+    /// The language as indented `what: values` lines under its name
+    pub fn render(&self) -> String {
+        let list = |values: &[String]| -> String {
+            match values.is_empty() {
+                true => "none".to_string(),
+                false => values.join(" "),
+            }
+        };
+
+        let mut lines: Vec<String> = vec![format!(
+            "{} ({})",
+            self.name,
+            match self.origin {
+                Origin::BuiltIn => "built in",
+                Origin::Added => "added by the languages file",
+                Origin::Replaced => "replaced by the languages file",
+            }
+        )];
+        let extensions: Vec<String> = self
+            .extensions
+            .iter()
+            .map(|extension| format!(".{}", extension))
+            .collect();
+        let interpreters: Vec<String> = self
+            .interpreters
+            .iter()
+            .map(|interpreter| format!("#!{}", interpreter))
+            .collect();
+        for (what, values) in [
+            ("extensions", &extensions),
+            ("file names", &self.file_names),
+            ("suffixes", &self.suffixes),
+            ("interpreters", &interpreters),
+        ] {
+            if !values.is_empty() {
+                lines.push(format!("  {}: {}", what, values.join(" ")));
+            }
+        }
+        for (shown, winner) in &self.taken_by {
+            lines.push(format!("  {} is classified as {} instead", shown, winner));
+        }
+
+        lines.push(format!(
+            "  inline comments: {}",
+            list(&self.inline_comments)
+        ));
+        let block_comments: Vec<String> = self
+            .block_comments
+            .iter()
+            .map(|(start, end)| match self.nested.contains(start) {
+                true => format!("{} {} nesting", start, end),
+                false => format!("{} {}", start, end),
+            })
+            .collect();
+        lines.push(format!(
+            "  block comments: {}",
+            match block_comments.is_empty() {
+                true => "none".to_string(),
+                false => block_comments.join(", "),
+            }
+        ));
+        lines.push(format!("  doc comments: {}", list(&self.doc_comments)));
+
+        lines.join("\n") + "\n"
+    }
+}
+
+/// Every language files are recognized as: the built in ones and those of
+/// `file`, a TOML file of languages in the format of `src/languages.toml`,
+/// sorted by name
+pub fn list(file: Option<&Path>) -> Result<Vec<LanguageInfo>, std::io::Error> {
+    let built_in: Registry = Registry::load(None)?;
+    let registry: Registry = Registry::load(file)?;
+
+    let mut languages: Vec<LanguageInfo> = registry
+        .languages()
+        .iter()
+        .map(|language| {
+            let origin: Origin = match built_in.language(&language.name) {
+                Some(known) if known == language => Origin::BuiltIn,
+                Some(_) => Origin::Replaced,
+                None => Origin::Added,
+            };
+            LanguageInfo::new(language, origin, &registry)
+        })
+        .collect();
+    languages.sort_by_key(|language| language.name.to_lowercase());
+    Ok(languages)
+}
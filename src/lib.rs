@@ -0,0 +1,229 @@
+/*
+ *  lib.rs - Library interface to the profiler
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! Find TODOs and friends in a codebase.
+//!
+//! ```no_run
+//! let report = pursue::profile(std::path::Path::new("src"), &pursue::Options::default())?;
+//! println!("{} TODOs", report.keyword_table["TODO"]);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! [`ScannerBuilder`] sets up a scan one setting at a time, and the
+//! [`Scanner`] it builds can be run again and again.
+#![deny(unsafe_code)]
+
+pub mod archive;
+pub mod baseline;
+pub mod blame;
+pub mod cache;
+pub mod cluster;
+mod comment;
+mod compat;
+pub mod config;
+#[cfg(unix)]
+pub mod daemon;
+pub mod delta;
+pub mod dismissed;
+pub mod duplicates;
+mod filetype;
+pub mod grammar;
+pub mod html;
+pub mod import;
+pub mod jobs;
+pub mod languages;
+pub mod license;
+mod logger;
+// The only module allowed to use unsafe code, behind a safe interface
+#[allow(unsafe_code)]
+pub mod map;
+pub mod matching;
+pub mod output;
+mod priority;
+pub mod report;
+pub mod repository;
+mod results;
+mod sarif;
+pub mod scanner;
+pub mod serve;
+pub mod severity;
+pub mod sink;
+pub mod terminal;
+mod text;
+pub mod why;
+
+use std::{
+    collections::BTreeMap,
+    num::NonZero,
+    path::{Path, PathBuf},
+};
+
+use filetype::Registry;
+use license::LicenseCheck;
+use logger::Logger;
+use matching::Matching;
+pub use report::{ByteSize, FileReport, Metrics, OutputFormat, Report, Threshold};
+pub use scanner::{ScanReport, Scanner, ScannerBuilder};
+use severity::Severity;
+pub use sink::FindingSink;
+use terminal::ColorChoice;
+
+/// Keywords tracked when none are configured
+pub const DEFAULT_KEYWORDS: [&str; 4] = Logger::KEY_COMMENTS;
+
+/// How a directory should be profiled
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Keywords to look for. Config files found while profiling may add more
+    pub keywords: Vec<String>,
+    /// Print every keyword occurrence as it is found
+    pub verbose: bool,
+    /// Format the report will be printed in. Machine readable formats keep
+    /// verbose and status output off of stdout
+    pub format: OutputFormat,
+    /// Skip build output and dependency directories like `target/` and
+    /// `node_modules/` next to the manifests that produce them
+    pub default_ignores: bool,
+    /// Leave out findings recorded in the baseline file of the root
+    pub use_baseline: bool,
+    /// Profile the directories symlinks point to. Each directory is only
+    /// profiled once, which also breaks symlink loops
+    pub follow_symlinks: bool,
+    /// Worker threads to profile with, one per CPU when None
+    pub jobs: Option<NonZero<usize>>,
+    /// TOML file of languages to add to the built in ones or replace them
+    /// with, in the format of `src/languages.toml`
+    pub languages: Option<PathBuf>,
+    /// Remember the languages of files that had to be read to be classified
+    /// in the user's cache directory
+    pub cache: bool,
+    /// Gitignore style patterns, relative to the scan root, of paths to
+    /// scan before everything else, most important first
+    pub prioritize: Vec<String>,
+    /// Gitignore style patterns, relative to the scan root, of paths to
+    /// skip on top of those of config and ignore files
+    pub excludes: Vec<String>,
+    /// Scan a sample of the files again on one thread, reading each one
+    /// whole, and report where the two passes disagree
+    pub verify: bool,
+    /// How many directories below each profiled path are walked, all of
+    /// them when None
+    pub max_depth: Option<usize>,
+    /// Files bigger than this are skipped without being read
+    pub max_filesize: Option<u64>,
+    /// Scan the files git tracks, as listed by `git ls-files`, instead of
+    /// walking the directories
+    pub git: bool,
+    /// Scan only the files that changed since this git revision, committed
+    /// or not, along with new files git doesn't ignore
+    pub since: Option<String>,
+    /// Print the findings of every file to stdout as JSON Lines as soon as
+    /// the file is scanned
+    pub stream: bool,
+    /// Severity of each keyword mapped to one other than its default
+    pub severities: BTreeMap<String, Severity>,
+    /// How keywords are matched, unless `keyword_matching` says otherwise
+    pub matching: Matching,
+    /// How the keywords config files set up are matched
+    pub keyword_matching: BTreeMap<String, Matching>,
+    /// Whether findings printed while profiling are highlighted
+    pub color: ColorChoice,
+    /// Also measure line lengths, indentation, and functions of every file
+    pub health: bool,
+    /// Check that every file with comments starts with a license header
+    pub license: Option<LicenseCheck>,
+    /// Count whitespace problems of every line, and lines longer than this
+    /// many characters
+    pub hygiene: Option<usize>,
+    /// Scan the files inside the zip and tar archives found, `.crate`
+    /// files included, without unpacking them to disk
+    pub archives: bool,
+    /// File to keep the report of every file in between runs, so a run only
+    /// parses the files that changed since the last one
+    pub result_cache: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            keywords: DEFAULT_KEYWORDS
+                .iter()
+                .map(|keyword| keyword.to_string())
+                .collect(),
+            verbose: false,
+            format: OutputFormat::default(),
+            default_ignores: true,
+            use_baseline: true,
+            follow_symlinks: false,
+            jobs: None,
+            languages: None,
+            cache: true,
+            prioritize: Vec::new(),
+            excludes: Vec::new(),
+            verify: false,
+            max_depth: None,
+            max_filesize: None,
+            git: false,
+            since: None,
+            severities: BTreeMap::new(),
+            matching: Matching::default(),
+            keyword_matching: BTreeMap::new(),
+            color: ColorChoice::Auto,
+            stream: false,
+            health: false,
+            license: None,
+            hygiene: None,
+            archives: false,
+            result_cache: None,
+        }
+    }
+}
+
+/// Profile `path`, a directory or a single file, and return everything found
+pub fn profile(path: &Path, options: &Options) -> Result<Report, std::io::Error> {
+    profile_paths(&[path.to_path_buf()], options)
+}
+
+/// Profile several directories and files as one codebase. Paths nested in
+/// another one are only profiled once
+pub fn profile_paths(paths: &[PathBuf], options: &Options) -> Result<Report, std::io::Error> {
+    ScannerBuilder::new()
+        .options(options.clone())
+        .paths(paths.iter().cloned())
+        .build()?
+        .scan()
+}
+
+/// Count and search `source` as if it were the file `path`, without reading
+/// anything from disk. None when no language recognizes it
+pub fn scan_source(
+    path: &Path,
+    source: &str,
+    options: &Options,
+) -> Result<Option<FileReport>, std::io::Error> {
+    let registry: Registry = Registry::load(options.languages.as_deref())?;
+    let logger = Logger::new(vec![path.to_path_buf()], options, registry);
+    Ok(logger
+        .scan_source(path, source)
+        .map(|(_, file_report, _)| file_report))
+}
+
+/// Directory config files are read from when profiling `paths` together
+pub fn scan_root(paths: &[PathBuf]) -> PathBuf {
+    Logger::common_root(paths)
+}
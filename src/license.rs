@@ -0,0 +1,67 @@
+/*
+ *  license.rs - Check that source files start with a license header
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::io::ErrorKind;
+
+use serde::{Deserialize, Serialize};
+
+/// What a license header has to say and how far down it may start, with
+/// `--check-license`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LicenseCheck {
+    /// Any one of these found on a line counts as a header
+    pub patterns: Vec<String>,
+    /// Lines at the top of a file the header has to be in
+    pub lines: usize,
+}
+
+impl LicenseCheck {
+    /// Lines looked at when no other count is given, enough for a long
+    /// header below a shebang or a package clause
+    pub const DEFAULT_LINES: usize = 20;
+
+    /// A check for any of the `|` separated `patterns`, e.g.
+    /// `SPDX-License-Identifier|Copyright`
+    pub fn new(patterns: &str, lines: usize) -> Result<Self, std::io::Error> {
+        let patterns: Vec<String> = patterns
+            .split('|')
+            .map(str::trim)
+            .filter(|pattern| !pattern.is_empty())
+            .map(str::to_string)
+            .collect();
+        if patterns.is_empty() {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "--check-license needs at least one pattern",
+            ));
+        }
+        if lines == 0 {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "--license-lines must be at least 1",
+            ));
+        }
+
+        Ok(Self { patterns, lines })
+    }
+
+    /// Whether line `line_number` of a file, counting from 1, is a header
+    pub fn is_header(&self, line_number: usize, line: &str) -> bool {
+        line_number <= self.lines && self.patterns.iter().any(|pattern| line.contains(pattern))
+    }
+}
@@ -13,374 +13,1730 @@
  *  You should have received a copy of the GNU General Public License along with this program. If not, see <https://www.gnu.org/licenses/> */
 
 use std::{
-    collections::{HashMap, VecDeque},
-    fs::File,
-    io::{BufRead, BufReader, ErrorKind},
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{DirEntry, File, FileType, Metadata, ReadDir},
+    io::{BufRead, BufReader, ErrorKind, IsTerminal, Seek, SeekFrom},
     num::NonZero,
     path::{Path, PathBuf},
-    sync::{Arc, Condvar, Mutex, RwLock},
+    sync::{Arc, Mutex},
     thread,
+    time::{Duration, UNIX_EPOCH},
 };
 
-use crate::filetype::{destructure_filetype, stringify_filetype, FileType};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::{
+    Options, archive, baseline,
+    cache::{ClassificationCache, ResultCache, Scanned, Stamp},
+    comment::{self, Comment, OpenComment},
+    config::Scope,
+    dismissed::{self, FNV_OFFSET_BASIS, fnv1a, to_hex},
+    filetype::{Language, Registry, bytes_encoding, has_binary_extension, sniff, text_encoding},
+    license::LicenseCheck,
+    map::Map,
+    matching::Matching,
+    priority::{Priorities, WorkQueue},
+    report::{
+        BudgetResult, Discrepancy, FileHealth, FileReport, Hygiene, LineCounts, Metrics,
+        Occurrence, OutputFormat, Report, Verification,
+    },
+    repository,
+    results::Results,
+    severity::Severity,
+    sink::{JsonLines, Sinks},
+    text::{self, Encoding},
+    why::{CommentTrace, FileTrace, LineTrace},
+};
+
+/// How a line was counted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum LineKind {
+    Code,
+    Comment,
+    Blank,
+}
+
+/// How a line of source scanned on its own was read
+pub(crate) struct SourceLine {
+    pub(crate) kind: LineKind,
+    /// Characters each comment on it starts and ends at, counting from 1
+    pub(crate) comments: Vec<(usize, usize)>,
+}
+
+/// Comment state carried from one line of a file to the next
+#[derive(Default)]
+struct CommentState {
+    /// The block comment the last line left open
+    open: Option<OpenComment>,
+    in_doc_comment: bool,
+    /// A `pursue:ignore-file` directive was seen
+    ignore_file: bool,
+    /// Lines of plain inline comments in a row right above this one, which
+    /// become documentation if a declaration follows them
+    comment_run: usize,
+}
 
 pub struct Logger {
-    data: Mutex<VecDeque<PathBuf>>,
-    finish_flag: RwLock<bool>,
-    line_count: Mutex<usize>,
-    keyword_table: Mutex<HashMap<Arc<str>, usize>>,
-    filetype_table: Mutex<HashMap<Arc<str>, usize>>,
-    data_condition: Condvar,
-    data_condition_lock: Mutex<()>,
+    dismissed: HashSet<String>,
+    baseline: HashSet<String>,
+    use_baseline: bool,
+    /// Deepest directory holding every path
     root_directory: PathBuf,
+    /// Where the dismissed list and the baseline are kept, the base
+    /// fingerprints are relative to. See `dismissed::anchor`
+    anchor: PathBuf,
+    paths: Vec<PathBuf>,
+    keywords: Vec<Arc<str>>,
     verbose: bool,
+    format: OutputFormat,
+    default_ignores: bool,
+    follow_symlinks: bool,
+    jobs: Option<NonZero<usize>>,
+    registry: Registry,
+    use_cache: bool,
+    classifications: ClassificationCache,
+    /// File the reports of files are kept in between runs, with `--cache`
+    result_cache: Option<PathBuf>,
+    /// Reports of files from an earlier run, None unless they are cached
+    scanned: Option<ResultCache>,
+    prioritize: Vec<String>,
+    excludes: Vec<String>,
+    priorities: Priorities,
+    verify: bool,
+    max_depth: Option<usize>,
+    max_filesize: Option<u64>,
+    /// Scan the files inside archives instead of skipping them as binary
+    archives: bool,
+    git: bool,
+    since: Option<String>,
+    /// Handed every file as soon as it is scanned
+    sinks: Sinks,
+    health: bool,
+    license: Option<LicenseCheck>,
+    /// Characters a line can have before `--hygiene` counts it as long
+    max_line_length: Option<usize>,
+    /// Canonical directories already queued, so symlink loops end
+    visited: Mutex<HashSet<PathBuf>>,
+    /// Whether verbose output goes to a terminal that can show colors
+    highlight: bool,
+    severities: BTreeMap<String, Severity>,
+    matching: Matching,
+    keyword_matching: BTreeMap<String, Matching>,
 }
 
 impl<'a> Logger {
     const CORE_NUM_ERROR: &'a str = "ERROR: Could not properly deduce number of cpu cores!";
-    const CPP_FILE_EXTENSIONS: [&'a str; 3] = ["cpp", "cxx", "cc"];
-    const KEY_COMMENTS: [&'a str; 4] = ["TODO", "HACK", "BUG", "FIXME"];
+    /// Files at least this big are memory mapped instead of read
+    const MAP_THRESHOLD: u64 = 64 * 1024;
+    /// Bytes `--verify` reads again at most. Smaller scans are verified in
+    /// full, bigger ones by a random sample of files this big
+    const VERIFY_BYTES: u64 = 32 * 1024 * 1024;
+    const TAB_WIDTH: usize = 4;
+    const READ_RETRIES: u32 = 3;
+    const READ_RETRY_DELAY: Duration = Duration::from_millis(50);
+    /// Characters of a line shown in verbose output before it is cut down
+    const PREVIEW_WIDTH: usize = 100;
+    pub const KEY_COMMENTS: [&'a str; 4] = ["TODO", "HACK", "BUG", "FIXME"];
+    /// Tool directives that live in comments. Keywords on these lines are
+    /// almost always about the directive, not real debt
+    const DIRECTIVE_MARKERS: [&'a str; 14] = [
+        "noqa",
+        "type: ignore",
+        "pylint:",
+        "eslint-disable",
+        "eslint-enable",
+        "@ts-ignore",
+        "@ts-expect-error",
+        "prettier-ignore",
+        "istanbul ignore",
+        "NOLINT",
+        "rubocop:",
+        "shellcheck disable",
+        "clang-format off",
+        "clang-format on",
+    ];
+    const MODELINE_MARKERS: [&'a str; 3] = ["vim:", "vi:", "ex:"];
+    /// Leaves the findings of the comment it is in out of every table
+    const IGNORE_DIRECTIVE: &'a str = "pursue:ignore";
+    /// Leaves every finding of the file it is in out of every table
+    const IGNORE_FILE_DIRECTIVE: &'a str = "pursue:ignore-file";
 
-    pub fn new(directory: PathBuf, verbose_printing: bool) -> Self {
-        let mut comment_table: HashMap<Arc<str>, usize> = HashMap::new();
-        for comment in Self::KEY_COMMENTS {
-            comment_table.insert(comment.into(), 0);
+    pub fn new(paths: Vec<PathBuf>, options: &Options, registry: Registry) -> Self {
+        let format: OutputFormat = options.format;
+        let keywords: Vec<Arc<str>> = options
+            .keywords
+            .iter()
+            .map(|keyword| text::nfc(keyword).into())
+            .collect();
+        let mut sinks: Sinks = Sinks::default();
+        if options.stream {
+            sinks.push(Arc::new(JsonLines));
         }
 
         Self {
-            data: Mutex::new(VecDeque::new()),
-            finish_flag: RwLock::new(false),
-            line_count: Mutex::new(0),
-            keyword_table: Mutex::new(comment_table),
-            filetype_table: Mutex::new(HashMap::new()),
-            data_condition: Condvar::new(),
-            data_condition_lock: Mutex::new(()),
-            root_directory: directory,
-            verbose: verbose_printing,
-        }
-    }
-
-    fn print_result(&self) {
-        println!("-----------------------------------");
-        println!(
-            "{: <20} | {: <10}\n",
-            "Lines processed",
-            self.line_count.lock().unwrap()
-        );
+            dismissed: HashSet::new(),
+            baseline: HashSet::new(),
+            use_baseline: options.use_baseline,
+            root_directory: Self::common_root(&paths),
+            anchor: Self::common_root(&paths),
+            paths: Self::outermost(paths),
+            keywords,
+            verbose: options.verbose,
+            format,
+            default_ignores: options.default_ignores,
+            follow_symlinks: options.follow_symlinks,
+            jobs: options.jobs,
+            registry,
+            use_cache: options.cache,
+            classifications: ClassificationCache::disabled(),
+            result_cache: options.result_cache.clone(),
+            scanned: None,
+            prioritize: options.prioritize.clone(),
+            excludes: options.excludes.clone(),
+            priorities: Priorities::none(),
+            verify: options.verify,
+            max_depth: options.max_depth,
+            max_filesize: options.max_filesize,
+            archives: options.archives,
+            git: options.git,
+            since: options.since.clone(),
+            sinks,
+            health: options.health,
+            license: options.license.clone(),
+            max_line_length: options.hygiene,
+            visited: Mutex::new(HashSet::new()),
+            highlight: options.color.enabled(match format.is_machine_readable() {
+                true => std::io::stderr().is_terminal(),
+                false => std::io::stdout().is_terminal(),
+            }),
+            severities: options.severities.clone(),
+            matching: options.matching,
+            keyword_matching: options.keyword_matching.clone(),
+        }
+    }
 
-        println!("-----------------------------------");
-        println!("{: <20} | {: <15}", "Key Comment", "Frequency");
-        println!("-----------------------------------");
-        for (key, frequency) in self.keyword_table.lock().unwrap().iter() {
-            println!("{: <20} | {: <15}", key, frequency);
+    /// Also hand every scanned file to `sinks`
+    pub(crate) fn with_sinks(mut self, sinks: &Sinks) -> Self {
+        self.sinks.extend(sinks);
+        self
+    }
+
+    /// Take the reports of unchanged files from `cache`, a cache kept in
+    /// memory by the caller, rather than from a file
+    pub(crate) fn with_result_cache(mut self, cache: ResultCache) -> Self {
+        self.scanned = Some(cache);
+        self
+    }
+
+    /// The result cache, holding the reports of the files of the last scan
+    pub(crate) fn take_result_cache(&mut self) -> Option<ResultCache> {
+        self.scanned.take()
+    }
+
+    pub(crate) fn common_root(paths: &[PathBuf]) -> PathBuf {
+        let directories = paths.iter().map(|path| match path.is_dir() {
+            true => path.as_path(),
+            false => path.parent().unwrap_or(path),
+        });
+
+        let mut root: PathBuf = PathBuf::new();
+        for directory in directories {
+            root = match root.as_os_str().is_empty() {
+                true => directory.to_path_buf(),
+                false => root
+                    .components()
+                    .zip(directory.components())
+                    .take_while(|(left, right)| left == right)
+                    .map(|(component, _)| component)
+                    .collect(),
+            };
         }
+        root
+    }
 
-        println!("\n-----------------------------------");
-        println!("{: <20} | {: <15}", "File Type", "Frequency");
-        println!("-----------------------------------");
-        for (key, frequency) in self.filetype_table.lock().unwrap().iter() {
-            println!("{: <20} | {: <15}", key, frequency);
+    /// Drop duplicates and paths inside another one so nothing is counted twice
+    fn outermost(mut paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        paths.sort();
+        paths.dedup();
+        let mut kept: Vec<PathBuf> = Vec::new();
+        for path in paths {
+            if !kept.iter().any(|outer| path.starts_with(outer)) {
+                kept.push(path);
+            }
         }
+        kept
     }
 
-    fn increment_keyword(&self, keyword: &str) {
-        if let Some(value) = self.keyword_table.lock().unwrap().get_mut(keyword) {
-            *value += 1;
-            return;
+    /// Scope of the directory holding `path`, applying every config file
+    /// between the root and it
+    fn scope_for(
+        &self,
+        root_scope: &Arc<Scope>,
+        path: &Path,
+        results: &mut Results,
+    ) -> Result<Arc<Scope>, std::io::Error> {
+        let mut scope: Arc<Scope> = Arc::clone(root_scope);
+        let parent: &Path = path.parent().unwrap_or(path);
+        let relative: &Path = match parent.strip_prefix(&self.root_directory) {
+            Ok(relative) => relative,
+            Err(_) => return Ok(scope),
+        };
+
+        let mut directory: PathBuf = self.root_directory.clone();
+        scope = self.enter(&scope, &directory, results)?;
+        for component in relative.components() {
+            directory.push(component);
+            scope = self.enter(&scope, &directory, results)?;
+        }
+        Ok(scope)
+    }
+
+    /// Descend into `directory`, picking up the budgets of any config file
+    fn enter(
+        &self,
+        scope: &Arc<Scope>,
+        directory: &Path,
+        results: &mut Results,
+    ) -> Result<Arc<Scope>, std::io::Error> {
+        let child: Arc<Scope> = scope.descend(directory)?;
+        if !Arc::ptr_eq(&child, scope) {
+            results.budgets.extend(child.budgets().iter().cloned());
+        }
+        Ok(child)
+    }
+
+    /// The report of everything the workers found, merged into `results`
+    fn result(&self, results: Results) -> Report {
+        let Results {
+            mut keyword_table,
+            mut doc_keyword_table,
+            filetype_table,
+            mut files,
+            budgets,
+            dismissed_count,
+            baselined_count,
+            suppressed_count,
+            oversized_file_count,
+            binary_file_count,
+            unrecognized_file_count,
+            too_deep_directory_count,
+            mut warnings,
+            classifications: _,
+            scanned: _,
+        } = results;
+        // Every keyword is listed, even those never found
+        for keyword in &self.keywords {
+            keyword_table.entry(Arc::clone(keyword)).or_insert(0);
+            doc_keyword_table.entry(Arc::clone(keyword)).or_insert(0);
+        }
+
+        files.sort_by(|left, right| left.path.cmp(&right.path));
+        warnings.sort_by(|left, right| left.path.cmp(&right.path));
+
+        let mut language_line_counts: BTreeMap<String, LineCounts> = BTreeMap::new();
+        let mut language_byte_counts: BTreeMap<String, u64> = BTreeMap::new();
+        let mut language_doc_line_counts: BTreeMap<String, usize> = BTreeMap::new();
+        let mut metrics: Metrics = Metrics::default();
+        let mut assignee_table: BTreeMap<String, usize> = BTreeMap::new();
+        for file in &files {
+            metrics += file.metrics();
+            for assignee in file
+                .occurrences
+                .iter()
+                .filter_map(|occurrence| occurrence.assignee.as_ref())
+            {
+                *assignee_table.entry(assignee.clone()).or_insert(0) += 1;
+            }
+            *language_line_counts
+                .entry(file.file_type.clone())
+                .or_default() += file.line_counts;
+            *language_byte_counts
+                .entry(file.file_type.clone())
+                .or_default() += file.bytes;
+            *language_doc_line_counts
+                .entry(file.file_type.clone())
+                .or_default() += file.doc_comment_line_count;
         }
 
-        self.keyword_table.lock().unwrap().insert(keyword.into(), 1);
+        let budgets: Vec<BudgetResult> = budgets
+            .into_iter()
+            .map(|budget| BudgetResult {
+                count: files
+                    .iter()
+                    .filter(|file| file.path.starts_with(&budget.directory))
+                    .filter_map(|file| file.keyword_table.get(&budget.keyword))
+                    .sum(),
+                directory: budget.directory,
+                keyword: budget.keyword,
+                max: budget.max,
+                source: budget.source,
+                base_count: None,
+            })
+            .collect();
+
+        Report {
+            repository: self
+                .paths
+                .first()
+                .and_then(|path| repository::identity(path)),
+            deduplicated_file_count: 0,
+            budgets,
+            clusters: Vec::new(),
+            duplicates: Vec::new(),
+            license_check: self.license.clone(),
+            max_line_length: self.max_line_length,
+            language_line_counts,
+            language_byte_counts,
+            language_doc_line_counts,
+            files,
+            dismissed_count,
+            baselined_count,
+            suppressed_count,
+            oversized_file_count,
+            binary_file_count,
+            unrecognized_file_count,
+            too_deep_directory_count,
+            author_table: BTreeMap::new(),
+            max_age_days: None,
+            top: None,
+            color: false,
+            stale_count: 0,
+            assignee_table,
+            verification: None,
+            warnings,
+            metrics,
+            severities: self.severities.clone(),
+            doc_keyword_table: doc_keyword_table
+                .into_iter()
+                .map(|(key, frequency)| (key.to_string(), frequency))
+                .collect(),
+            keyword_table: keyword_table
+                .into_iter()
+                .map(|(key, frequency)| (key.to_string(), frequency))
+                .collect(),
+            filetype_table: filetype_table
+                .into_iter()
+                .map(|(key, frequency)| (key.to_string(), frequency))
+                .collect(),
+        }
     }
 
-    fn increment_filetype_frequency(&self, filetype: &FileType) {
-        let name = stringify_filetype!(filetype);
+    /// Take the findings of a file with a `pursue:ignore-file` directive
+    /// back out, counting them as suppressed
+    fn suppress_file(file_report: &mut FileReport, results: &mut Results) {
+        for occurrence in file_report.occurrences.drain(..) {
+            results.decrement_keyword(&occurrence.keyword, occurrence.in_doc_comment);
+            results.suppressed_count += 1;
+        }
+        file_report.keyword_table.clear();
+    }
 
-        let mut hashmap_guard = self.filetype_table.lock().unwrap();
+    /// Language of `path` by its name, or failing that by its contents,
+    /// along with the rule that picked it. Only what was found by reading
+    /// is remembered between runs, since going by the name is faster than
+    /// any lookup
+    fn classify(&self, path: &Path, results: &mut Results) -> Option<(&Language, String)> {
+        if let Some(classified) = self.registry.classify(path) {
+            return Some(classified);
+        }
 
-        if let Some(value) = hashmap_guard.get_mut(name) {
-            *value += 1;
-        } else {
-            hashmap_guard.insert(name.into(), 1);
-        }
-    }
-
-    fn classify_file(file: &Path) -> Option<FileType> {
-        return match file.extension() {
-            Some(extension) => match extension.to_str() {
-                Some("c") => Some(FileType::C {
-                    inline_comment_format: Some("//"),
-                    multiline_comment_start_format: Some("/*"),
-                    multiline_comment_end_format: Some("*/"),
-                }),
-                Some("h") => Some(FileType::CHeader {
-                    inline_comment_format: Some("//"),
-                    multiline_comment_start_format: Some("/*"),
-                    multiline_comment_end_format: Some("*/"),
-                }),
-                Some(ext) if Self::CPP_FILE_EXTENSIONS.contains(&ext) => Some(FileType::Cpp {
-                    inline_comment_format: Some("//"),
-                    multiline_comment_start_format: Some("/*"),
-                    multiline_comment_end_format: Some("*/"),
-                }),
-                Some("hpp") => Some(FileType::CppHeader {
-                    inline_comment_format: Some("//"),
-                    multiline_comment_start_format: Some("/*"),
-                    multiline_comment_end_format: Some("*/"),
-                }),
-                Some("cs") => Some(FileType::CSharp {
-                    inline_comment_format: Some("//"),
-                    multiline_comment_start_format: Some("/*"),
-                    multiline_comment_end_format: Some("*/"),
-                }),
-                Some("java") => Some(FileType::Java {
-                    inline_comment_format: Some("//"),
-                    multiline_comment_start_format: Some("/*"),
-                    multiline_comment_end_format: Some("*/"),
-                }),
-                Some("py") => Some(FileType::Python {
-                    inline_comment_format: Some("#"),
-                    multiline_comment_start_format: None,
-                    multiline_comment_end_format: None,
-                }),
-                Some("go") => Some(FileType::Go {
-                    inline_comment_format: Some("//"),
-                    multiline_comment_start_format: Some("/*"),
-                    multiline_comment_end_format: Some("*/"),
-                }),
-                Some("zig") => Some(FileType::Zig {
-                    inline_comment_format: Some("//"),
-                    multiline_comment_start_format: None,
-                    multiline_comment_end_format: None,
-                }),
-                Some("rs") => Some(FileType::Rust {
-                    inline_comment_format: Some("//"),
-                    multiline_comment_start_format: Some("/*"),
-                    multiline_comment_end_format: Some("*/"),
-                }),
-                Some("js") => Some(FileType::Javascript {
-                    inline_comment_format: Some("//"),
-                    multiline_comment_start_format: Some("/*"),
-                    multiline_comment_end_format: Some("*/"),
-                }),
-                Some("ts") => Some(FileType::Typescript {
-                    inline_comment_format: Some("//"),
-                    multiline_comment_start_format: Some("/*"),
-                    multiline_comment_end_format: Some("*/"),
-                }),
-                Some("json") => Some(FileType::Json {
-                    inline_comment_format: None,
-                    multiline_comment_start_format: None,
-                    multiline_comment_end_format: None,
-                }),
-                Some("vb") => Some(FileType::VB {
-                    inline_comment_format: Some("'"),
-                    multiline_comment_start_format: None,
-                    multiline_comment_end_format: None,
-                }),
-                _ => None,
+        let stamp: Stamp = Stamp::of(&std::fs::metadata(path).ok()?);
+        if let Some(name) = self
+            .classifications
+            .get(path, stamp, &mut results.classifications)
+        {
+            return name
+                .and_then(|name| self.registry.language(&name))
+                .map(|language| (language, "contents, as read by an earlier run".to_string()));
+        }
+
+        let classified: Option<(&Language, String)> =
+            sniff(path).and_then(|head| self.registry.classify_content(&head));
+        results.classifications.insert(
+            path,
+            stamp,
+            classified
+                .as_ref()
+                .map(|(language, _)| language.name.clone()),
+        );
+        classified
+    }
+
+    /// `line` made readable for a finding: tabs expanded, outer whitespace
+    /// trimmed, cut down around `keyword` when long, and with `keyword`
+    /// highlighted in the color of its severity on terminals
+    fn preview(
+        line: &str,
+        keyword: &str,
+        matching: Matching,
+        highlight: Option<Severity>,
+    ) -> String {
+        let line: Cow<str> = text::nfc(line);
+        let mut expanded: String = String::with_capacity(line.len());
+        let mut column: usize = 0;
+        for c in line.chars() {
+            match c {
+                '\t' => {
+                    let width: usize = Self::TAB_WIDTH - column % Self::TAB_WIDTH;
+                    expanded.extend(std::iter::repeat_n(' ', width));
+                    column += width;
+                }
+                _ => {
+                    expanded.push(c);
+                    column += 1;
+                }
+            }
+        }
+
+        let chars: Vec<char> = expanded.trim().chars().collect();
+        let (keyword_start, keyword_length): (usize, usize) =
+            match matching.find(expanded.trim(), keyword) {
+                Some(found) => (
+                    expanded.trim()[..found.start].chars().count(),
+                    expanded.trim()[found].chars().count(),
+                ),
+                None => (0, keyword.chars().count()),
+            };
+
+        let (start, end): (usize, usize) = match chars.len() > Self::PREVIEW_WIDTH {
+            true => {
+                let start: usize = keyword_start
+                    .saturating_sub(Self::PREVIEW_WIDTH.saturating_sub(keyword_length) / 2)
+                    .min(chars.len() - Self::PREVIEW_WIDTH);
+                (start, start + Self::PREVIEW_WIDTH)
+            }
+            false => (0, chars.len()),
+        };
+
+        let text = |from: usize, to: usize| -> String {
+            chars[from.clamp(start, end)..to.clamp(start, end)]
+                .iter()
+                .collect()
+        };
+        let keyword_end: usize = keyword_start + keyword_length;
+        let (before, matched, after): (String, String, String) = (
+            text(start, keyword_start),
+            text(keyword_start, keyword_end),
+            text(keyword_end, end),
+        );
+
+        format!(
+            "{}{}{}{}{}",
+            match start > 0 {
+                true => "…",
+                false => "",
             },
-            None => match file.file_name()?.to_str() {
-                Some("Makefile") => Some(FileType::Makefile {
-                    inline_comment_format: Some("#"),
-                    multiline_comment_start_format: None,
-                    multiline_comment_end_format: None,
-                }),
-                None => None,
-                _ => None,
+            before,
+            match highlight {
+                Some(severity) if !matched.is_empty() => {
+                    format!("{}{}\x1b[0m", severity.ansi_color(), matched)
+                }
+                _ => matched,
             },
-        };
+            after,
+            match end < chars.len() {
+                true => "…",
+                false => "",
+            }
+        )
+    }
+
+    /// Linter directives and editor modelines (`vim: set ts=4:`,
+    /// `-*- mode: c -*-`)
+    fn is_directive_comment(comment: &str) -> bool {
+        if Self::DIRECTIVE_MARKERS
+            .iter()
+            .any(|marker| comment.contains(marker))
+        {
+            return true;
+        }
+
+        if comment.matches("-*-").count() >= 2 {
+            return true;
+        }
+
+        // vim modelines need a marker word followed by at least one option
+        let has_modeline_marker: bool = comment.split_whitespace().any(|word| {
+            Self::MODELINE_MARKERS
+                .iter()
+                .any(|marker| word.starts_with(marker))
+        });
+        has_modeline_marker && (comment.contains('=') || comment.contains(" set "))
     }
 
     fn process_line(
         &self,
         line: &str,
-        filetype: &FileType,
-        file_path: &Path,
-        in_multiline_comment: &mut bool,
+        language: &Language,
+        scope: &Scope,
+        state: &mut CommentState,
+        file_report: &mut FileReport,
+        results: &mut Results,
     ) {
-        if line.is_empty() {
+        if line.trim().is_empty() {
+            file_report.line_counts.blank += 1;
+            state.comment_run = 0;
             return;
         }
+        let line_number: usize = file_report.line_count + 1;
 
-        let (inline_comment_format, multiline_comment_start_format, multiline_comment_end_format) =
-            destructure_filetype!(filetype);
+        // TODO(SEP): There should be 1 of these
+        /* HACK(SEP): even in multiline comments
+        /* */ FIXME(SEP): This should be caught even with moronic comment style
+         */ // BUG(SEP): Even when the comments are weird as hell
 
-        let multiline_start_position: Option<usize> = match multiline_comment_start_format {
-            None => None,
-            Some(comment_pattern) => line.find(comment_pattern),
-        };
+        let open_block: Option<OpenComment> = state.open;
+        let scanned: comment::Line = comment::Line::scan(language, line, open_block);
+        let with_markers = |comment: &Comment| -> &str { text::slice(line, comment.range.clone()) };
+        let comment_text = |comment: &Comment| -> &str { text::slice(line, comment.text.clone()) };
 
-        let multiline_end_position: Option<usize> = match multiline_comment_end_format {
-            None => None,
-            Some(comment_pattern) => line.rfind(comment_pattern),
+        // Only comments, or comments around nothing but whitespace
+        let is_comment: bool = !scanned.has_code;
+        match is_comment {
+            true => file_report.line_counts.comment += 1,
+            false => file_report.line_counts.code += 1,
+        }
+        if let Some(functions) = file_report
+            .health
+            .as_mut()
+            .and_then(|health| health.functions.as_mut())
+            && !is_comment
+            && open_block.is_none()
+            && language.declares_function(&scanned.code(line))
+        {
+            *functions += 1;
+        }
+
+        let was_in_doc_comment: bool = open_block.is_some() && state.in_doc_comment;
+        let starts_doc_comment: bool = scanned
+            .comments
+            .iter()
+            .find(|comment| !comment.continued)
+            .is_some_and(|comment| language.is_doc_comment(with_markers(comment)));
+        if !language.doc_comment_before.is_empty() {
+            let is_inline_comment: bool = is_comment
+                && !was_in_doc_comment
+                && !starts_doc_comment
+                && scanned
+                    .comments
+                    .iter()
+                    .all(|comment| comment.block.is_none());
+            match (is_inline_comment, language.is_documented_declaration(line)) {
+                (true, _) => state.comment_run += 1,
+                (false, true) => Self::document(
+                    file_report,
+                    results,
+                    line_number - std::mem::take(&mut state.comment_run),
+                ),
+                (false, false) => state.comment_run = 0,
+            }
+        }
+
+        // A comment left open is documentation if it opened as such, or was
+        // already open as such
+        state.in_doc_comment = match (scanned.open, scanned.comments.last()) {
+            (None, _) => false,
+            (Some(_), Some(last)) if !last.continued => language.is_doc_comment(with_markers(last)),
+            (Some(_), _) => was_in_doc_comment,
         };
+        state.open = scanned.open;
 
-        let comment_position: Option<usize> = match inline_comment_format {
-            None => None,
-            Some(comment_pattern) => line.find(comment_pattern),
+        let comment_portion: Cow<str> = match scanned.comments.as_slice() {
+            [] => return,
+            [comment] => Cow::Borrowed(comment_text(comment)),
+            comments => Cow::Owned(
+                comments
+                    .iter()
+                    .map(comment_text)
+                    .collect::<Vec<&str>>()
+                    .join(" "),
+            ),
         };
 
-        // TODO(SEP): There should be 1 of these
-        /* HACK(SEP): even in multiline comments
-        /* */ FIXME(SEP): This should be caught even with moronic comment style
-         */ // BUG(SEP): Even when the comments are weird as hell
+        let is_doc_comment: bool = was_in_doc_comment || starts_doc_comment;
+        if is_doc_comment {
+            file_report.doc_comment_line_count += 1;
+        }
 
-        let comment_portion: &str = match (
-            multiline_start_position,
-            multiline_end_position,
-            comment_position,
-            *in_multiline_comment,
-        ) {
-            (None, None, None, false) => return, // Should be impossible
-            (Some(_), Some(_), None, true) => line,
-            (_, None, _, true) => line,
-            (Some(_), Some(_), Some(_), true) => line,
-
-            (Some(multi_left), None, None, false) => {
-                *in_multiline_comment = true;
-                &line[multi_left..]
-            }
-
-            (None, Some(multi_right), None, _) => {
-                *in_multiline_comment = false;
-                &line[..multi_right]
-            }
-            (None, Some(multi_right), Some(comment_start), _) => {
-                *in_multiline_comment = false;
-                match multi_right < comment_start {
-                    true => &(line[..multi_right].to_string() + &line[comment_start..]),
-                    false => &line[..multi_right],
+        if comment_portion.contains(Self::IGNORE_FILE_DIRECTIVE) {
+            state.ignore_file = true;
+        }
+        let ignored: bool = comment_portion.contains(Self::IGNORE_DIRECTIVE);
+
+        if Self::is_directive_comment(&comment_portion) {
+            return;
+        }
+
+        for keyword in scope.keywords() {
+            let matching: Matching = self.matching.of(keyword, &self.keyword_matching);
+            // Each comment is searched on its own, so a marker's message
+            // doesn't run into the next comment. Keywords are composed too,
+            // so they match however the text was typed
+            let found: Option<(&Comment, Cow<str>)> = scanned.comments.iter().find_map(|comment| {
+                let composed: Cow<str> = text::nfc(comment_text(comment));
+                matching
+                    .is_match(&composed, keyword)
+                    .then_some((comment, composed))
+            });
+            if let Some((comment, composed)) = found {
+                if ignored {
+                    results.suppressed_count += 1;
+                    continue;
                 }
-            }
-            (Some(multi_left), None, Some(comment_start), false) => {
-                *in_multiline_comment = true;
-                match multi_left < comment_start {
-                    true => &line[multi_left..],
-                    false => &(line[..comment_start].to_string() + &line[multi_left..]),
+
+                let fingerprint: String = dismissed::fingerprint(
+                    file_report
+                        .path
+                        .strip_prefix(&self.anchor)
+                        .unwrap_or(&file_report.path),
+                    keyword,
+                    line,
+                );
+
+                if self.dismissed.contains(&fingerprint) {
+                    results.dismissed_count += 1;
+                    continue;
                 }
-            }
 
-            (Some(multi_left), Some(multi_right), None, false) => {
-                if multi_left < multi_right {
-                    &line[multi_left..multi_right]
-                } else {
-                    line
+                if self.baseline.contains(&fingerprint) {
+                    results.baselined_count += 1;
+                    continue;
                 }
-            }
 
-            (Some(_multi_left), Some(_multi_right), Some(_comment_start), false) => {
+                let (assignee, message): (Option<String>, String) =
+                    Occurrence::parse_marker(&composed, keyword, matching);
+
+                results.increment_keyword(keyword, is_doc_comment);
+                *file_report
+                    .keyword_table
+                    .entry(keyword.to_string())
+                    .or_insert(0) += 1;
+                let byte_offset: usize = comment.text.start
+                    + matching
+                        .find(comment_text(comment), keyword)
+                        .map_or(0, |found| found.start);
+                let column: usize = text::slice(line, 0..byte_offset).chars().count() + 1;
+                let severity: Severity = Severity::of(keyword, scope.severities());
+                file_report.occurrences.push(Occurrence {
+                    line: line_number,
+                    column,
+                    byte_offset,
+                    keyword: keyword.to_string(),
+                    severity,
+                    snippet: line.trim().to_string(),
+                    in_doc_comment: is_doc_comment,
+                    fingerprint: fingerprint.clone(),
+                    author: None,
+                    authored: None,
+                    escalated_from: None,
+                    assignee,
+                    message,
+                    language: file_report.file_type.clone(),
+                    file_bytes: file_report.bytes,
+                    classified_by: file_report.classified_by.clone(),
+                });
+
                 if self.verbose {
-                    eprintln!(
-                        "WARNING: 
-                          This is a complex comment and parsing it is not yet implemented: {:?}",
-                        line
+                    let message = format!(
+                        "{} Found!\nFile: {:?}\nLine {}, column {}: {}\nFingerprint: {}\n",
+                        keyword,
+                        file_report.path,
+                        line_number,
+                        column,
+                        Self::preview(line, keyword, matching, self.highlight.then_some(severity)),
+                        fingerprint
                     );
+                    match self.format.is_machine_readable() {
+                        true => eprintln!("{}", message),
+                        false => println!("{}", message),
+                    }
                 }
-                line
             }
+        }
+    }
 
-            (None, None, Some(comment_start), false) => &line[comment_start..],
+    /// What `--health` measures of a file of `language`, before reading it
+    fn health(&self, language: &Language) -> Option<FileHealth> {
+        self.health.then(|| FileHealth {
+            functions: (!language.functions.is_empty()).then_some(0),
+            ..FileHealth::default()
+        })
+    }
+
+    /// Whether a file of `language` is checked for a license header, as
+    /// unchecked as None. Files that can't have comments can't have one
+    fn license_header(&self, language: &Language) -> Option<bool> {
+        let has_comments: bool =
+            !language.inline_comments.is_empty() || !language.block_comments.is_empty();
+        (self.license.is_some() && has_comments).then_some(false)
+    }
+
+    /// Count the comment lines from `first_line` up to this one as
+    /// documentation after all, along with the keywords found in them
+    fn document(file_report: &mut FileReport, results: &mut Results, first_line: usize) {
+        let line_number: usize = file_report.line_count + 1;
+        file_report.doc_comment_line_count += line_number - first_line;
+        for occurrence in file_report
+            .occurrences
+            .iter_mut()
+            .filter(|occurrence| occurrence.line >= first_line && !occurrence.in_doc_comment)
+        {
+            occurrence.in_doc_comment = true;
+            results.decrement_keyword(&occurrence.keyword, false);
+            results.increment_keyword(&occurrence.keyword, true);
+        }
+    }
+
+    /// Whether `line`, the first of a file, names the interpreter to run it
+    /// with. Rust's `#![...]` attributes start with `#!` too, but are code
+    fn is_shebang(line: &str) -> bool {
+        line.starts_with("#!/") || line.starts_with("#! /")
+    }
+
+    /// Count and search the next line of a file
+    fn scan_line(
+        &self,
+        line: &str,
+        language: &Language,
+        scope: &Scope,
+        state: &mut CommentState,
+        file_report: &mut FileReport,
+        results: &mut Results,
+    ) {
+        let is_shebang: bool = file_report.line_count == 0 && Self::is_shebang(line);
+        if let Some(health) = &mut file_report.health {
+            health.measure(line, Self::TAB_WIDTH);
+        }
+        if let (Some(hygiene), Some(max_line_length)) =
+            (&mut file_report.hygiene, self.max_line_length)
+        {
+            hygiene.measure(line, max_line_length);
+        }
+        if let Some(license) = &self.license
+            && license.is_header(file_report.line_count + 1, line)
+        {
+            file_report.license_header = Some(true);
+        }
+
+        if is_shebang {
+            file_report.line_counts.comment += 1;
+        } else {
+            self.process_line(line, language, scope, state, file_report, results);
+        }
+
+        file_report.line_count += 1;
+    }
+
+    /// Scan `source` as if it were the file `path`, along with how each
+    /// line was counted and where its comments are. None when no language
+    /// recognizes the file. For checking language definitions
+    pub(crate) fn scan_source(
+        &self,
+        path: &Path,
+        source: &str,
+    ) -> Option<(&Language, FileReport, Vec<SourceLine>)> {
+        let (language, classified_by): (&Language, String) = match self.registry.classify(path) {
+            Some(classified) => classified,
+            None => self.registry.classify_content(source)?,
         };
 
-        for keyword in Self::KEY_COMMENTS {
-            if comment_portion.contains(keyword) {
-                {
-                    *self.line_count.lock().unwrap() += 1;
+        let mut file_report: FileReport =
+            self.file_report(path, language, classified_by, source.len() as u64);
+
+        let mut results: Results = Results::default();
+        let mut state: CommentState = CommentState::default();
+        let mut lines: Vec<SourceLine> = Vec::new();
+        let scope: Scope = Scope::detached(&self.keywords, &self.severities);
+        for line in source.lines() {
+            let before: LineCounts = file_report.line_counts;
+            let open: Option<OpenComment> = state.open;
+            let is_shebang: bool = file_report.line_count == 0 && Self::is_shebang(line);
+            self.scan_line(
+                line,
+                language,
+                &scope,
+                &mut state,
+                &mut file_report,
+                &mut results,
+            );
+            let after: LineCounts = file_report.line_counts;
+            let kind: LineKind = match (after.code > before.code, after.comment > before.comment) {
+                (true, _) => LineKind::Code,
+                (false, true) => LineKind::Comment,
+                (false, false) => LineKind::Blank,
+            };
+            let comments: Vec<(usize, usize)> = match is_shebang || kind == LineKind::Blank {
+                true => Vec::new(),
+                false => comment::Line::scan(language, line, open)
+                    .comments
+                    .iter()
+                    .map(|comment| comment.columns(line))
+                    .collect(),
+            };
+            lines.push(SourceLine { kind, comments });
+        }
+
+        if state.ignore_file {
+            Self::suppress_file(&mut file_report, &mut results);
+        }
+        Some((language, file_report, lines))
+    }
+
+    /// Classify and parse `path` the way a scan would, recording how every
+    /// line was read. Findings a `pursue:ignore-file` directive leaves out
+    /// are kept, so they can be shown
+    pub(crate) fn explain(&mut self, path: &Path) -> Result<FileTrace, std::io::Error> {
+        self.load_lists()?;
+        let root_scope: Arc<Scope> = Scope::root(
+            &self.keywords,
+            &self.severities,
+            self.default_ignores,
+            &self.root_directory,
+            &self.excludes,
+        )?;
+        let scope: Arc<Scope> = self.scope_for(&root_scope, path, &mut Results::default())?;
+
+        let (language, classified_by): (&Language, String) =
+            self.registry.classify_file(path).ok_or_else(|| {
+                std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{} is of no known language", path.display()),
+                )
+            })?;
+        let contents: Vec<u8> = std::fs::read(path)?;
+        if bytes_encoding(&contents).is_none() {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} is binary", path.display()),
+            ));
+        }
+
+        let mut file_report: FileReport =
+            self.file_report(path, language, classified_by, contents.len() as u64);
+        let mut results: Results = Results::default();
+        let mut state: CommentState = CommentState::default();
+        let mut lines: Vec<LineTrace> = Vec::new();
+        for line in Self::split_lines(&Self::decode(&contents)) {
+            let line: Cow<str> = String::from_utf8_lossy(line);
+            let open: Option<OpenComment> = state.open;
+            let before: (LineCounts, usize, Results) = (
+                file_report.line_counts,
+                file_report.doc_comment_line_count,
+                Results {
+                    suppressed_count: results.suppressed_count,
+                    dismissed_count: results.dismissed_count,
+                    baselined_count: results.baselined_count,
+                    ..Results::default()
+                },
+            );
+            let is_shebang: bool = file_report.line_count == 0 && Self::is_shebang(&line);
+            self.scan_line(
+                &line,
+                language,
+                &scope,
+                &mut state,
+                &mut file_report,
+                &mut results,
+            );
+
+            let counts: LineCounts = file_report.line_counts;
+            let kind: &str = match (
+                counts.code > before.0.code,
+                counts.comment > before.0.comment,
+            ) {
+                (true, _) => "code",
+                (false, true) => "comment",
+                (false, false) => "blank",
+            };
+            // Go style languages count the comments right above a
+            // declaration as documentation once the declaration is read
+            let documented: usize = file_report.doc_comment_line_count - before.1;
+            let documentation: bool = kind == "comment" && documented > 0;
+            if kind != "comment" {
+                for earlier in lines.iter_mut().rev().take(documented) {
+                    earlier.documentation = true;
                 }
+            }
 
-                self.increment_keyword(keyword);
+            let scanned: comment::Line = match is_shebang || kind == "blank" {
+                true => comment::Line::default(),
+                false => comment::Line::scan(language, &line, open),
+            };
+            let comments: Vec<CommentTrace> = scanned
+                .comments
+                .iter()
+                .map(|comment| CommentTrace {
+                    columns: comment.columns(&line),
+                    marker: match comment.block {
+                        Some(block) => language.block_comments[block].0.clone(),
+                        None => {
+                            text::slice(&line, comment.range.start..comment.text.start).to_string()
+                        }
+                    },
+                    continued: comment.continued,
+                    left_open: scanned.open.is_some()
+                        && comment.range.end == line.len()
+                        && comment.block.is_some(),
+                    text: text::slice(&line, comment.text.clone()).to_string(),
+                })
+                .collect();
 
-                if self.verbose {
-                    println!(
-                        "{} Found!\nFile: {:?}\nLine: {}\n",
-                        keyword, file_path, line
-                    );
+            let mut skipped: Vec<String> = Vec::new();
+            if is_shebang {
+                skipped.push("a #! line is a comment that is never searched".to_string());
+            }
+            for (count, before, why) in [
+                (
+                    results.suppressed_count,
+                    before.2.suppressed_count,
+                    "left out by pursue:ignore",
+                ),
+                (
+                    results.dismissed_count,
+                    before.2.dismissed_count,
+                    "dismissed",
+                ),
+                (
+                    results.baselined_count,
+                    before.2.baselined_count,
+                    "in the baseline",
+                ),
+            ] {
+                if count > before {
+                    skipped.push(match count - before {
+                        1 => format!("1 finding {}", why),
+                        skipped => format!("{} findings {}", skipped, why),
+                    });
                 }
             }
+            let interiors: Vec<&str> = scanned
+                .comments
+                .iter()
+                .map(|comment| text::slice(&line, comment.text.clone()))
+                .collect();
+            if !interiors.is_empty() && Self::is_directive_comment(&interiors.join(" ")) {
+                skipped.push("a tool directive, whose keywords are about the tool".to_string());
+            }
+
+            lines.push(LineTrace {
+                line: file_report.line_count,
+                kind: kind.to_string(),
+                inside: open
+                    .map(|open| (language.block_comments[open.block].0.clone(), open.depth)),
+                documentation,
+                comments,
+                skipped,
+                findings: Vec::new(),
+                source: line.into_owned(),
+            });
         }
+
+        for occurrence in file_report.occurrences {
+            lines[occurrence.line - 1].findings.push(occurrence);
+        }
+        Ok(FileTrace {
+            path: path.to_path_buf(),
+            language: language.name.clone(),
+            classified_by: file_report.classified_by,
+            inline_comments: language.inline_comments.clone(),
+            block_comments: language.block_comments.clone(),
+            nesting: language.nesting_openers(),
+            doc_comments: language.doc_comments.clone(),
+            strings: language.strings.clone(),
+            raw_strings: language.raw_strings.clone(),
+            keywords: scope
+                .keywords()
+                .iter()
+                .map(|keyword| keyword.to_string())
+                .collect(),
+            ignore_file: state.ignore_file,
+            lines,
+        })
     }
 
-    fn parse_file(&self, file_path: &Path) {
+    fn parse_file(&self, file_path: &Path, scope: &Scope, results: &mut Results) {
         // println!("Parsing File: {:?}", file);
 
-        let file_type = match Self::classify_file(file_path) {
-            Some(t) => t,
-            None => return,
+        // Never counted, or it would differ from one run to the next
+        if self
+            .scanned
+            .as_ref()
+            .is_some_and(|scanned| scanned.is_saved_to(file_path))
+        {
+            return;
+        }
+        if self.archives
+            && let Some(format) = archive::Format::of(file_path)
+        {
+            self.parse_archive(file_path, format, scope, results);
+            return;
+        }
+        let metadata: Option<Metadata> = std::fs::metadata(file_path).ok();
+        let bytes: u64 = metadata.as_ref().map_or(0, Metadata::len);
+        if self
+            .max_filesize
+            .is_some_and(|max_filesize| bytes > max_filesize)
+        {
+            results.oversized_file_count += 1;
+            return;
+        }
+        if has_binary_extension(file_path) {
+            results.binary_file_count += 1;
+            return;
+        }
+        let stamp: Option<Stamp> = match self.scanned {
+            Some(_) => metadata.as_ref().map(Stamp::of),
+            None => None,
         };
+        if let Some(stamp) = stamp
+            && self.reuse(file_path, stamp, scope, results)
+        {
+            return;
+        }
 
-        self.increment_filetype_frequency(&file_type);
+        let (language, classified_by): (&Language, String) = match self.classify(file_path, results)
+        {
+            Some(classified) => classified,
+            None => {
+                results.unrecognized_file_count += 1;
+                return;
+            }
+        };
+        // Only files of known languages are checked, e.g. a `.ts` video
+        let (encoding, byte_order_mark): (Encoding, usize) = match text_encoding(file_path) {
+            Some(encoding) => encoding,
+            None => {
+                results.binary_file_count += 1;
+                return;
+            }
+        };
 
-        if let (None, None, None) = destructure_filetype!(file_type) {
-            return; // Count file but don't waste time parsing it's lines
-        }
+        let mut file_report: FileReport =
+            self.file_report(file_path, language, classified_by, bytes);
 
         let file = match File::open(file_path) {
             Ok(f) => f,
-            Err(_) => return,
+            Err(error) => {
+                results.warn(file_path, format!("could not open: {}", error));
+                return;
+            }
         };
+        results.increment_filetype(&language.name);
 
-        let file_reader: BufReader<File> = BufReader::new(file);
-        let mut in_multiline_comment: bool = false;
+        let left_out: (usize, usize, usize) = (
+            results.suppressed_count,
+            results.dismissed_count,
+            results.baselined_count,
+        );
+        let mut state: CommentState = CommentState::default();
+        let mut content_hash: u64 = FNV_OFFSET_BASIS;
+        // A UTF-8 byte order mark is no part of the first line. UTF-16 ones
+        // are dropped before decoding
+        let mut skipped: usize = match encoding {
+            Encoding::Utf8 => byte_order_mark,
+            Encoding::Utf16Le | Encoding::Utf16Be => 0,
+        };
+        let mut handle_line = |line: &[u8]| {
+            let line: &[u8] = &line[std::mem::take(&mut skipped).min(line.len())..];
+            content_hash = fnv1a(content_hash, line);
+            content_hash = fnv1a(content_hash, b"\n");
 
-        for line in file_reader.lines() {
-            self.process_line(
-                match &line {
-                    Ok(good_line) => good_line,
-                    Err(_) => "",
-                },
-                &file_type,
-                file_path,
-                &mut in_multiline_comment,
+            // Invalid UTF-8 is replaced rather than dropped, so keywords
+            // next to it are still found
+            self.scan_line(
+                &String::from_utf8_lossy(line),
+                language,
+                scope,
+                &mut state,
+                &mut file_report,
+                results,
             );
+        };
 
-            {
-                *self.line_count.lock().unwrap() += 1;
+        // Mapping only pays for itself on big regular files, and may not be
+        // possible at all, so everything else is read through a buffer
+        let map: Option<Map> = match file
+            .metadata()
+            .is_ok_and(|metadata| metadata.is_file() && metadata.len() >= Self::MAP_THRESHOLD)
+        {
+            true => Map::new(&file).ok(),
+            false => None,
+        };
+
+        let read: Result<(), std::io::Error> = match (encoding, map) {
+            (Encoding::Utf16Le | Encoding::Utf16Be, _) => std::fs::read(file_path).map(|bytes| {
+                let decoded: String =
+                    text::decode_utf16(&bytes[byte_order_mark.min(bytes.len())..], encoding);
+                for line in decoded.lines() {
+                    handle_line(line.as_bytes());
+                }
+            }),
+            (Encoding::Utf8, Some(map)) => {
+                for line in map.lines() {
+                    handle_line(line);
+                }
+                Ok(())
+            }
+            (Encoding::Utf8, None) => Self::read_lines(file_path, file, &mut handle_line),
+        };
+
+        match read {
+            Ok(()) => file_report.content_hash = Some(to_hex(content_hash)),
+            Err(error) => {
+                results.warn(
+                    file_path,
+                    format!(
+                        "could not finish reading: {}, findings up to line {} are kept",
+                        error, file_report.line_count
+                    ),
+                );
+                file_report.partial = true;
             }
         }
+
+        if state.ignore_file {
+            Self::suppress_file(&mut file_report, results);
+        }
+        if let Some(stamp) = stamp
+            && !file_report.partial
+        {
+            results.scanned.insert(Scanned::new(
+                stamp,
+                scope
+                    .keywords()
+                    .iter()
+                    .map(|keyword| keyword.to_string())
+                    .collect(),
+                scope.severities().clone(),
+                file_report.clone(),
+                (
+                    results.suppressed_count - left_out.0,
+                    results.dismissed_count - left_out.1,
+                    results.baselined_count - left_out.2,
+                ),
+            ));
+        }
+        // Whole files at a time, since a directive further down can still
+        // suppress what was found
+        self.sinks.file(&file_report);
+        results.files.push(file_report);
+    }
+
+    /// Count the report the result cache has of `path`, if the file is
+    /// unchanged since it was saved
+    fn reuse(&self, path: &Path, stamp: Stamp, scope: &Scope, results: &mut Results) -> bool {
+        let keywords: Vec<String> = scope
+            .keywords()
+            .iter()
+            .map(|keyword| keyword.to_string())
+            .collect();
+        // Files touched but not changed, e.g. by switching branches and
+        // back, are read to be sure, which still beats parsing them
+        let hash = || {
+            let contents: Vec<u8> = std::fs::read(path).ok()?;
+            let decoded: Cow<[u8]> = Self::decode(&contents);
+            Some(to_hex(
+                Self::split_lines(&decoded)
+                    .iter()
+                    .fold(FNV_OFFSET_BASIS, |hash, line| {
+                        fnv1a(fnv1a(hash, line), b"\n")
+                    }),
+            ))
+        };
+        let Some(scanned) = self.scanned.as_ref().and_then(|cache| {
+            cache.get(
+                path,
+                stamp,
+                &keywords,
+                scope.severities(),
+                hash,
+                &mut results.scanned,
+            )
+        }) else {
+            return false;
+        };
+
+        results.increment_filetype(&scanned.report.file_type);
+        for occurrence in &scanned.report.occurrences {
+            results.increment_keyword(&occurrence.keyword, occurrence.in_doc_comment);
+        }
+        results.suppressed_count += scanned.suppressed;
+        results.dismissed_count += scanned.dismissed;
+        results.baselined_count += scanned.baselined;
+        self.sinks.file(&scanned.report);
+        results.files.push(scanned.report);
+        true
+    }
+
+    /// Everything besides a file and the keywords and severities of its
+    /// directory that decides what scanning it finds. Results saved with another are
+    /// scanned again
+    fn result_signature(&self) -> String {
+        let mut dismissed: Vec<&String> = self.dismissed.iter().collect();
+        dismissed.sort();
+        let mut baseline: Vec<&String> = self.baseline.iter().collect();
+        baseline.sort();
+        let settings: String = format!(
+            "{:?}",
+            (
+                env!("CARGO_PKG_VERSION"),
+                self.registry.signature(),
+                &self.severities,
+                &self.anchor,
+                self.matching,
+                &self.keyword_matching,
+                self.health,
+                &self.license,
+                self.max_line_length,
+                dismissed,
+                baseline,
+            )
+        );
+        to_hex(fnv1a(FNV_OFFSET_BASIS, settings.as_bytes()))
+    }
+
+    /// Scan the files inside `archive_path` like files on disk, skipping
+    /// the same ones `parse_file` would
+    fn parse_archive(
+        &self,
+        archive_path: &Path,
+        format: archive::Format,
+        scope: &Scope,
+        results: &mut Results,
+    ) {
+        let read: Result<(), std::io::Error> =
+            archive::members(archive_path, format, |path, size, member| {
+                if self
+                    .max_filesize
+                    .is_some_and(|max_filesize| size > max_filesize)
+                {
+                    results.oversized_file_count += 1;
+                    return Ok(());
+                }
+                if has_binary_extension(&path) {
+                    results.binary_file_count += 1;
+                    return Ok(());
+                }
+
+                let mut contents: Vec<u8> = Vec::new();
+                member.read_to_end(&mut contents)?;
+                if let Some(mut file_report) = self.scan_contents(&path, &contents, scope, results)
+                {
+                    file_report.archive = Some(archive_path.to_path_buf());
+                    self.sinks.file(&file_report);
+                    results.files.push(file_report);
+                }
+                Ok(())
+            });
+
+        // What was scanned before the archive turned out broken is kept
+        if let Err(error) = read {
+            results.warn(
+                archive_path,
+                format!("could not read the archive: {}", error),
+            );
+        }
     }
 
-    unsafe fn unsafe_waiting_room(handle: LogWrapper) {
-        let LogWrapper(inner) = handle;
-        (*inner).waiting_room();
+    /// Scan `contents`, read for `path` from somewhere other than the file
+    /// on disk. None when it is of no known language or binary
+    fn scan_contents(
+        &self,
+        path: &Path,
+        contents: &[u8],
+        scope: &Scope,
+        results: &mut Results,
+    ) -> Option<FileReport> {
+        let Some((language, classified_by)) = self.registry.classify_bytes(path, contents) else {
+            results.unrecognized_file_count += 1;
+            return None;
+        };
+        if bytes_encoding(contents).is_none() {
+            results.binary_file_count += 1;
+            return None;
+        }
+
+        results.increment_filetype(&language.name);
+        Some(self.scan_bytes(path, contents, language, classified_by, scope, results))
+    }
+
+    /// Scan what is staged in git for the changed files under every profiled
+    /// path instead of what is on disk, leaving out what config files
+    /// exclude. For checking a commit before it is made
+    pub(crate) fn log_staged(&mut self) -> Result<Report, std::io::Error> {
+        self.load_lists()?;
+        let root_scope: Arc<Scope> = Scope::root(
+            &self.keywords,
+            &self.severities,
+            self.default_ignores,
+            &self.root_directory,
+            &self.excludes,
+        )?;
+        let mut results: Results = Results::default();
+        results.budgets.extend(root_scope.inherited_budgets());
+
+        let mut scopes: HashMap<PathBuf, Option<Arc<Scope>>> = HashMap::new();
+        for path in &self.paths {
+            for file in repository::staged_files(path)? {
+                let directory: &Path = file.parent().unwrap_or(&file);
+                let scope: Arc<Scope> =
+                    match self.tracked_scope(directory, &root_scope, &mut scopes, &mut results)? {
+                        Some(scope) if !scope.is_excluded(&file, false) => scope,
+                        _ => continue,
+                    };
+                if has_binary_extension(&file) {
+                    results.binary_file_count += 1;
+                    continue;
+                }
+
+                let contents: Vec<u8> = repository::staged_contents(&file)?;
+                if self
+                    .max_filesize
+                    .is_some_and(|max_filesize| contents.len() as u64 > max_filesize)
+                {
+                    results.oversized_file_count += 1;
+                    continue;
+                }
+                if let Some(file_report) =
+                    self.scan_contents(&file, &contents, &scope, &mut results)
+                {
+                    self.sinks.file(&file_report);
+                    results.files.push(file_report);
+                }
+            }
+        }
+
+        Ok(self.result(results))
     }
 
-    fn waiting_room(&self) {
+    /// Hand every line of `file` to `handle_line`, without its line ending.
+    /// Read errors are retried from where reading stopped with a growing
+    /// delay, since network file systems tend to recover from hiccups
+    fn read_lines(
+        file_path: &Path,
+        file: File,
+        handle_line: &mut impl FnMut(&[u8]),
+    ) -> Result<(), std::io::Error> {
+        let mut reader: BufReader<File> = BufReader::new(file);
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut offset: u64 = 0;
+        let mut retries: u32 = 0;
+
         loop {
-            {
-                let guard = self.data_condition_lock.lock().unwrap();
-                let _unused = self.data_condition.wait(guard).unwrap();
+            buffer.clear();
+            match reader.read_until(b'\n', &mut buffer) {
+                Ok(0) => return Ok(()),
+                Ok(read) => {
+                    offset += read as u64;
+                    retries = 0;
+
+                    let line: &[u8] = buffer.strip_suffix(b"\n").unwrap_or(&buffer);
+                    let line: &[u8] = line.strip_suffix(b"\r").unwrap_or(line);
+                    handle_line(line);
+                }
+                Err(error) if retries >= Self::READ_RETRIES => return Err(error),
+                Err(_) => {
+                    thread::sleep(Self::READ_RETRY_DELAY * 2u32.pow(retries));
+                    retries += 1;
+
+                    let mut file: File = File::open(file_path)?;
+                    file.seek(SeekFrom::Start(offset))?;
+                    reader = BufReader::new(file);
+                }
             }
+        }
+    }
 
-            if let Some(found_file) = self.data.lock().unwrap().pop_front() {
-                self.parse_file(&found_file);
-            } else if *self.finish_flag.read().unwrap() {
-                self.data_condition.notify_all();
-                return;
+    /// Do the most important piece of work queued, then count what it
+    /// found into the results of the thread that did it. Nothing is held
+    /// locked meanwhile, since a sink blocking on other rayon work lets
+    /// this thread pick up more of the scan
+    fn work(&self, queue: &Queue) {
+        let Some(item) = queue.items.pop() else {
+            return;
+        };
+        let mut results: Results = Results::default();
+        match item {
+            WorkItem::File(path, scope) => self.parse_file(&path, &scope, &mut results),
+            WorkItem::Directory(path, scope, depth) => {
+                if let Err(walk_error) = self.expand(&path, &scope, depth, queue, &mut results) {
+                    queue.error.lock().unwrap().get_or_insert(walk_error);
+                }
             }
         }
+        let thread: usize = rayon::current_thread_index().unwrap_or(0);
+        queue.results[thread].lock().unwrap().merge(results);
     }
 
-    fn populate_queue(&self, root: &Path) -> Result<(), std::io::Error> {
-        if root.is_dir() {
-            for entry in root.read_dir()? {
-                let entry = entry?;
-                if entry.path().is_dir() {
-                    self.populate_queue(&entry.path())?;
-                } else {
-                    self.data.lock().unwrap().push_back(entry.path());
-                    self.data_condition.notify_one();
+    /// Queue `path` to be parsed, or to be expanded if it is a directory
+    /// `depth` directories below a profiled path. Files come first within
+    /// a priority, so directories are only expanded once there is nothing
+    /// left to parse and the queue stays short however big the tree is
+    fn queue_path(
+        &self,
+        path: PathBuf,
+        is_dir: bool,
+        depth: usize,
+        scope: Arc<Scope>,
+        queue: &Queue,
+    ) {
+        let rank: usize = self.priorities.rank(&path, is_dir) * 2 + usize::from(is_dir);
+        queue.push(
+            rank,
+            match is_dir {
+                true => WorkItem::Directory(path, scope, depth),
+                false => WorkItem::File(path, scope),
+            },
+        );
+    }
+
+    /// Queue everything in `directory` that isn't excluded. Any worker can
+    /// do this, so walking the tree overlaps with parsing what was found.
+    /// What can't be listed is noted in the warnings and skipped, only a
+    /// broken config file ends the scan
+    fn expand(
+        &self,
+        directory: &Path,
+        scope: &Arc<Scope>,
+        depth: usize,
+        queue: &Queue,
+        results: &mut Results,
+    ) -> Result<(), std::io::Error> {
+        let canonical: Option<PathBuf> = match self.follow_symlinks {
+            true => match directory.canonicalize() {
+                Ok(canonical) => Some(canonical),
+                Err(error) => {
+                    results.warn(directory, format!("could not resolve: {}", error));
+                    return Ok(());
                 }
+            },
+            false => None,
+        };
+        if canonical.is_some_and(|canonical| !self.visited.lock().unwrap().insert(canonical)) {
+            if self.verbose {
+                eprintln!(
+                    "Skipping {:?}, a symlink loop or a directory already profiled",
+                    directory
+                );
             }
-        } else {
-            self.data.lock().unwrap().push_back(root.to_path_buf());
-            self.data_condition.notify_one();
+            return Ok(());
+        }
+
+        let scope: Arc<Scope> = self.enter(scope, directory, results)?;
+        let entries: ReadDir = match directory.read_dir() {
+            Ok(entries) => entries,
+            Err(error) => {
+                results.warn(directory, format!("could not list: {}", error));
+                return Ok(());
+            }
+        };
+        for entry in entries {
+            let (entry, file_type): (DirEntry, FileType) =
+                match entry.and_then(|entry| Ok((entry.file_type()?, entry))) {
+                    Ok((file_type, entry)) => (entry, file_type),
+                    Err(error) => {
+                        results.warn(directory, format!("could not list an entry: {}", error));
+                        continue;
+                    }
+                };
+            let is_symlink: bool = file_type.is_symlink();
+            let is_dir: bool = match is_symlink {
+                true => entry.path().is_dir(),
+                false => file_type.is_dir(),
+            };
+            if is_symlink && is_dir && !self.follow_symlinks {
+                continue;
+            }
+            if scope.is_excluded(&entry.path(), is_dir) {
+                continue;
+            }
+            if is_dir && self.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+                results.too_deep_directory_count += 1;
+                continue;
+            }
+
+            self.queue_path(entry.path(), is_dir, depth + 1, Arc::clone(&scope), queue);
+        }
+
+        Ok(())
+    }
+
+    /// Scope of `directory` in the scan root, built from the scopes of its
+    /// parents, which are remembered in `scopes`. None when it or a parent
+    /// is excluded
+    fn tracked_scope(
+        &self,
+        directory: &Path,
+        root_scope: &Arc<Scope>,
+        scopes: &mut HashMap<PathBuf, Option<Arc<Scope>>>,
+        results: &mut Results,
+    ) -> Result<Option<Arc<Scope>>, std::io::Error> {
+        if let Some(scope) = scopes.get(directory) {
+            return Ok(scope.clone());
+        }
+
+        let scope: Option<Arc<Scope>> = match directory.parent() {
+            Some(parent)
+                if directory != self.root_directory && parent.starts_with(&self.root_directory) =>
+            {
+                match self.tracked_scope(parent, root_scope, scopes, results)? {
+                    Some(parent_scope) if !parent_scope.is_excluded(directory, true) => {
+                        Some(self.enter(&parent_scope, directory, results)?)
+                    }
+                    _ => None,
+                }
+            }
+            _ => Some(self.enter(root_scope, directory, results)?),
+        };
+        scopes.insert(directory.to_path_buf(), scope.clone());
+        Ok(scope)
+    }
+
+    /// Queue the files git tracks under every profiled path, or only those
+    /// changed since a revision, instead of walking them, leaving out what
+    /// config files exclude
+    fn queue_tracked(
+        &self,
+        root_scope: &Arc<Scope>,
+        queue: &Queue,
+        results: &mut Results,
+    ) -> Result<(), std::io::Error> {
+        let mut scopes: HashMap<PathBuf, Option<Arc<Scope>>> = HashMap::new();
+        for path in &self.paths {
+            let files: Vec<PathBuf> = match &self.since {
+                Some(revision) => repository::changed_files(path, revision)?,
+                None => repository::tracked_files(path)?,
+            };
+            for file in files {
+                // Deleted without the deletion being staged yet
+                if !file.is_file() {
+                    continue;
+                }
+
+                let directory: &Path = file.parent().unwrap_or(&file);
+                let depth: usize = directory
+                    .strip_prefix(path)
+                    .map_or(0, |relative| relative.components().count());
+                if self.max_depth.is_some_and(|max_depth| depth > max_depth) {
+                    continue;
+                }
+
+                match self.tracked_scope(directory, root_scope, &mut scopes, results)? {
+                    Some(scope) if !scope.is_excluded(&file, false) => {
+                        self.queue_path(file, false, depth, scope, queue)
+                    }
+                    _ => continue,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Cheap summary of what a scan would walk, built from the metadata of
+    /// every path it doesn't exclude, so a change under `target/` or `.git/`
+    /// or anything an ignore file leaves out doesn't change it
+    pub(crate) fn tree_stamp(&self) -> Result<u64, std::io::Error> {
+        let root_scope: Arc<Scope> = Scope::root(
+            &self.keywords,
+            &self.severities,
+            self.default_ignores,
+            &self.root_directory,
+            &self.excludes,
+        )?;
+        let mut results: Results = Results::default();
+        let mut visited: HashSet<PathBuf> = HashSet::new();
+        let mut stamp: u64 = FNV_OFFSET_BASIS;
+        for path in &self.paths {
+            let scope: Arc<Scope> = self.scope_for(&root_scope, path, &mut results)?;
+            stamp = self.stamp_path(stamp, path, &scope, &mut visited, &mut results)?;
+        }
+        Ok(stamp)
+    }
+
+    /// `stamp` with the metadata of `path` and everything below it that
+    /// `scope` doesn't exclude hashed in
+    fn stamp_path(
+        &self,
+        mut stamp: u64,
+        path: &Path,
+        scope: &Arc<Scope>,
+        visited: &mut HashSet<PathBuf>,
+        results: &mut Results,
+    ) -> Result<u64, std::io::Error> {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return Ok(stamp);
+        };
+        stamp = fnv1a(stamp, path.as_os_str().as_encoded_bytes());
+        stamp = fnv1a(stamp, &metadata.len().to_le_bytes());
+        if let Ok(modified) = metadata.modified() {
+            let since: Duration = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+            stamp = fnv1a(stamp, &since.as_nanos().to_le_bytes());
+        }
+        if !metadata.is_dir() {
+            return Ok(stamp);
+        }
+        // Symlinks are only followed with `follow_symlinks`, and never in
+        // circles
+        if self.follow_symlinks
+            && !path
+                .canonicalize()
+                .is_ok_and(|canonical| visited.insert(canonical))
+        {
+            return Ok(stamp);
         }
 
+        let scope: Arc<Scope> = self.enter(scope, path, results)?;
+        let Ok(entries) = path.read_dir() else {
+            return Ok(stamp);
+        };
+        let mut children: Vec<(PathBuf, bool)> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let file_type: FileType = entry.file_type().ok()?;
+                let is_dir: bool = match file_type.is_symlink() {
+                    true if !self.follow_symlinks && entry.path().is_dir() => return None,
+                    true => entry.path().is_dir(),
+                    false => file_type.is_dir(),
+                };
+                Some((entry.path(), is_dir))
+            })
+            .filter(|(child, is_dir)| !scope.is_excluded(child, *is_dir))
+            .collect();
+        children.sort();
+        for (child, _) in children {
+            stamp = self.stamp_path(stamp, &child, &scope, visited, results)?;
+        }
+        Ok(stamp)
+    }
+
+    /// Read the dismissed findings and the baseline of the anchor
+    fn load_lists(&mut self) -> Result<(), std::io::Error> {
+        if self.root_directory.is_dir() {
+            self.anchor = dismissed::anchor(&self.root_directory);
+            self.dismissed = dismissed::load(&self.anchor)?;
+            if self.use_baseline {
+                self.baseline = baseline::fingerprints(&self.anchor)?;
+            }
+        }
         Ok(())
     }
 
-    pub fn log(&mut self) -> Result<(), std::io::Error> {
-        let worker_count = NonZero::new(num_cpus::get());
+    pub fn log(&mut self) -> Result<Report, std::io::Error> {
+        self.load_lists()?;
+        self.priorities = Priorities::new(&self.root_directory, &self.prioritize)?;
+        if self.use_cache {
+            self.classifications =
+                ClassificationCache::load(&self.root_directory, self.registry.signature());
+        }
+        let signature: String = self.result_signature();
+        match (&self.result_cache, &mut self.scanned) {
+            (Some(file), _) => self.scanned = Some(ResultCache::load(file, signature)),
+            (None, Some(resident)) => resident.prepare(signature),
+            (None, None) => (),
+        }
+
+        let worker_count = self.jobs.or(NonZero::new(num_cpus::get()));
         let worker_count = match worker_count {
             Some(number) => number,
             None => {
@@ -392,53 +1748,683 @@ impl<'a> Logger {
             }
         };
 
-        println!(
+        let message = format!(
             "Number of CPUs supported for Trace's file I/O: {}\n",
             worker_count
         );
+        match self.format.is_machine_readable() {
+            true => eprintln!("{}", message),
+            false => println!("{}", message),
+        }
+
+        let pool: ThreadPool = ThreadPoolBuilder::new()
+            .num_threads(worker_count.get())
+            .build()
+            .map_err(|error| std::io::Error::other(error.to_string()))?;
 
-        let mut workers: Vec<thread::JoinHandle<()>> = Vec::with_capacity(worker_count.get());
+        // Workers walk directories as well as parse files, so nothing waits
+        // for the whole tree to be listed, and take the most important work
+        // queued. The pool's scope ends once all of it is done
+        let items: WorkQueue<WorkItem> = WorkQueue::new();
+        let walk_error: Mutex<Option<std::io::Error>> = Mutex::new(None);
+        let worker_results: Vec<Mutex<Results>> = (0..worker_count.get())
+            .map(|_| Mutex::new(Results::default()))
+            .collect();
+        let this: &Logger = self;
 
-        // keep one instance of object with internal locks
-        unsafe {
-            for _ in 0..worker_count.get() {
-                let handle: LogWrapper = LogWrapper(self as *mut Logger);
-                workers.push(thread::spawn(|| {
-                    Self::unsafe_waiting_room(handle);
-                }));
+        let mut results: Results = Results::default();
+        let populated: Result<(), std::io::Error> = pool.scope(|pool| {
+            let queue: Queue = Queue {
+                pool,
+                logger: this,
+                items: &items,
+                results: &worker_results,
+                error: &walk_error,
+            };
+            Scope::root(
+                &this.keywords,
+                &this.severities,
+                this.default_ignores,
+                &this.root_directory,
+                &this.excludes,
+            )
+            .and_then(|root_scope| {
+                // Config files outside the scan root are never entered
+                results.budgets.extend(root_scope.inherited_budgets());
+                match this.git || this.since.is_some() {
+                    true => this.queue_tracked(&root_scope, &queue, &mut results),
+                    false => this.paths.iter().try_for_each(|path| {
+                        let scope: Arc<Scope> = this.scope_for(&root_scope, path, &mut results)?;
+                        this.queue_path(path.clone(), path.is_dir(), 0, scope, &queue);
+                        Ok(())
+                    }),
+                }
+            })
+        });
+        populated?;
+        for worker in worker_results {
+            results.merge(worker.into_inner().unwrap());
+        }
+        if let Some(error) = walk_error.into_inner().unwrap() {
+            return Err(error);
+        }
+
+        if let Err(error) = self.classifications.save(&results.classifications) {
+            eprintln!(
+                "WARNING: Could not save the classification cache: {}",
+                error
+            );
+        }
+        if let Some(scanned) = &mut self.scanned {
+            if let Err(error) = scanned.save(&results.scanned) {
+                eprintln!("WARNING: Could not save the result cache: {}", error);
             }
+            scanned.keep(std::mem::take(&mut results.scanned));
         }
 
-        self.populate_queue(&self.root_directory)?;
+        let mut report: Report = self.result(results);
+        if self.verify {
+            report.verification = Some(self.verify(&report.files)?);
+        }
+        Ok(report)
+    }
 
-        loop {
-            let size = match self.data.try_lock() {
-                Ok(s) => s.len(),
-                _ => continue,
-            };
+    /// `path` scanned the plain way: read whole, split into lines, and
+    /// scanned on the calling thread. None for files the scan skips reading
+    fn scan_reference(&self, path: &Path, scope: &Scope) -> Option<FileReport> {
+        let (language, classified_by): (&Language, String) = self.registry.classify_file(path)?;
+        let bytes: Vec<u8> = std::fs::read(path).ok()?;
+
+        // Only the file report is compared, what else it counts is dropped
+        Some(self.scan_bytes(
+            path,
+            &bytes,
+            language,
+            classified_by,
+            scope,
+            &mut Results::default(),
+        ))
+    }
 
-            if size == 0 {
-                break;
-            } else {
-                self.data_condition.notify_all();
+    /// Scan `contents`, the whole of the file `path`, already in memory
+    fn scan_bytes(
+        &self,
+        path: &Path,
+        contents: &[u8],
+        language: &Language,
+        classified_by: String,
+        scope: &Scope,
+        results: &mut Results,
+    ) -> FileReport {
+        let mut file_report: FileReport =
+            self.file_report(path, language, classified_by, contents.len() as u64);
+        let decoded: Cow<[u8]> = Self::decode(contents);
+
+        let mut state: CommentState = CommentState::default();
+        let mut content_hash: u64 = FNV_OFFSET_BASIS;
+        for line in Self::split_lines(&decoded) {
+            content_hash = fnv1a(content_hash, line);
+            content_hash = fnv1a(content_hash, b"\n");
+            self.scan_line(
+                &String::from_utf8_lossy(line),
+                language,
+                scope,
+                &mut state,
+                &mut file_report,
+                results,
+            );
+        }
+        file_report.content_hash = Some(to_hex(content_hash));
+        if state.ignore_file {
+            Self::suppress_file(&mut file_report, results);
+        }
+        file_report
+    }
+
+    /// `contents` of a file as UTF-8, without a byte order mark
+    fn decode(contents: &[u8]) -> Cow<'_, [u8]> {
+        let (encoding, byte_order_mark): (Encoding, usize) = Encoding::detect(contents);
+        match encoding {
+            Encoding::Utf8 => Cow::Borrowed(&contents[byte_order_mark..]),
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                Cow::Owned(text::decode_utf16(&contents[byte_order_mark..], encoding).into_bytes())
             }
         }
+    }
 
-        {
-            *self.finish_flag.write().unwrap() = true;
-            self.data_condition.notify_all();
+    /// Lines of `decoded`, without their line endings
+    fn split_lines(decoded: &[u8]) -> Vec<&[u8]> {
+        let mut lines: Vec<&[u8]> = decoded
+            .split(|byte| *byte == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+            .collect();
+        // A final line ending ends the last line rather than starting one
+        if lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
         }
+        lines
+    }
 
-        for worker in workers {
-            let _ = worker.join();
+    /// Report of `path` with nothing counted yet
+    fn file_report(
+        &self,
+        path: &Path,
+        language: &Language,
+        classified_by: String,
+        bytes: u64,
+    ) -> FileReport {
+        FileReport {
+            path: path.to_path_buf(),
+            file_type: language.name.clone(),
+            classified_by,
+            line_count: 0,
+            line_counts: LineCounts::default(),
+            bytes,
+            doc_comment_line_count: 0,
+            keyword_table: BTreeMap::new(),
+            occurrences: Vec::new(),
+            content_hash: None,
+            partial: false,
+            health: self.health(language),
+            license_header: self.license_header(language),
+            hygiene: self.max_line_length.map(|_| Hygiene::default()),
+            archive: None,
         }
+    }
 
-        self.print_result();
+    /// Scan a random sample of `files` again with `scan_reference` and
+    /// list every file where the two disagree
+    fn verify(&mut self, files: &[FileReport]) -> Result<Verification, std::io::Error> {
+        // The reference pass finds everything again, which is not news
+        self.verbose = false;
 
-        Ok(())
+        // Files read out of archives aren't on disk to be read again
+        let mut sample: Vec<&FileReport> = files
+            .iter()
+            .filter(|file| !file.partial && file.archive.is_none())
+            .collect();
+        if sample.iter().map(|file| file.bytes).sum::<u64>() > Self::VERIFY_BYTES {
+            // xorshift, seeded by the clock so that every run checks
+            // different files
+            let mut random: u64 = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+                | 1;
+            for index in (1..sample.len()).rev() {
+                random ^= random << 13;
+                random ^= random >> 7;
+                random ^= random << 17;
+                sample.swap(index, (random % (index as u64 + 1)) as usize);
+            }
+
+            let mut budget: u64 = Self::VERIFY_BYTES;
+            sample.retain(|file| match file.bytes <= budget {
+                true => {
+                    budget -= file.bytes;
+                    true
+                }
+                false => false,
+            });
+        }
+
+        let root_scope: Arc<Scope> = Scope::root(
+            &self.keywords,
+            &self.severities,
+            self.default_ignores,
+            &self.root_directory,
+            &self.excludes,
+        )?;
+        let mut discrepancies: Vec<Discrepancy> = Vec::new();
+        for file in &sample {
+            let scope: Arc<Scope> =
+                self.scope_for(&root_scope, &file.path, &mut Results::default())?;
+            let differences: Vec<String> = match self.scan_reference(&file.path, &scope) {
+                Some(reference) => Self::differences(file, &reference),
+                None => vec!["the reference pass could not read or classify it".to_string()],
+            };
+            if !differences.is_empty() {
+                discrepancies.push(Discrepancy {
+                    path: file.path.clone(),
+                    differences,
+                });
+            }
+        }
+        discrepancies.sort_by(|left, right| left.path.cmp(&right.path));
+
+        Ok(Verification {
+            files_checked: sample.len(),
+            files_total: files.len(),
+            discrepancies,
+        })
     }
+
+    /// How `reference` counted the file differently than `scanned`
+    fn differences(scanned: &FileReport, reference: &FileReport) -> Vec<String> {
+        let mut differences: Vec<String> = Vec::new();
+        let mut compare = |what: &str, scanned: String, reference: String| {
+            if scanned != reference {
+                differences.push(format!(
+                    "{}: scan {}, reference {}",
+                    what, scanned, reference
+                ));
+            }
+        };
+
+        compare(
+            "language",
+            scanned.file_type.clone(),
+            reference.file_type.clone(),
+        );
+        compare(
+            "lines",
+            scanned.line_count.to_string(),
+            reference.line_count.to_string(),
+        );
+        for (what, scanned_count, reference_count) in [
+            (
+                "code lines",
+                scanned.line_counts.code,
+                reference.line_counts.code,
+            ),
+            (
+                "comment lines",
+                scanned.line_counts.comment,
+                reference.line_counts.comment,
+            ),
+            (
+                "blank lines",
+                scanned.line_counts.blank,
+                reference.line_counts.blank,
+            ),
+            (
+                "doc comment lines",
+                scanned.doc_comment_line_count,
+                reference.doc_comment_line_count,
+            ),
+        ] {
+            compare(what, scanned_count.to_string(), reference_count.to_string());
+        }
+
+        let findings = |file: &FileReport| -> Vec<String> {
+            let mut findings: Vec<String> = file
+                .occurrences
+                .iter()
+                .map(|occurrence| format!("{} on line {}", occurrence.keyword, occurrence.line))
+                .collect();
+            findings.sort();
+            findings
+        };
+        let (scanned_findings, reference_findings) = (findings(scanned), findings(reference));
+        for finding in &scanned_findings {
+            if !reference_findings.contains(finding) {
+                differences.push(format!("{} only found by the scan", finding));
+            }
+        }
+        for finding in &reference_findings {
+            if !scanned_findings.contains(finding) {
+                differences.push(format!("{} only found by the reference", finding));
+            }
+        }
+        differences
+    }
+}
+
+/// Work of a scan in progress, along with where workers count what they
+/// find
+struct Queue<'p, 'w> {
+    pool: &'p rayon::Scope<'w>,
+    logger: &'w Logger,
+    items: &'w WorkQueue<WorkItem>,
+    /// One per thread of the pool, by its index
+    results: &'w [Mutex<Results>],
+    /// The first walk error, which ends the scan once the rest is done
+    error: &'w Mutex<Option<std::io::Error>>,
+}
+
+impl Queue<'_, '_> {
+    /// Queue `item` and have a thread of the pool do the most important
+    /// work queued by then
+    fn push(&self, rank: usize, item: WorkItem) {
+        self.items.push(rank, item);
+        let (logger, items, results, error) = (self.logger, self.items, self.results, self.error);
+        self.pool.spawn(move |pool| {
+            logger.work(&Queue {
+                pool,
+                logger,
+                items,
+                results,
+                error,
+            })
+        });
+    }
+}
+
+/// A directory to walk or a file to parse, along with the config scope it
+/// was found in
+enum WorkItem {
+    /// Along with how many directories below a profiled path it is
+    Directory(PathBuf, Arc<Scope>, usize),
+    File(PathBuf, Arc<Scope>),
 }
 
-struct LogWrapper(*mut Logger);
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::{Path, PathBuf},
+        process::Command,
+    };
 
-unsafe impl Send for LogWrapper {}
+    use crate::{
+        Options, Scanner, ScannerBuilder,
+        cache::ResultCache,
+        report::{FileReport, Report},
+        severity::Severity,
+    };
+
+    /// `source` scanned as the file `name`
+    fn scan(name: &str, source: &str) -> FileReport {
+        let options: Options = Options {
+            cache: false,
+            ..Options::default()
+        };
+        crate::scan_source(Path::new(name), source, &options)
+            .unwrap()
+            .unwrap()
+    }
+
+    /// Line, column, and keyword of every finding
+    fn findings(file_report: &FileReport) -> Vec<(usize, usize, &str)> {
+        file_report
+            .occurrences
+            .iter()
+            .map(|occurrence| {
+                (
+                    occurrence.line,
+                    occurrence.column,
+                    occurrence.keyword.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn markers_next_to_multi_byte_characters() {
+        let file_report: FileReport = scan(
+            "unicode.rs",
+            "let s = \"😀\";//😀TODO\n/*ü*/ let ß = 1; //é FIXME\n/*日本\n語*/ let x = \"/*☃\";\n",
+        );
+
+        assert_eq!(findings(&file_report), [(1, 16, "TODO"), (2, 22, "FIXME")]);
+        assert_eq!(file_report.line_counts.code, 3);
+        assert_eq!(file_report.line_counts.comment, 1);
+    }
+
+    #[test]
+    fn columns_count_characters_not_bytes() {
+        let file_report: FileReport = scan(
+            "unicode.py",
+            "größe = 'ñ'  # 日本語 TODO: 翻訳\n# ✓ e\u{301} HACK\n",
+        );
+
+        assert_eq!(findings(&file_report), [(1, 20, "TODO"), (2, 8, "HACK")]);
+    }
+
+    #[test]
+    fn keywords_in_block_comments_full_of_emoji() {
+        let file_report: FileReport = scan(
+            "emoji.c",
+            "/* 🚀🚀 BUG 🚀🚀\n 🎉 FIXME 🎉 */ int 🎉 = 0;\nint a; /*🚀*/ // TODO 🚀\n",
+        );
+
+        assert_eq!(
+            findings(&file_report),
+            [(1, 7, "BUG"), (2, 4, "FIXME"), (3, 17, "TODO")]
+        );
+        assert_eq!(file_report.line_counts.comment, 1);
+    }
+
+    #[test]
+    fn keywords_in_several_comments_on_a_line() {
+        let file_report: FileReport = scan(
+            "same_line.c",
+            "/* TODO first */ code(); /* FIXME second */\n/* a */ TODO(); /* TODO b */\n",
+        );
+
+        assert_eq!(
+            findings(&file_report),
+            [(1, 4, "TODO"), (1, 29, "FIXME"), (2, 20, "TODO")]
+        );
+        let messages: Vec<&str> = file_report
+            .occurrences
+            .iter()
+            .map(|occurrence| occurrence.message.as_str())
+            .collect();
+        assert_eq!(messages, ["first", "second", "b"]);
+    }
+
+    #[test]
+    fn inner_attributes_are_not_shebangs() {
+        let file_report: FileReport = scan(
+            "lib.rs",
+            "#![deny(missing_docs)] // TODO: document
+fn main() {}
+",
+        );
+        assert_eq!(findings(&file_report), [(1, 27, "TODO")]);
+        assert_eq!(file_report.line_counts.code, 2);
+
+        let file_report: FileReport = scan(
+            "run.sh",
+            "#!/bin/sh TODO
+echo hi # HACK
+",
+        );
+        assert_eq!(findings(&file_report), [(2, 11, "HACK")]);
+        assert_eq!(file_report.line_counts.comment, 1);
+    }
+
+    #[test]
+    fn nested_config_files_set_severities_of_their_subtree() {
+        let root: PathBuf =
+            std::env::temp_dir().join(format!("pursue-logger-{}-severities", std::process::id()));
+        let nested: PathBuf = root.join("nested");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(root.join("outer.rs"), "// TODO: outer\n").unwrap();
+        std::fs::write(nested.join("inner.rs"), "// TODO: inner\n").unwrap();
+        let config: PathBuf = nested.join(".pursue.toml");
+        std::fs::write(&config, "[severities]\nTODO = \"error\"\n").unwrap();
+
+        let scanner: Scanner = ScannerBuilder::new()
+            .paths([root.clone()])
+            .cache(false)
+            .build()
+            .unwrap();
+        let mut cache: ResultCache = ResultCache::resident();
+        let severities = |report: &Report| -> Vec<(String, Severity)> {
+            report
+                .occurrences()
+                .map(|(_, occurrence)| (occurrence.message.clone(), occurrence.severity))
+                .collect()
+        };
+
+        let report: Report = scanner.scan_with(&mut cache).unwrap();
+        assert_eq!(
+            severities(&report),
+            [
+                ("inner".to_string(), Severity::Error),
+                ("outer".to_string(), Severity::Info),
+            ]
+        );
+
+        // Results cached under the old severities are stale
+        std::fs::write(&config, "[severities]\nTODO = \"warning\"\n").unwrap();
+        let report: Report = scanner.scan_with(&mut cache).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            severities(&report),
+            [
+                ("inner".to_string(), Severity::Warning),
+                ("outer".to_string(), Severity::Info),
+            ]
+        );
+    }
+
+    #[test]
+    fn staged_scans_read_the_index() {
+        let root: PathBuf =
+            std::env::temp_dir().join(format!("pursue-logger-{}-staged", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(&root)
+                .args([
+                    "-c",
+                    "user.name=Stager",
+                    "-c",
+                    "user.email=stager@example.com",
+                ])
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        std::fs::write(root.join("committed.rs"), "fn main() {}\n").unwrap();
+        git(&["add", "-A"]);
+        git(&["commit", "-qm", "start"]);
+
+        std::fs::write(root.join("staged.rs"), "// FIXME: staged\n").unwrap();
+        git(&["add", "staged.rs"]);
+        // Neither of these is what would be committed
+        std::fs::write(root.join("staged.rs"), "// TODO: on disk\n").unwrap();
+        std::fs::write(root.join("committed.rs"), "// BUG: on disk\n").unwrap();
+
+        let report: Report = ScannerBuilder::new()
+            .paths([root.clone()])
+            .cache(false)
+            .build()
+            .unwrap()
+            .scan_staged()
+            .unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        let findings: Vec<(&str, &str)> = report
+            .occurrences()
+            .map(|(file, occurrence)| {
+                (
+                    file.file_name().unwrap().to_str().unwrap(),
+                    occurrence.message.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(findings, [("staged.rs", "staged")]);
+    }
+
+    #[test]
+    fn dismissals_hold_whichever_directory_the_scan_starts_from() {
+        let root: PathBuf =
+            std::env::temp_dir().join(format!("pursue-logger-{}-dismissed", std::process::id()));
+        let src: PathBuf = root.join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(&root)
+            .args(["init", "-q"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        std::fs::write(src.join("notes.rs"), "// TODO: first\n// FIXME: second\n").unwrap();
+        let root: PathBuf = root.canonicalize().unwrap();
+        let src: PathBuf = src.canonicalize().unwrap();
+
+        let scan = |path: &Path| -> Report {
+            ScannerBuilder::new()
+                .paths([path.to_path_buf()])
+                .cache(false)
+                .build()
+                .unwrap()
+                .scan()
+                .unwrap()
+        };
+        let fingerprint = |report: &Report, keyword: &str| -> String {
+            report
+                .occurrences()
+                .find(|(_, occurrence)| occurrence.keyword == keyword)
+                .map(|(_, occurrence)| occurrence.fingerprint.clone())
+                .unwrap()
+        };
+
+        // Dismissed from the top, honored in a scan of src/
+        let report: Report = scan(&root);
+        crate::dismissed::dismiss(
+            &crate::dismissed::anchor(&root),
+            &fingerprint(&report, "TODO"),
+            "",
+        )
+        .unwrap();
+        let report: Report = scan(&src);
+        assert_eq!(report.dismissed_count, 1);
+        assert_eq!(report.occurrences().count(), 1);
+
+        // And the other way around
+        crate::dismissed::dismiss(
+            &crate::dismissed::anchor(&src),
+            &fingerprint(&report, "FIXME"),
+            "",
+        )
+        .unwrap();
+        let report: Report = scan(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(report.dismissed_count, 2);
+        assert_eq!(report.occurrences().count(), 0);
+    }
+
+    #[test]
+    fn baselines_hold_whichever_directory_the_scan_starts_from() {
+        let root: PathBuf =
+            std::env::temp_dir().join(format!("pursue-logger-{}-baseline", std::process::id()));
+        let src: PathBuf = root.join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(&root)
+            .args(["init", "-q"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        std::fs::write(src.join("notes.rs"), "// TODO: old\n").unwrap();
+        let src: PathBuf = src.canonicalize().unwrap();
+
+        // Recorded from src/, as `pursue baseline update -d src` does
+        let anchor: PathBuf = crate::dismissed::anchor(&src);
+        let report: Report = ScannerBuilder::new()
+            .paths([src.clone()])
+            .use_baseline(false)
+            .cache(false)
+            .build()
+            .unwrap()
+            .scan()
+            .unwrap();
+        crate::baseline::save(
+            &crate::baseline::path(&anchor),
+            &crate::baseline::entries(&report, &anchor),
+        )
+        .unwrap();
+
+        std::fs::write(src.join("notes.rs"), "// TODO: old\n// TODO: new\n").unwrap();
+        let report: Report = ScannerBuilder::new()
+            .paths([anchor.clone()])
+            .cache(false)
+            .build()
+            .unwrap()
+            .scan()
+            .unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(report.baselined_count, 1);
+        let messages: Vec<&str> = report
+            .occurrences()
+            .map(|(_, occurrence)| occurrence.message.as_str())
+            .collect();
+        assert_eq!(messages, ["new"]);
+    }
+}
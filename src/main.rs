@@ -16,41 +16,1361 @@
  *  with this program. If not, see <https://www.gnu.org/licenses/>
  */
 
-mod filetype;
-mod logger;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{IsTerminal, Write},
+    num::NonZero,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
 
-use std::path::{Path, PathBuf};
+use argparse::{ArgumentParser, List, Store, StoreFalse, StoreOption, StoreTrue};
+use pursue::{
+    Options, OutputFormat, Scanner, ScannerBuilder, Threshold, baseline, config, delta, dismissed,
+    grammar, html, import, jobs,
+    license::LicenseCheck,
+    matching::Matching,
+    report::Hygiene,
+    repository, serve,
+    severity::{Escalation, Severity},
+    terminal::ColorChoice,
+    why,
+};
 
-use argparse::{ArgumentParser, Store, StoreTrue};
-use logger::Logger;
+const COPYRIGHT_NOTICE: &str = "Copyright (c) 2024 Sebastian Pineda (spineda.wpi.alum@gmail.com)
+This program is free software; you may redistribute it under the terms of the
+GNU General Public License version 2 or (at your option) any later version. This
+program has absolutely no warranty.";
+const VERSION: &str = "0.0.5";
+const COOL_NAME_ART: &str = r"
+___________
+\___   ___/___________    ____  ____
+   |   |  \_  __ \__  \ _/ ___\/ __ \
+   |   |   |  | \// __ \\  \__\  ___/
+   |___|   |__|  (____ / \_____\____|
+";
+
+fn print_version_info() {
+    println!("{}", COOL_NAME_ART);
+    println!("Trace version {}\n", VERSION);
+    println!("{}\n", COPYRIGHT_NOTICE);
+}
+
+fn config_command(arguments: Vec<String>) -> Result<(), std::io::Error> {
+    let mut action: String = String::new();
+    let mut directory: String = String::from(".");
+    let mut path: String = String::new();
+    let mut effective: bool = false;
+    let mut keyword_list: String = String::new();
+    let mut format: Option<OutputFormat> = None;
+
+    {
+        let mut argument_parser: ArgumentParser = ArgumentParser::new();
+        argument_parser.set_description("Inspect pursue configuration files");
+
+        argument_parser.refer(&mut action).required().add_argument(
+            "action",
+            Store,
+            "check: validate every config file, show: print the config files in use",
+        );
+
+        argument_parser.refer(&mut directory).add_option(
+            &["-d", "--directory"],
+            Store,
+            "Directory that would be profiled",
+        );
+
+        argument_parser.refer(&mut path).add_option(
+            &["-p", "--path"],
+            Store,
+            "show: subdirectory to show the configuration of (default: --directory)",
+        );
+
+        argument_parser.refer(&mut effective).add_option(
+            &["--effective"],
+            StoreTrue,
+            "show: print the merged configuration annotated with where each value came from",
+        );
+
+        argument_parser.refer(&mut keyword_list).add_option(
+            &["-k", "--keywords"],
+            Store,
+            "show: keywords that would be passed on the command line",
+        );
+
+        argument_parser.refer(&mut format).add_option(
+            &["-f", "--format"],
+            StoreOption,
+            "show: output format that would be passed on the command line",
+        );
+
+        if let Err(code) =
+            argument_parser.parse(arguments, &mut std::io::stdout(), &mut std::io::stderr())
+        {
+            std::process::exit(code);
+        }
+    }
+
+    match action.as_str() {
+        "check" => {
+            let mut failed: bool = false;
+            for (config_path, problems) in config::check_tree(Path::new(&directory))? {
+                match problems.is_empty() {
+                    true => println!("OK: {}", config_path.display()),
+                    false => {
+                        failed = true;
+                        for problem in problems {
+                            eprintln!("{}", problem);
+                        }
+                    }
+                }
+            }
+
+            if failed {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        "show" => {
+            let root: PathBuf = Path::canonicalize(Path::new(&directory))?;
+            let target: PathBuf = match path.is_empty() {
+                true => root.clone(),
+                false => Path::canonicalize(Path::new(&path))?,
+            };
+
+            match effective {
+                true => {
+                    let settings = config::Settings::resolve(&keyword_list, format)?
+                        .with_config_files(&root)?;
+                    print!("{}", settings.render_effective(&root, &target)?);
+                }
+                false => {
+                    for (_, config_path, _) in config::config_chain(&root, &target)? {
+                        println!("# {}", config_path.display());
+                        println!("{}", std::fs::read_to_string(&config_path)?);
+                    }
+                }
+            }
+            Ok(())
+        }
+        _ => {
+            eprintln!("Unknown config action {:?}, expected: check, show", action);
+            std::process::exit(2);
+        }
+    }
+}
+
+fn dismiss_command(arguments: Vec<String>) -> Result<(), std::io::Error> {
+    let mut fingerprint: String = String::new();
+    let mut directory: String = String::from(".");
+    let mut reason: String = String::new();
+
+    {
+        let mut argument_parser: ArgumentParser = ArgumentParser::new();
+        argument_parser
+            .set_description("Dismiss a finding as a false positive so future runs don't count it");
+
+        argument_parser
+            .refer(&mut fingerprint)
+            .required()
+            .add_argument(
+                "fingerprint",
+                Store,
+                "Fingerprint of the finding, as printed by --log",
+            );
+
+        argument_parser.refer(&mut directory).add_option(
+            &["-d", "--directory"],
+            Store,
+            "Directory that is profiled. The dismissed list is kept at the top of its git \
+             repository, or else in its outermost directory with a config file",
+        );
+
+        argument_parser.refer(&mut reason).add_option(
+            &["-m", "--message"],
+            Store,
+            "Why the finding was dismissed, recorded next to it",
+        );
+
+        if let Err(code) =
+            argument_parser.parse(arguments, &mut std::io::stdout(), &mut std::io::stderr())
+        {
+            std::process::exit(code);
+        }
+    }
+
+    let anchor: PathBuf = dismissed::anchor(&Path::canonicalize(Path::new(&directory))?);
+    let path: PathBuf = dismissed::dismiss(&anchor, &fingerprint, &reason)?;
+    println!("Dismissed {} in {}", fingerprint, path.display());
+    Ok(())
+}
+
+fn baseline_command(arguments: Vec<String>) -> Result<(), std::io::Error> {
+    let mut action: String = String::new();
+    let mut files: Vec<String> = Vec::new();
+    let mut directory: String = String::from(".");
+    let mut keyword_list: String = String::new();
+    let mut ignore_case: bool = false;
+    let mut whole_word: bool = false;
+
+    {
+        let mut argument_parser: ArgumentParser = ArgumentParser::new();
+        argument_parser.set_description(
+            "Manage .pursue-baseline, the findings accepted as existing debt and left out of \
+                 every scan",
+        );
+
+        argument_parser.refer(&mut action).required().add_argument(
+            "action",
+            Store,
+            "update: record every current finding, prune: drop findings that are gone, \
+             merge: three way merge BASE OURS THEIRS into OURS (usable as a git merge driver)",
+        );
+
+        argument_parser.refer(&mut files).add_argument(
+            "files",
+            List,
+            "merge: the base, ours, and theirs baselines",
+        );
+
+        argument_parser.refer(&mut directory).add_option(
+            &["-d", "--directory"],
+            Store,
+            "Directory that is profiled. The baseline is kept at the top of its git repository, \
+             or else in its outermost directory with a config file, and only its entries under \
+             this directory change",
+        );
+
+        argument_parser.refer(&mut keyword_list).add_option(
+            &["-k", "--keywords"],
+            Store,
+            "Comma separated keywords to track instead of TODO,HACK,BUG,FIXME",
+        );
+
+        argument_parser.refer(&mut ignore_case).add_option(
+            &["-i", "--ignore-case"],
+            StoreTrue,
+            "Match keywords whatever their case, so todo counts as TODO",
+        );
+
+        argument_parser.refer(&mut whole_word).add_option(
+            &["-w", "--whole-word"],
+            StoreTrue,
+            "Only match keywords as whole words, so mastodon doesn't count as TODO",
+        );
+
+        if let Err(code) =
+            argument_parser.parse(arguments, &mut std::io::stdout(), &mut std::io::stderr())
+        {
+            std::process::exit(code);
+        }
+    }
+
+    if action == "merge" {
+        let [base, ours, theirs] = files.as_slice() else {
+            eprintln!("baseline merge expects exactly three files: BASE OURS THEIRS");
+            std::process::exit(2);
+        };
+        let merged = baseline::merge(
+            &baseline::load(Path::new(base))?,
+            &baseline::load(Path::new(ours))?,
+            &baseline::load(Path::new(theirs))?,
+        );
+        return baseline::save(Path::new(ours), &merged);
+    }
+
+    let root: PathBuf = Path::canonicalize(Path::new(&directory))?;
+    let anchor: PathBuf = dismissed::anchor(&root);
+    let path: PathBuf = baseline::path(&anchor);
+    let settings = config::Settings::resolve(&keyword_list, Some(OutputFormat::Json))?
+        .with_config_files(&root)?;
+    let matching: Matching = Matching {
+        ignore_case,
+        whole_word,
+    };
+    let scanner: Scanner = ScannerBuilder::new()
+        .path(&root)
+        .keyword_matching(settings.keyword_matching(matching))
+        .matching(matching)
+        .keywords(settings.keywords.value)
+        // Machine readable so only the summary below ends up on stdout
+        .format(OutputFormat::Json)
+        .use_baseline(false)
+        .build()?;
+    let current = baseline::entries(&scanner.scan()?, &anchor);
+
+    // Entries of the rest of the project are left as they are
+    let (recorded, kept): (BTreeSet<baseline::Entry>, BTreeSet<baseline::Entry>) =
+        baseline::load(&path)?
+            .into_iter()
+            .partition(|entry| anchor.join(&entry.path).starts_with(&root));
+    let entries: BTreeSet<baseline::Entry> = match action.as_str() {
+        "update" => current.union(&kept).cloned().collect(),
+        "prune" => recorded
+            .intersection(&current)
+            .chain(&kept)
+            .cloned()
+            .collect(),
+        _ => {
+            eprintln!(
+                "Unknown baseline action {:?}, expected: update, prune, merge",
+                action
+            );
+            std::process::exit(2);
+        }
+    };
+
+    baseline::save(&path, &entries)?;
+    println!("{} findings in {}", entries.len(), path.display());
+    Ok(())
+}
+
+fn report_command(arguments: Vec<String>) -> Result<(), std::io::Error> {
+    let mut directory: String = String::from(".");
+    let mut keyword_list: String = String::new();
+    let mut ignore_case: bool = false;
+    let mut whole_word: bool = false;
+    let mut output: String = String::from("pursue-report");
+    let mut page_size: usize = html::DEFAULT_PAGE_SIZE;
+    let mut serve: bool = false;
+    let mut port: u16 = 8080;
+    let mut watch: bool = false;
+    let mut interval: u64 = 2;
+    let mut delta_log: Option<PathBuf> = None;
+    let mut default_ignores: bool = true;
+    let mut prioritize: Vec<String> = Vec::new();
+    let mut excludes: Vec<String> = Vec::new();
+
+    {
+        let mut argument_parser: ArgumentParser = ArgumentParser::new();
+        argument_parser.set_description("Write an HTML report of a directory");
+
+        argument_parser.refer(&mut directory).add_option(
+            &["-d", "--directory"],
+            Store,
+            "Directory you would like to profile",
+        );
+
+        argument_parser.refer(&mut keyword_list).add_option(
+            &["-k", "--keywords"],
+            Store,
+            "Comma separated keywords to track instead of TODO,HACK,BUG,FIXME",
+        );
+
+        argument_parser.refer(&mut ignore_case).add_option(
+            &["-i", "--ignore-case"],
+            StoreTrue,
+            "Match keywords whatever their case, so todo counts as TODO",
+        );
+
+        argument_parser.refer(&mut whole_word).add_option(
+            &["-w", "--whole-word"],
+            StoreTrue,
+            "Only match keywords as whole words, so mastodon doesn't count as TODO",
+        );
+
+        argument_parser.refer(&mut output).add_option(
+            &["-o", "--output"],
+            Store,
+            "Directory the report pages are written to (default: pursue-report)",
+        );
+
+        argument_parser.refer(&mut page_size).add_option(
+            &["--page-size"],
+            Store,
+            "Files listed per page of each language",
+        );
+
+        argument_parser.refer(&mut serve).add_option(
+            &["--serve"],
+            StoreTrue,
+            "Serve the report on localhost instead of writing it to disk",
+        );
+
+        argument_parser.refer(&mut port).add_option(
+            &["--port"],
+            Store,
+            "Port to serve the report on (default: 8080)",
+        );
+
+        argument_parser.refer(&mut watch).add_option(
+            &["--watch"],
+            StoreTrue,
+            "Regenerate the report whenever the directory changes",
+        );
+
+        argument_parser.refer(&mut interval).add_option(
+            &["--interval"],
+            Store,
+            "Seconds between checks for changes with --watch (default: 2)",
+        );
+
+        argument_parser.refer(&mut delta_log).add_option(
+            &["--delta-log"],
+            StoreOption,
+            "--watch: also append the findings added and removed by every change to this NDJSON file",
+        );
+
+        argument_parser.refer(&mut prioritize).add_option(
+            &["--prioritize"],
+            List,
+            "Scan paths matching this gitignore style pattern first, e.g. --prioritize 'src/**'. Repeat to rank several",
+        );
+
+        argument_parser.refer(&mut excludes).add_option(
+            &["--exclude"],
+            List,
+            "Skip paths matching this gitignore style pattern, relative to the scanned directory. Repeat to skip several",
+        );
+
+        argument_parser.refer(&mut default_ignores).add_option(
+            &["--no-default-ignores"],
+            StoreFalse,
+            "Also profile build output and dependency directories such as target/ and node_modules/",
+        );
+
+        if let Err(code) =
+            argument_parser.parse(arguments, &mut std::io::stdout(), &mut std::io::stderr())
+        {
+            std::process::exit(code);
+        }
+    }
+
+    let root: PathBuf = Path::canonicalize(Path::new(&directory))?;
+    let settings = config::Settings::resolve(&keyword_list, None)?.with_config_files(&root)?;
+    let matching: Matching = Matching {
+        ignore_case,
+        whole_word,
+    };
+    let scanner: Scanner = ScannerBuilder::new()
+        .path(&root)
+        .severities(settings.keyword_severities())
+        .keyword_matching(settings.keyword_matching(matching))
+        .matching(matching)
+        .keywords(settings.keywords.value)
+        // Machine readable while watching so status output of every scan
+        // stays off of stdout, between the delta summaries
+        .format(match watch {
+            true => OutputFormat::Json,
+            false => OutputFormat::Table,
+        })
+        .default_ignores(default_ignores)
+        .threads(settings.jobs.map_or(0, |jobs| jobs.value.get()))
+        .languages(settings.languages.map(|languages| languages.value))
+        .priorities(prioritize)
+        .excludes(excludes)
+        .build()?;
+
+    let interval: Duration = Duration::from_secs(interval.max(1));
+
+    // Every change after the first report is summarized as a delta
+    let tracker: Mutex<delta::Tracker> =
+        Mutex::new(delta::Tracker::new(&root, delta_log.as_deref())?);
+
+    if serve {
+        return serve::serve(port, &root, watch.then_some(interval), move || {
+            let report = scanner.scan()?;
+            let pages: Vec<html::Page> = html::render(&report, page_size);
+            tracker.lock().unwrap().record(report)?;
+            Ok(pages)
+        });
+    }
+
+    let mut first: bool = true;
+    loop {
+        let report = scanner.scan()?;
+        let index: PathBuf = html::write(&report, Path::new(&output), page_size)?;
+        if first {
+            println!("Report written to {}", index.display());
+            first = false;
+        }
+
+        if !watch {
+            return Ok(());
+        }
+        tracker.lock().unwrap().record(report)?;
+
+        // Taken after writing since the report may live inside the directory
+        let stamp: u64 = serve::tree_stamp(&root);
+        while serve::tree_stamp(&root) == stamp {
+            std::thread::sleep(interval);
+        }
+    }
+}
+
+fn daemon_command(arguments: Vec<String>) -> Result<(), std::io::Error> {
+    let mut directory: String = String::from(".");
+    let mut keyword_list: String = String::new();
+    let mut ignore_case: bool = false;
+    let mut whole_word: bool = false;
+    let mut socket: Option<PathBuf> = None;
+    let mut interval: u64 = 2;
+    let mut query: Option<String> = None;
+    let mut default_ignores: bool = true;
+    let mut excludes: Vec<String> = Vec::new();
+    let mut languages: Option<PathBuf> = None;
+
+    {
+        let mut argument_parser: ArgumentParser = ArgumentParser::new();
+        argument_parser.set_description(
+            "Keep a directory scanned, scanning only the files that change, and answer JSON queries about its findings on a Unix socket",
+        );
+
+        argument_parser.refer(&mut directory).add_option(
+            &["-d", "--directory"],
+            Store,
+            "Directory you would like to profile",
+        );
+
+        argument_parser.refer(&mut keyword_list).add_option(
+            &["-k", "--keywords"],
+            Store,
+            "Comma separated keywords to track instead of TODO,HACK,BUG,FIXME",
+        );
+
+        argument_parser.refer(&mut ignore_case).add_option(
+            &["-i", "--ignore-case"],
+            StoreTrue,
+            "Match keywords whatever their case, so todo counts as TODO",
+        );
+
+        argument_parser.refer(&mut whole_word).add_option(
+            &["-w", "--whole-word"],
+            StoreTrue,
+            "Only match keywords as whole words, so mastodon doesn't count as TODO",
+        );
+
+        argument_parser.refer(&mut socket).add_option(
+            &["--socket"],
+            StoreOption,
+            "Unix socket to listen on (default: .pursue.sock in the directory)",
+        );
+
+        argument_parser.refer(&mut interval).add_option(
+            &["--interval"],
+            Store,
+            "Seconds between checks for changes (default: 2)",
+        );
+
+        argument_parser.refer(&mut query).add_option(
+            &["--query"],
+            StoreOption,
+            "Instead of starting a daemon, send this JSON query to the one running and print its answer",
+        );
+
+        argument_parser.refer(&mut excludes).add_option(
+            &["--exclude"],
+            List,
+            "Skip paths matching this gitignore style pattern, relative to the scanned directory. Repeat to skip several",
+        );
+
+        argument_parser.refer(&mut default_ignores).add_option(
+            &["--no-default-ignores"],
+            StoreFalse,
+            "Also profile build output and dependency directories such as target/ and node_modules/",
+        );
+
+        argument_parser.refer(&mut languages).add_option(
+            &["--languages"],
+            StoreOption,
+            "TOML file of languages to add to or replace the built in ones",
+        );
+
+        if let Err(code) =
+            argument_parser.parse(arguments, &mut std::io::stdout(), &mut std::io::stderr())
+        {
+            std::process::exit(code);
+        }
+    }
+
+    let root: PathBuf = Path::canonicalize(Path::new(&directory))?;
+    let socket: PathBuf = socket.unwrap_or(root.join(".pursue.sock"));
+    serve_daemon(
+        root,
+        socket,
+        query,
+        |root: &Path| -> Result<Scanner, std::io::Error> {
+            let settings =
+                config::Settings::resolve(&keyword_list, None)?.with_config_files(root)?;
+            let matching: Matching = Matching {
+                ignore_case,
+                whole_word,
+            };
+            ScannerBuilder::new()
+                .path(root)
+                .severities(settings.keyword_severities())
+                .keyword_matching(settings.keyword_matching(matching))
+                .matching(matching)
+                .keywords(settings.keywords.value)
+                // Keeps the status output of every scan off of stdout
+                .format(OutputFormat::Json)
+                .default_ignores(default_ignores)
+                .threads(settings.jobs.map_or(0, |jobs| jobs.value.get()))
+                .languages(languages.or(settings.languages.map(|languages| languages.value)))
+                .excludes(excludes)
+                .build()
+        },
+        Duration::from_secs(interval.max(1)),
+    )
+}
+
+#[cfg(unix)]
+fn serve_daemon(
+    root: PathBuf,
+    socket: PathBuf,
+    query: Option<String>,
+    scanner: impl FnOnce(&Path) -> Result<Scanner, std::io::Error>,
+    interval: Duration,
+) -> Result<(), std::io::Error> {
+    match query {
+        Some(request) => {
+            println!("{}", pursue::daemon::query(&socket, &request)?);
+            Ok(())
+        }
+        None => pursue::daemon::run(scanner(&root)?, &root, &socket, interval),
+    }
+}
+
+#[cfg(not(unix))]
+fn serve_daemon(
+    _root: PathBuf,
+    _socket: PathBuf,
+    _query: Option<String>,
+    _scanner: impl FnOnce(&Path) -> Result<Scanner, std::io::Error>,
+    _interval: Duration,
+) -> Result<(), std::io::Error> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "pursue daemon listens on a Unix socket, which this platform doesn't have",
+    ))
+}
+
+fn merge_command(arguments: Vec<String>) -> Result<(), std::io::Error> {
+    let mut reports: Vec<String> = Vec::new();
+    let mut format: Option<OutputFormat> = None;
+
+    {
+        let mut argument_parser: ArgumentParser = ArgumentParser::new();
+        argument_parser.set_description(
+            "Merge JSON reports of several repositories, counting vendored copies of files once",
+        );
+
+        argument_parser.refer(&mut reports).required().add_argument(
+            "reports",
+            List,
+            "JSON reports, as printed by --format json",
+        );
+
+        argument_parser.refer(&mut format).add_option(
+            &["-f", "--format"],
+            StoreOption,
+            "Output format of the merged report: table (default), json, markdown, csv, tokei-json, cloc-json, html, jsonl, or sarif",
+        );
+
+        if let Err(code) =
+            argument_parser.parse(arguments, &mut std::io::stdout(), &mut std::io::stderr())
+        {
+            std::process::exit(code);
+        }
+    }
+
+    let settings = config::Settings::resolve("", format)?;
+    let reports: Vec<pursue::Report> = reports
+        .iter()
+        .map(|report| pursue::Report::load(Path::new(report)))
+        .collect::<Result<_, _>>()?;
+    pursue::Report::merge(reports).print(settings.format.value)
+}
+
+fn why_command(arguments: Vec<String>) -> Result<(), std::io::Error> {
+    let mut path: String = String::new();
+    let mut directory: String = String::from(".");
+    let mut keyword_list: String = String::new();
+    let mut default_ignores: bool = true;
+    let mut format: Option<OutputFormat> = None;
+    let mut languages: Option<PathBuf> = None;
+    let mut excludes: Vec<String> = Vec::new();
+
+    {
+        let mut argument_parser: ArgumentParser = ArgumentParser::new();
+        argument_parser.set_description(
+            "Explain how a file would be classified, which exclude rules apply to it, and how its comments are parsed",
+        );
+
+        argument_parser
+            .refer(&mut path)
+            .required()
+            .add_argument("path", Store, "File to explain");
+
+        argument_parser.refer(&mut directory).add_option(
+            &["-d", "--directory"],
+            Store,
+            "Directory that would be profiled",
+        );
+
+        argument_parser.refer(&mut keyword_list).add_option(
+            &["-k", "--keywords"],
+            Store,
+            "Keywords that would be passed on the command line",
+        );
+
+        argument_parser.refer(&mut default_ignores).add_option(
+            &["--no-default-ignores"],
+            StoreFalse,
+            "Explain as if default ignores were turned off",
+        );
+
+        argument_parser.refer(&mut excludes).add_option(
+            &["--exclude"],
+            List,
+            "Explain as if paths matching this gitignore style pattern were skipped",
+        );
+
+        argument_parser.refer(&mut languages).add_option(
+            &["--languages"],
+            StoreOption,
+            "TOML file of languages to add to or replace the built in ones",
+        );
+
+        argument_parser.refer(&mut format).add_option(
+            &["-f", "--format"],
+            StoreOption,
+            "json for machine readable output",
+        );
+
+        if let Err(code) =
+            argument_parser.parse(arguments, &mut std::io::stdout(), &mut std::io::stderr())
+        {
+            std::process::exit(code);
+        }
+    }
+
+    let root: PathBuf = Path::canonicalize(Path::new(&directory))?;
+    let settings = config::Settings::resolve(&keyword_list, format)?.with_config_files(&root)?;
+    let options: Options = Options {
+        keywords: settings.keywords.value,
+        default_ignores,
+        languages: languages.or(settings.languages.map(|languages| languages.value)),
+        excludes,
+        ..Options::default()
+    };
+
+    let path: PathBuf = Path::canonicalize(Path::new(&path))?;
+    let explanation = why::explain(&root, &path, &options)?;
+    match settings.format.value {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&explanation)?),
+        _ => print!("{}", explanation.render()),
+    }
+    Ok(())
+}
+
+fn grammar_test_command(arguments: Vec<String>) -> Result<(), std::io::Error> {
+    let mut corpus: Option<PathBuf> = None;
+    let mut languages: Option<PathBuf> = None;
+
+    {
+        let mut argument_parser: ArgumentParser = ArgumentParser::new();
+        argument_parser.set_description(
+            "Check how comments are recognized against snippets annotated with what each line should count as",
+        );
+
+        argument_parser.refer(&mut corpus).add_option(
+            &["--corpus"],
+            StoreOption,
+            "Directory of snippets, each with a .expected file next to it. Defaults to the bundled corpus",
+        );
+
+        argument_parser.refer(&mut languages).add_option(
+            &["--languages"],
+            StoreOption,
+            "TOML file of languages to add to or replace the built in ones",
+        );
+
+        if let Err(code) =
+            argument_parser.parse(arguments, &mut std::io::stdout(), &mut std::io::stderr())
+        {
+            std::process::exit(code);
+        }
+    }
+
+    let cases: Vec<grammar::Case> = match &corpus {
+        Some(directory) => grammar::load(directory)?,
+        None => grammar::bundled(),
+    };
+    let outcomes: Vec<grammar::Outcome> = grammar::run(&cases, languages.as_deref())?;
+
+    let mut by_language: BTreeMap<&str, (usize, Vec<&grammar::Outcome>)> = BTreeMap::new();
+    for outcome in &outcomes {
+        let tally = by_language
+            .entry(outcome.language.as_deref().unwrap_or("Unrecognized"))
+            .or_default();
+        match outcome.passed() {
+            true => tally.0 += 1,
+            false => tally.1.push(outcome),
+        }
+    }
+
+    for (language, (passed, failed)) in &by_language {
+        println!("{}: {} passed, {} failed", language, passed, failed.len());
+        for outcome in failed {
+            for failure in &outcome.failures {
+                println!("    {}: {}", outcome.name.display(), failure);
+            }
+        }
+    }
+
+    if outcomes.iter().any(|outcome| !outcome.passed()) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Print every budget `report` went over, returning what was printed
+fn report_exceeded_budgets(report: &pursue::Report) -> Vec<String> {
+    let exceeded: Vec<String> = report
+        .exceeded_budgets()
+        .map(|budget| {
+            format!(
+                "{} has {} {}, over its budget of {} (set in {})",
+                budget.directory.display(),
+                budget.count,
+                budget.keyword,
+                budget.max,
+                budget.source.display()
+            )
+        })
+        .collect();
+    for message in &exceeded {
+        eprintln!("BUDGET EXCEEDED: {}", message);
+    }
+    exceeded
+}
+
+fn import_command(arguments: Vec<String>) -> Result<(), std::io::Error> {
+    let mut source: Option<import::Source> = None;
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut directory: String = String::from(".");
+    let mut keyword_list: String = String::new();
+    let mut ignore_case: bool = false;
+    let mut whole_word: bool = false;
+    let mut format: Option<OutputFormat> = None;
+    let mut use_baseline: bool = true;
+
+    {
+        let mut argument_parser: ArgumentParser = ArgumentParser::new();
+        argument_parser.set_description(
+            "Profile a directory along with the findings of other tools, so one report and one set of budgets covers them all",
+        );
+
+        argument_parser.refer(&mut source).required().add_option(
+            &["--from"],
+            StoreOption,
+            "Format of the files to import: sarif",
+        );
+
+        argument_parser.refer(&mut files).required().add_argument(
+            "files",
+            List,
+            "Output of the other tools, e.g. clippy.sarif",
+        );
+
+        argument_parser.refer(&mut directory).add_option(
+            &["-d", "--directory"],
+            Store,
+            "Directory to profile, which relative paths in the imported files are resolved against",
+        );
+
+        argument_parser.refer(&mut keyword_list).add_option(
+            &["-k", "--keywords"],
+            Store,
+            "Comma separated keywords to look for instead of TODO,HACK,BUG,FIXME",
+        );
+
+        argument_parser.refer(&mut ignore_case).add_option(
+            &["-i", "--ignore-case"],
+            StoreTrue,
+            "Match keywords whatever their case, so todo counts as TODO",
+        );
+
+        argument_parser.refer(&mut whole_word).add_option(
+            &["-w", "--whole-word"],
+            StoreTrue,
+            "Only match keywords as whole words, so mastodon doesn't count as TODO",
+        );
+
+        argument_parser.refer(&mut format).add_option(
+            &["-f", "--format"],
+            StoreOption,
+            "Output format: table (default), json, markdown, csv, tokei-json, cloc-json, html, jsonl, or sarif",
+        );
+
+        argument_parser.refer(&mut use_baseline).add_option(
+            &["--no-baseline"],
+            StoreFalse,
+            "Report imported and scanned findings even if they are in .pursue-baseline",
+        );
+
+        if let Err(code) =
+            argument_parser.parse(arguments, &mut std::io::stdout(), &mut std::io::stderr())
+        {
+            std::process::exit(code);
+        }
+    }
+
+    let root: PathBuf = Path::canonicalize(Path::new(&directory))?;
+    let settings = config::Settings::resolve(&keyword_list, format)?.with_config_files(&root)?;
+    let source: import::Source = source.unwrap_or(import::Source::Sarif);
+    let matching: Matching = Matching {
+        ignore_case,
+        whole_word,
+    };
+    let scanner: Scanner = ScannerBuilder::new()
+        .path(&root)
+        .severities(settings.keyword_severities())
+        .keyword_matching(settings.keyword_matching(matching))
+        .matching(matching)
+        .keywords(settings.keywords.value)
+        .format(settings.format.value)
+        .use_baseline(use_baseline)
+        .threads(settings.jobs.map_or(0, |jobs| jobs.value.get()))
+        .languages(settings.languages.map(|languages| languages.value))
+        .build()?;
+
+    let mut report = scanner.scan()?;
+    for file in &files {
+        report.absorb(&root, import::load(source, file, &root)?, use_baseline)?;
+    }
+    report.print(scanner.options().format)?;
+
+    if !report_exceeded_budgets(&report).is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Print `question` and read a line of the answer, trimmed. Empty when
+/// the user just presses enter
+fn ask(question: &str) -> Result<String, std::io::Error> {
+    print!("{} ", question);
+    std::io::stdout().flush()?;
+    let mut answer: String = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+/// Walk a first time user through creating a config file for `root`
+fn prompt_for_config(root: &Path) -> Result<(), std::io::Error> {
+    println!("No pursue config found in {}.", root.display());
+    let create: String = ask("Create .pursue.toml now? [Y/n]")?;
+    if create.to_ascii_lowercase().starts_with('n') {
+        println!("Skipping. Pass --no-interactive to never be asked.\n");
+        return Ok(());
+    }
+
+    let default_ignores: bool =
+        !ask("Skip build output and dependency directories like target/ and node_modules/? [Y/n]")?
+            .to_ascii_lowercase()
+            .starts_with('n');
+
+    let keywords: Vec<String> = config::split_keywords(&ask(&format!(
+        "Keywords to track on top of {}, comma separated [none]:",
+        pursue::DEFAULT_KEYWORDS.join(",")
+    ))?);
+
+    let format: OutputFormat = loop {
+        let answer: String = ask("Output format: table, json, markdown, or csv [table]:")?;
+        match answer.is_empty() {
+            true => break OutputFormat::default(),
+            false => match answer.parse() {
+                Ok(format) => break format,
+                Err(error) => println!("{}", error),
+            },
+        }
+    };
+
+    let config_path: PathBuf = config::write_initial(
+        root,
+        &config::InitialConfig {
+            default_ignores,
+            keywords,
+            format,
+        },
+    )?;
+    println!("Wrote {}\n", config_path.display());
+    Ok(())
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("ERROR: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn diff_command(arguments: Vec<String>) -> Result<(), std::io::Error> {
+    let mut old: String = String::new();
+    let mut new: String = String::new();
+    let mut format: Option<OutputFormat> = None;
+
+    {
+        let mut argument_parser: ArgumentParser = ArgumentParser::new();
+        argument_parser.set_description(
+            "Show the findings added and removed between two JSON reports, e.g. of two branches",
+        );
+
+        argument_parser.refer(&mut old).required().add_argument(
+            "old",
+            Store,
+            "Earlier JSON report, a snapshot saved by --save-baseline or printed by --format json",
+        );
+
+        argument_parser.refer(&mut new).required().add_argument(
+            "new",
+            Store,
+            "Later JSON report, whose findings count as added when the earlier one lacks them",
+        );
+
+        argument_parser.refer(&mut format).add_option(
+            &["-f", "--format"],
+            StoreOption,
+            "Output format: table (default) or json",
+        );
+
+        if let Err(code) =
+            argument_parser.parse(arguments, &mut std::io::stdout(), &mut std::io::stderr())
+        {
+            std::process::exit(code);
+        }
+    }
+
+    let settings = config::Settings::resolve("", format)?;
+    let new: pursue::Report = pursue::Report::load(Path::new(&new))?;
+    let delta: delta::Delta = delta::Delta::between(&pursue::Report::load(Path::new(&old))?, &new);
+    match settings.format.value {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&delta)?),
+        _ => {
+            let paths: Vec<PathBuf> = new.files.iter().map(|file| file.path.clone()).collect();
+            print!("{}", delta.render(&pursue::scan_root(&paths)));
+        }
+    }
+    Ok(())
+}
+
+fn languages_command(arguments: Vec<String>) -> Result<(), std::io::Error> {
+    let mut name: String = String::new();
+    let mut directory: String = String::from(".");
+    let mut languages: Option<PathBuf> = None;
+    let mut format: Option<OutputFormat> = None;
+
+    {
+        let mut argument_parser: ArgumentParser = ArgumentParser::new();
+        argument_parser.set_description(
+            "List the languages files are recognized as, what recognizes them, and their comments",
+        );
+
+        argument_parser.refer(&mut name).add_argument(
+            "language",
+            Store,
+            "Only list the language of this name, in any case",
+        );
+
+        argument_parser.refer(&mut directory).add_option(
+            &["-d", "--directory"],
+            Store,
+            "Directory that would be profiled, whose config file may name a languages file",
+        );
+
+        argument_parser.refer(&mut languages).add_option(
+            &["--languages"],
+            StoreOption,
+            "TOML file of languages to add to or replace the built in ones",
+        );
+
+        argument_parser.refer(&mut format).add_option(
+            &["-f", "--format"],
+            StoreOption,
+            "json for machine readable output",
+        );
+
+        if let Err(code) =
+            argument_parser.parse(arguments, &mut std::io::stdout(), &mut std::io::stderr())
+        {
+            std::process::exit(code);
+        }
+    }
 
-const COPYRIGHT_NOTICE: &str = "Copyright (c) 2024 Sebastian Pineda (spineda.wpi.alum@gmail.com)
-This program is free software; you may redistribute it under the terms of the
-GNU General Public License version 2 or (at your option) any later version. This
-program has absolutely no warranty.";
-const VERSION: &str = "0.0.5";
-const COOL_NAME_ART: &str = r"
-___________
-\___   ___/___________    ____  ____
-   |   |  \_  __ \__  \ _/ ___\/ __ \
-   |   |   |  | \// __ \\  \__\  ___/
-   |___|   |__|  (____ / \_____\____|
-";
+    let root: PathBuf = Path::canonicalize(Path::new(&directory))?;
+    let settings = config::Settings::resolve("", format)?.with_config_files(&root)?;
+    let languages: Option<PathBuf> =
+        languages.or(settings.languages.map(|languages| languages.value));
+    let mut listed: Vec<pursue::languages::LanguageInfo> =
+        pursue::languages::list(languages.as_deref())?;
+    if !name.is_empty() {
+        listed.retain(|language| language.name.eq_ignore_ascii_case(&name));
+        if listed.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No language is called {:?}", name),
+            ));
+        }
+    }
 
-fn print_version_info() {
-    println!("{}", COOL_NAME_ART);
-    println!("Trace version {}\n", VERSION);
-    println!("{}\n", COPYRIGHT_NOTICE);
+    match settings.format.value {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&listed)?),
+        _ => {
+            let rendered: Vec<String> = listed.iter().map(|language| language.render()).collect();
+            print!("{}", rendered.join("\n"));
+        }
+    }
+    Ok(())
+}
+
+/// First line after the shebang of pre-commit hooks pursue installs, so it
+/// only ever replaces or removes its own
+const HOOK_MARKER: &str = "# Installed by pursue hook install";
+
+fn hook_command(arguments: Vec<String>) -> Result<(), std::io::Error> {
+    let mut action: String = String::new();
+    let mut directory: String = String::from(".");
+    let mut severity: Severity = Severity::Error;
+    let mut force: bool = false;
+
+    {
+        let mut argument_parser: ArgumentParser = ArgumentParser::new();
+        argument_parser.set_description(
+            "Stop commits that bring findings of a severity into the files they change",
+        );
+
+        argument_parser.refer(&mut action).required().add_argument(
+            "action",
+            Store,
+            "install: add a git pre-commit hook, uninstall: remove it, run: check the changed files now",
+        );
+
+        argument_parser.refer(&mut directory).add_option(
+            &["-d", "--directory"],
+            Store,
+            "Directory of the repository",
+        );
+
+        argument_parser.refer(&mut severity).add_option(
+            &["--severity"],
+            Store,
+            "Fail on findings this severe or worse: error (default), warning, or info",
+        );
+
+        argument_parser.refer(&mut force).add_option(
+            &["--force"],
+            StoreTrue,
+            "install: replace a pre-commit hook pursue didn't install",
+        );
+
+        if let Err(code) =
+            argument_parser.parse(arguments, &mut std::io::stdout(), &mut std::io::stderr())
+        {
+            std::process::exit(code);
+        }
+    }
+
+    let root: PathBuf = Path::canonicalize(Path::new(&directory))?;
+    let hook: PathBuf = repository::hooks_directory(&root)?.join("pre-commit");
+    let installed: bool = std::fs::read_to_string(&hook)
+        .is_ok_and(|script| script.lines().nth(1) == Some(HOOK_MARKER));
+
+    match action.as_str() {
+        "install" => {
+            if hook.exists() && !installed && !force {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    format!(
+                        "{} already exists, pass --force to replace it",
+                        hook.display()
+                    ),
+                ));
+            }
+            if let Some(hooks) = hook.parent() {
+                std::fs::create_dir_all(hooks)?;
+            }
+            std::fs::write(
+                &hook,
+                format!(
+                    "#!/bin/sh\n{}\nexec pursue hook run --severity {}\n",
+                    HOOK_MARKER, severity
+                ),
+            )?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755))?;
+            }
+            println!("Installed {}", hook.display());
+            Ok(())
+        }
+        "uninstall" => {
+            if !installed {
+                eprintln!("No pre-commit hook of pursue at {}", hook.display());
+                std::process::exit(1);
+            }
+            std::fs::remove_file(&hook)?;
+            println!("Removed {}", hook.display());
+            Ok(())
+        }
+        "run" => {
+            let settings = config::Settings::resolve("", None)?.with_config_files(&root)?;
+            let matching: Matching = Matching::default();
+            let report = ScannerBuilder::new()
+                .path(&root)
+                .severities(settings.keyword_severities())
+                .keyword_matching(settings.keyword_matching(matching))
+                .matching(matching)
+                .keywords(settings.keywords.value)
+                // Keeps status output off of stdout
+                .format(OutputFormat::Json)
+                .threads(settings.jobs.map_or(0, |jobs| jobs.value.get()))
+                .languages(settings.languages.map(|languages| languages.value))
+                .build()?
+                // What is committed is what is staged, not what is on disk
+                .scan_staged()?;
+
+            let mut found: usize = 0;
+            for (path, occurrence) in report.occurrences() {
+                if occurrence.severity < severity {
+                    continue;
+                }
+                found += 1;
+                eprintln!(
+                    "{}:{}:{}: {} {}",
+                    path.strip_prefix(&root).unwrap_or(path).display(),
+                    occurrence.line,
+                    occurrence.column,
+                    occurrence.keyword,
+                    occurrence.message
+                );
+            }
+            if found > 0 {
+                eprintln!(
+                    "HOOK: {} findings are {} or worse in the changed files, commit with --no-verify to skip this check",
+                    found, severity
+                );
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        _ => {
+            eprintln!(
+                "Unknown hook action {:?}, expected: install, uninstall, run",
+                action
+            );
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Every command, printed by `pursue help`
+const COMMANDS: &str = "Usage: pursue [COMMAND] [OPTIONS]
+
+Commands, each with its own --help:
+  scan          Profile directories and files, what pursue does without a command
+  diff          Show the findings added and removed between two JSON reports
+  languages     List the languages files are recognized as
+  hook          Install, remove, or run a git pre-commit hook
+  report        Write or serve an HTML report of a directory
+  daemon        Keep a directory scanned and answer queries about it on a socket
+  merge         Merge JSON reports of several repositories
+  import        Profile a directory along with the findings of other tools
+  why           Explain how a file would be classified and parsed
+  baseline      Manage .pursue-baseline, the findings accepted as existing debt
+  dismiss       Dismiss a finding as a false positive
+  config        Inspect pursue configuration files
+  grammar-test  Check how comments are recognized against annotated snippets
+  help          Print this list";
+
+fn run() -> Result<(), std::io::Error> {
+    let arguments: Vec<String> = std::env::args().collect();
+    if let Some(command) = arguments.get(1) {
+        let mut subcommand_arguments: Vec<String> = vec![format!("{} {}", arguments[0], command)];
+        subcommand_arguments.extend_from_slice(&arguments[2..]);
+        match command.as_str() {
+            "config" => return config_command(subcommand_arguments),
+            "dismiss" => return dismiss_command(subcommand_arguments),
+            "report" => return report_command(subcommand_arguments),
+            "daemon" => return daemon_command(subcommand_arguments),
+            "merge" => return merge_command(subcommand_arguments),
+            "why" => return why_command(subcommand_arguments),
+            "baseline" => return baseline_command(subcommand_arguments),
+            "grammar-test" => return grammar_test_command(subcommand_arguments),
+            "import" => return import_command(subcommand_arguments),
+            "scan" => return scan_command(subcommand_arguments),
+            "diff" => return diff_command(subcommand_arguments),
+            "languages" => return languages_command(subcommand_arguments),
+            "hook" => return hook_command(subcommand_arguments),
+            "help" => {
+                println!("{}", COMMANDS);
+                return Ok(());
+            }
+            _ => (),
+        }
+    }
+
+    // Without a command, as before there were any
+    scan_command(arguments)
 }
 
-fn main() -> Result<(), std::io::Error> {
+fn scan_command(arguments: Vec<String>) -> Result<(), std::io::Error> {
     let mut logging: bool = false;
     let mut print_version: bool = false;
     let mut directory: String = String::new();
+    let mut paths: Vec<String> = Vec::new();
+    let mut format: Option<OutputFormat> = None;
+    let mut keyword_list: String = String::new();
+    let mut ignore_case: bool = false;
+    let mut whole_word: bool = false;
+    let mut default_ignores: bool = true;
+    let mut base: String = String::new();
+    let mut per_file: bool = false;
+    let mut health: bool = false;
+    let mut hygiene: bool = false;
+    let mut max_line_length: usize = Hygiene::DEFAULT_MAX_LINE_LENGTH;
+    let mut follow_symlinks: bool = false;
+    let mut jobs_from_env: bool = false;
+    let mut git: bool = false;
+    let mut repo: String = String::new();
+    let mut rev: String = String::new();
+    let mut since: Option<String> = None;
+    let mut explain: Option<PathBuf> = None;
+    let mut jobs_requested: Option<usize> = None;
+    let mut interactive: bool = true;
+    let mut cache: bool = true;
+    let mut result_cache: Option<PathBuf> = None;
+    let mut languages: Option<PathBuf> = None;
+    let mut fail_on: Vec<String> = Vec::new();
+    let mut fail_on_severity: Option<Severity> = None;
+    let mut color: ColorChoice = ColorChoice::Auto;
+    let mut blame: bool = false;
+    let mut max_age: u64 = pursue::blame::DEFAULT_MAX_AGE_DAYS;
+    let mut clusters: bool = false;
+    let mut duplicates: bool = false;
+    let mut check_license: String = String::new();
+    let mut license_lines: usize = LicenseCheck::DEFAULT_LINES;
+    let mut similarity: f64 = pursue::cluster::DEFAULT_SIMILARITY;
+    let mut top: Option<usize> = None;
+    let mut save_baseline: Option<PathBuf> = None;
+    let mut prioritize: Vec<String> = Vec::new();
+    let mut excludes: Vec<String> = Vec::new();
+    let mut diff: Option<PathBuf> = None;
+    let mut output: Option<PathBuf> = None;
+    let mut append: bool = false;
+    let mut verify: bool = false;
+    let mut strict: bool = false;
+    let mut max_depth: Option<usize> = None;
+    let mut max_filesize: Option<pursue::ByteSize> = None;
+    let mut archives: bool = false;
 
     {
         let mut argument_parser: ArgumentParser = ArgumentParser::new();
-        argument_parser.set_description("Check your codebase FAST");
+        argument_parser
+            .set_description("Check your codebase FAST. Run pursue help for the other commands");
 
         argument_parser.refer(&mut logging).add_option(
             &["-l", "--log"],
@@ -70,7 +1390,282 @@ fn main() -> Result<(), std::io::Error> {
             "Directory you would like to profile",
         );
 
-        argument_parser.parse_args_or_exit();
+        argument_parser.refer(&mut paths).add_argument(
+            "paths",
+            List,
+            "Directories and files to profile together, e.g. src/ tests/ build.rs",
+        );
+
+        argument_parser.refer(&mut keyword_list).add_option(
+            &["-k", "--keywords"],
+            Store,
+            "Comma separated keywords to track instead of TODO,HACK,BUG,FIXME",
+        );
+
+        argument_parser.refer(&mut ignore_case).add_option(
+            &["-i", "--ignore-case"],
+            StoreTrue,
+            "Match keywords whatever their case, so todo counts as TODO",
+        );
+
+        argument_parser.refer(&mut whole_word).add_option(
+            &["-w", "--whole-word"],
+            StoreTrue,
+            "Only match keywords as whole words, so mastodon doesn't count as TODO",
+        );
+
+        argument_parser.refer(&mut format).add_option(
+            &["-f", "--format"],
+            StoreOption,
+            "Output format of the results: table (default), json, markdown, csv, tokei-json, cloc-json, html, jsonl, or sarif",
+        );
+
+        argument_parser.refer(&mut base).add_option(
+            &["--base"],
+            Store,
+            "JSON report of the base branch to show budget deltas against",
+        );
+
+        argument_parser.refer(&mut save_baseline).add_option(
+            &["--save-baseline"],
+            StoreOption,
+            "Also save the report as a JSON snapshot to this file, to --diff against later. \
+             Unrelated to .pursue-baseline, which leaves accepted findings out of scans",
+        );
+
+        argument_parser.refer(&mut diff).add_option(
+            &["--diff"],
+            StoreOption,
+            "Show the findings added and removed since a snapshot saved with --save-baseline instead of the counts",
+        );
+
+        argument_parser.refer(&mut output).add_option(
+            &["-o", "--output"],
+            StoreOption,
+            "Write the results to this file instead of stdout, replacing it only once they are complete",
+        );
+
+        argument_parser.refer(&mut append).add_option(
+            &["--append"],
+            StoreTrue,
+            "Add the results to the end of the --output file instead of replacing it",
+        );
+
+        argument_parser.refer(&mut max_depth).add_option(
+            &["--max-depth"],
+            StoreOption,
+            "Walk at most this many directories below each path, 0 only profiles the files directly in it",
+        );
+
+        argument_parser.refer(&mut max_filesize).add_option(
+            &["--max-filesize"],
+            StoreOption,
+            "Skip files bigger than this, e.g. 500K or 10M, such as minified or generated files",
+        );
+
+        argument_parser.refer(&mut archives).add_option(
+            &["--archives"],
+            StoreTrue,
+            "Also profile the files inside .zip, .tar, .tar.gz, and .crate archives, without unpacking them",
+        );
+
+        argument_parser.refer(&mut verify).add_option(
+            &["--verify"],
+            StoreTrue,
+            "Scan the files again on a single thread, or a random sample of them in big trees, and report any file counted differently",
+        );
+
+        argument_parser.refer(&mut strict).add_option(
+            &["--strict"],
+            StoreTrue,
+            "Exit with 1 when any file or directory could not be read",
+        );
+
+        argument_parser.refer(&mut per_file).add_option(
+            &["--per-file"],
+            StoreTrue,
+            "csv: also list the counts of every file",
+        );
+
+        argument_parser.refer(&mut health).add_option(
+            &["--health"],
+            StoreTrue,
+            "Also measure the line lengths, indentation, and functions of every file",
+        );
+
+        argument_parser.refer(&mut hygiene).add_option(
+            &["--hygiene"],
+            StoreTrue,
+            "Also count lines with trailing whitespace, mixed tabs and spaces, or too many characters, per language",
+        );
+
+        argument_parser.refer(&mut max_line_length).add_option(
+            &["--max-line-length"],
+            Store,
+            "--hygiene: characters a line can have before it counts as long (default: 100)",
+        );
+
+        argument_parser.refer(&mut follow_symlinks).add_option(
+            &["--follow-symlinks"],
+            StoreTrue,
+            "Profile the directories symlinks point to instead of skipping them",
+        );
+
+        argument_parser.refer(&mut git).add_option(
+            &["--git"],
+            StoreTrue,
+            "Profile only the files git tracks, as listed by git ls-files, instead of walking the directories",
+        );
+
+        argument_parser.refer(&mut repo).add_option(
+            &["--repo"],
+            Store,
+            "Shallow clone this git URL to a temporary directory and profile it, e.g. to audit a dependency",
+        );
+
+        argument_parser.refer(&mut rev).add_option(
+            &["--rev"],
+            Store,
+            "Profile the files of this git commit, branch, or tag instead of the working directory",
+        );
+
+        argument_parser.refer(&mut since).add_option(
+            &["--since"],
+            StoreOption,
+            "Profile only the files changed since this git revision, e.g. --since main for what a branch touched",
+        );
+
+        argument_parser.refer(&mut explain).add_option(
+            &["--explain"],
+            StoreOption,
+            "Instead of scanning, show how this file is classified and parsed, line by line, with every finding",
+        );
+
+        argument_parser.refer(&mut jobs_requested).add_option(
+            &["-j", "--jobs"],
+            StoreOption,
+            "Number of worker threads, 1 scans on a single worker in a repeatable order. Defaults to $PURSUE_JOBS, or else one per CPU",
+        );
+
+        argument_parser.refer(&mut jobs_from_env).add_option(
+            &["--jobs-from-env"],
+            StoreTrue,
+            "Size the worker pool from CI variables and cgroup CPU quotas instead of the host's CPU count",
+        );
+
+        argument_parser.refer(&mut fail_on).add_option(
+            &["--fail-on"],
+            List,
+            "Exit with 1 when a keyword has more findings than allowed, e.g. --fail-on FIXME=0 --fail-on TODO=50",
+        );
+
+        argument_parser.refer(&mut color).add_option(
+            &["--color"],
+            Store,
+            "Color the output: auto (default, on terminals unless NO_COLOR is set), always, or never",
+        );
+
+        argument_parser.refer(&mut fail_on_severity).add_option(
+            &["--fail-on-severity"],
+            StoreOption,
+            "Exit with 1 when any finding is this severe or worse: error, warning, or info",
+        );
+
+        argument_parser.refer(&mut blame).add_option(
+            &["--blame"],
+            StoreTrue,
+            "Attribute findings to the last author of their line with git blame",
+        );
+
+        argument_parser.refer(&mut max_age).add_option(
+            &["--max-age"],
+            Store,
+            "--blame: days after which a finding is flagged as stale (default: 365)",
+        );
+
+        argument_parser.refer(&mut clusters).add_option(
+            &["--clusters"],
+            StoreTrue,
+            "Group findings in different files that say nearly the same thing",
+        );
+
+        argument_parser.refer(&mut duplicates).add_option(
+            &["--duplicates"],
+            StoreTrue,
+            "List sets of files with the same contents",
+        );
+
+        argument_parser.refer(&mut check_license).add_option(
+            &["--check-license"],
+            Store,
+            "List files whose first lines have none of these |-separated patterns, e.g. \"SPDX-License-Identifier|Copyright\", and exit with 1 if any",
+        );
+
+        argument_parser.refer(&mut license_lines).add_option(
+            &["--license-lines"],
+            Store,
+            "--check-license: lines at the top of a file the header has to be in (default: 20)",
+        );
+
+        argument_parser.refer(&mut similarity).add_option(
+            &["--similarity"],
+            Store,
+            "--clusters: share of word pairs two findings need in common, from 0 to 1 (default: 0.6)",
+        );
+
+        argument_parser.refer(&mut top).add_option(
+            &["--top"],
+            StoreOption,
+            "List the N files with the most findings per 100 lines, and with --blame the N oldest findings",
+        );
+
+        argument_parser.refer(&mut prioritize).add_option(
+            &["--prioritize"],
+            List,
+            "Scan paths matching this gitignore style pattern first, e.g. --prioritize 'src/**'. Repeat to rank several",
+        );
+
+        argument_parser.refer(&mut excludes).add_option(
+            &["--exclude"],
+            List,
+            "Skip paths matching this gitignore style pattern, relative to the scanned directory. Repeat to skip several",
+        );
+
+        argument_parser.refer(&mut default_ignores).add_option(
+            &["--no-default-ignores"],
+            StoreFalse,
+            "Also profile build output and dependency directories such as target/ and node_modules/",
+        );
+
+        argument_parser.refer(&mut languages).add_option(
+            &["--languages"],
+            StoreOption,
+            "TOML file of languages to add to or replace the built in ones, e.g. to teach pursue a DSL",
+        );
+
+        argument_parser.refer(&mut cache).add_option(
+            &["--no-cache"],
+            StoreFalse,
+            "Classify files by their contents again instead of using what earlier runs found",
+        );
+
+        argument_parser.refer(&mut result_cache).add_option(
+            &["--cache"],
+            StoreOption,
+            "Keep what was found in every file in this file, e.g. .pursue-cache, and only parse the files that changed since the last run",
+        );
+
+        argument_parser.refer(&mut interactive).add_option(
+            &["--no-interactive"],
+            StoreFalse,
+            "Never ask questions, even on the first run in a directory without a config file",
+        );
+
+        if let Err(code) =
+            argument_parser.parse(arguments, &mut std::io::stdout(), &mut std::io::stderr())
+        {
+            std::process::exit(code);
+        }
     }
 
     if print_version {
@@ -78,37 +1673,347 @@ fn main() -> Result<(), std::io::Error> {
         return Ok(());
     }
 
-    let designated_dir: PathBuf = match directory.is_empty() {
+    if !(similarity > 0.0 && similarity <= 1.0) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "--similarity has to be above 0 and at most 1, not {}",
+                similarity
+            ),
+        ));
+    }
+    if append && output.is_none() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "--append needs --output to know which file to add to",
+        ));
+    }
+
+    let thresholds: Vec<Threshold> = fail_on
+        .iter()
+        .map(|threshold| threshold.parse())
+        .collect::<Result<_, String>>()
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+
+    // Held back until the output format, which may come from a config
+    // file in the scan root, is known
+    let mut messages: Vec<String> = Vec::new();
+    let mut designated_paths: Vec<PathBuf> = Vec::new();
+    for path in &paths {
+        let full_path: PathBuf = Path::canonicalize(Path::new(path)).map_err(|error| {
+            std::io::Error::new(error.kind(), format!("{:?} could not be found", path))
+        })?;
+        messages.push(format!("Analyzing: {:?}", full_path));
+        designated_paths.push(full_path);
+    }
+
+    // Removed once the report is out
+    let checkout: Option<repository::Checkout> = match repo.is_empty() {
+        true => None,
+        false => {
+            let checkout: repository::Checkout = repository::Checkout::shallow(&repo)?;
+            messages.push(format!(
+                "Analyzing: {} (cloned to {:?})",
+                repository::normalize(&repo),
+                checkout.path()
+            ));
+            designated_paths.push(checkout.path().to_path_buf());
+            Some(checkout)
+        }
+    };
+
+    match directory.is_empty() {
         false => {
             let directory_path: &Path = Path::new(&directory);
             match directory_path.exists() {
                 true => {
                     let full_directory_path: PathBuf = Path::canonicalize(Path::new(&directory))?;
-                    println!("Analyzing: {:?}", full_directory_path);
-                    full_directory_path
+                    messages.push(format!("Analyzing: {:?}", full_directory_path));
+                    designated_paths.push(full_directory_path);
                 }
                 false => {
                     let cwd: PathBuf = std::env::current_dir()?;
-                    println!(
+                    messages.push(format!(
                         "WARNING: {:?} not be found, analyzing current working directory: {:?}",
                         directory_path, cwd
-                    );
-                    cwd
+                    ));
+                    designated_paths.push(cwd);
                 }
             }
         }
-        true => {
+        true if designated_paths.is_empty() => {
             let cwd: PathBuf = std::env::current_dir()?;
-            println!(
-                "No Directory specified, analyzing current working directory: {:?}",
-                cwd
+            // Only the trace is printed when explaining a file
+            if explain.is_none() {
+                messages.push(format!(
+                    "No Directory specified, analyzing current working directory: {:?}",
+                    cwd
+                ));
+            }
+            designated_paths.push(cwd);
+        }
+        true => (),
+    }
+
+    // A worktree of the clone, when both are asked for, so dropped first
+    let revision: Option<repository::Checkout> = match rev.is_empty() {
+        true => None,
+        false => {
+            let revision: repository::Checkout =
+                repository::Checkout::revision(&designated_paths[0], &rev)?;
+            designated_paths = designated_paths
+                .iter()
+                .map(|path| {
+                    revision.locate(path).ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::NotFound,
+                            format!("{:?} is not in {}", path, rev),
+                        )
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+            messages.push(format!(
+                "Analyzing: {} (checked out to {:?})",
+                rev,
+                revision.path()
+            ));
+            Some(revision)
+        }
+    };
+
+    let root: PathBuf = pursue::scan_root(&designated_paths);
+    if interactive
+        && checkout.is_none()
+        && revision.is_none()
+        && std::io::stdin().is_terminal()
+        && std::io::stdout().is_terminal()
+        && config::find_config(&root).is_none()
+        && config::outer_configs(&root).is_empty()
+    {
+        prompt_for_config(&root)?;
+    }
+
+    let settings = config::Settings::resolve(&keyword_list, format)?.with_config_files(&root)?;
+    let format: OutputFormat = settings.format.value;
+
+    // Keep stdout clean for machine readable formats so it can be piped
+    let status = |message: String| match format.is_machine_readable() {
+        true => eprintln!("{}", message),
+        false => println!("{}", message),
+    };
+    for message in messages {
+        status(message);
+    }
+
+    // $PURSUE_JOBS is asked for outright, unlike the count of a config file
+    let jobs: Option<jobs::Jobs> = match (
+        jobs::requested(jobs_requested)?,
+        jobs_from_env,
+        &settings.jobs,
+    ) {
+        (Some(jobs), _, _) => Some(jobs),
+        (None, true, Some(jobs)) if matches!(jobs.source, config::Source::Environment(_)) => {
+            Some(jobs::Jobs {
+                count: jobs.value,
+                source: jobs.source.to_string(),
+            })
+        }
+        (None, true, _) => Some(jobs::from_env()),
+        (None, false, jobs) => jobs.as_ref().map(|jobs| jobs::Jobs {
+            count: jobs.value,
+            source: jobs.source.to_string(),
+        }),
+    };
+    let jobs: Option<NonZero<usize>> = jobs.map(|jobs| {
+        status(format!("Using {} jobs ({})", jobs.count, jobs.source));
+        jobs.count
+    });
+
+    // jsonl goes out file by file while scanning, unless it is bound for a
+    // file or compared against another report
+    // Paths in a checkout are rewritten once the scan is done
+    let streamed: bool =
+        format == OutputFormat::Jsonl && output.is_none() && diff.is_none() && revision.is_none();
+
+    let matching: Matching = Matching {
+        ignore_case,
+        whole_word,
+    };
+    let escalation: BTreeMap<String, Escalation> = settings.escalation_rules();
+    let scanner: Scanner = ScannerBuilder::new()
+        .paths(designated_paths)
+        .excludes(excludes)
+        .priorities(prioritize)
+        .severities(settings.keyword_severities())
+        .keyword_matching(settings.keyword_matching(matching))
+        .matching(matching)
+        .keywords(settings.keywords.value)
+        .color(color)
+        .verbose(logging)
+        .format(format)
+        .default_ignores(default_ignores)
+        .follow_symlinks(follow_symlinks)
+        .threads(jobs.map_or(0, NonZero::get))
+        .languages(languages.or(settings.languages.map(|languages| languages.value)))
+        .cache(cache)
+        .result_cache(result_cache)
+        .verify(verify)
+        .max_depth(max_depth)
+        .git(git || checkout.is_some())
+        .since(since)
+        .stream(streamed)
+        .health(health)
+        .hygiene(hygiene.then_some(max_line_length))
+        .check_license(match check_license.is_empty() {
+            true => None,
+            false => Some(LicenseCheck::new(&check_license, license_lines)?),
+        })
+        .max_filesize(max_filesize.map(|size| size.0))
+        .archives(archives)
+        .build()?;
+    if let Some(file) = explain {
+        let trace: why::FileTrace = scanner.explain(&Path::canonicalize(&file)?)?;
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&trace)?),
+            _ => print!("{}", trace.render()),
+        }
+        return Ok(());
+    }
+    let mut report = scanner.scan()?;
+    match (blame, escalation.is_empty()) {
+        (true, _) => pursue::blame::annotate(&mut report, max_age),
+        // Escalation goes by the age of each line
+        (false, false) => pursue::blame::attribute(&mut report),
+        (false, true) => (),
+    }
+    if !escalation.is_empty() {
+        let now: u64 = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let escalated: usize = pursue::blame::escalate(&mut report, &escalation, now);
+        if escalated > 0 {
+            status(format!("{} findings escalated for their age", escalated));
+        }
+    }
+    if clusters {
+        pursue::cluster::annotate(&mut report, similarity);
+    }
+    if duplicates {
+        pursue::duplicates::annotate(&mut report);
+    }
+    // The checkout is gone by the time the report is read, and blame is
+    // done with it
+    if let Some(revision) = &revision {
+        report.relocate(revision.path());
+    }
+    report.top = top;
+    report.color = color.enabled(output.is_none() && std::io::stdout().is_terminal());
+    if !base.is_empty() {
+        report.compare_budgets(&pursue::Report::load(Path::new(&base))?);
+    }
+    let render = |writer: &mut dyn Write| -> Result<(), std::io::Error> {
+        match (&diff, format, per_file) {
+            (Some(baseline), _, _) => {
+                let delta: delta::Delta =
+                    delta::Delta::between(&pursue::Report::load(baseline)?, &report);
+                match format {
+                    OutputFormat::Json => {
+                        writeln!(writer, "{}", serde_json::to_string_pretty(&delta)?)
+                    }
+                    _ => write!(writer, "{}", delta.render(&root)),
+                }
+            }
+            (None, OutputFormat::Csv, true) => write!(writer, "{}", report.csv(true)),
+            (None, _, _) => report.write(format, writer),
+        }
+    };
+    match &output {
+        Some(file) => {
+            pursue::output::write_to(file, append, render)?;
+            status(format!("Results written to {}", file.display()));
+        }
+        // Already printed file by file
+        None if streamed => (),
+        None => render(&mut std::io::stdout().lock())?,
+    }
+    if let Some(file) = &save_baseline {
+        report.write_json(std::fs::File::create(file)?)?;
+        status(format!("Snapshot saved to {}", file.display()));
+    }
+
+    // Budgets double as a CI gate
+    let exceeded: Vec<String> = report_exceeded_budgets(&report);
+
+    for threshold in &thresholds {
+        if !report.keyword_table.contains_key(&threshold.keyword) {
+            eprintln!(
+                "WARNING: --fail-on {} can never fail, {:?} is not a tracked keyword",
+                threshold.keyword, threshold.keyword
             );
-            cwd
         }
+    }
+
+    let crossed: Vec<(&Threshold, usize)> = report.exceeded_thresholds(&thresholds);
+    for (threshold, count) in &crossed {
+        eprintln!(
+            "THRESHOLD EXCEEDED: {} {} found, at most {} allowed",
+            count, threshold.keyword, threshold.max
+        );
+    }
+
+    let severe: usize = fail_on_severity.map_or(0, |severity| report.findings_at_least(severity));
+    if let (Some(severity), true) = (fail_on_severity, severe > 0) {
+        eprintln!(
+            "SEVERITY EXCEEDED: {} findings are {} or worse",
+            severe, severity
+        );
+    }
+
+    let discrepancies: usize = report
+        .verification
+        .as_ref()
+        .map_or(0, |verification| verification.discrepancies.len());
+    if discrepancies > 0 {
+        eprintln!(
+            "VERIFICATION FAILED: {} files were counted differently by the reference pass",
+            discrepancies
+        );
+    }
+
+    // The table and markdown reports list them in their warnings section
+    if !matches!(format, OutputFormat::Table | OutputFormat::Markdown) || output.is_some() {
+        for warning in &report.warnings {
+            eprintln!("WARNING: {}: {}", warning.path.display(), warning.message);
+        }
+    }
+    let unlicensed: usize = report.unlicensed_files().count();
+    if unlicensed > 0 {
+        eprintln!("LICENSE: {} files lack a license header", unlicensed);
+    }
+
+    let unreadable: usize = match strict {
+        true => report.warnings.len(),
+        false => 0,
     };
+    if unreadable > 0 {
+        eprintln!(
+            "STRICT: {} files or directories could not be read",
+            unreadable
+        );
+    }
 
-    let mut logger = Logger::new(designated_dir, logging);
-    logger.log()?;
+    // Exiting skips dropping it
+    drop(revision);
+    drop(checkout);
+    if !exceeded.is_empty()
+        || !crossed.is_empty()
+        || severe > 0
+        || discrepancies > 0
+        || unlicensed > 0
+        || unreadable > 0
+    {
+        std::process::exit(1);
+    }
 
     Ok(())
 }
@@ -0,0 +1,312 @@
+/*
+ *  map.rs - Read only memory maps of files on POSIX and Windows
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{fs::File, ops::Deref, path::Path, ptr::NonNull};
+
+/// A whole file mapped read only into memory. The mapping is only ever
+/// handed out as `&[u8]`, never written through.
+///
+/// Like any memory map this trusts the file not to be truncated while it is
+/// mapped; reading past the new end faults on POSIX
+pub struct Map {
+    address: NonNull<u8>,
+    length: usize,
+}
+
+// SAFETY: the mapping is never written through, so sharing it is as safe as
+// sharing a `&[u8]`, and it may be unmapped from any thread
+unsafe impl Send for Map {}
+unsafe impl Sync for Map {}
+
+impl Map {
+    pub fn open(path: &Path) -> Result<Self, std::io::Error> {
+        Self::new(&File::open(path)?)
+    }
+
+    pub fn new(file: &File) -> Result<Self, std::io::Error> {
+        // A slice may not be longer than isize::MAX bytes
+        let length: usize = usize::try_from(file.metadata()?.len())
+            .ok()
+            .filter(|length| isize::try_from(*length).is_ok())
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::OutOfMemory,
+                    "File is too large to map into memory",
+                )
+            })?;
+
+        // Neither mmap nor CreateFileMapping accept an empty file, and
+        // there is nothing to map anyway
+        if length == 0 {
+            return Ok(Self {
+                address: NonNull::dangling(),
+                length,
+            });
+        }
+
+        // Mapped from offset 0, so the address is page aligned
+        Ok(Self {
+            address: platform::map(file, length)?,
+            length,
+        })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        // SAFETY: `address` is either dangling with a length of 0, which is a
+        // valid empty slice, or the start of `length` readable bytes that
+        // stay mapped until `self` is dropped. Nothing writes through them
+        unsafe { std::slice::from_raw_parts(self.address.as_ptr(), self.length) }
+    }
+
+    /// Lines of the file without their `\n` or `\r\n` endings
+    pub fn lines(&self) -> impl Iterator<Item = &[u8]> {
+        let bytes: &[u8] = self.as_bytes();
+        let bytes: &[u8] = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+        // An empty file has no lines rather than a single empty one
+        (!self.is_empty())
+            .then_some(bytes)
+            .into_iter()
+            .flat_map(|bytes| bytes.split(|byte| *byte == b'\n'))
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+    }
+}
+
+impl Deref for Map {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl Drop for Map {
+    fn drop(&mut self) {
+        // Empty maps were never mapped
+        if self.length != 0 {
+            platform::unmap(self.address, self.length);
+        }
+    }
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::{fs::File, os::fd::AsRawFd, ptr::NonNull};
+
+    /// `length` must be the nonzero length of `file`
+    pub fn map(file: &File, length: usize) -> Result<NonNull<u8>, std::io::Error> {
+        // SAFETY: a fresh private read only mapping aliases no Rust memory
+        let address = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                length,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+
+        match address == libc::MAP_FAILED {
+            true => Err(std::io::Error::last_os_error()),
+            false => NonNull::new(address.cast()).ok_or_else(std::io::Error::last_os_error),
+        }
+    }
+
+    /// `address` and `length` must come from `map`, and no slice of the
+    /// mapping may outlive this call
+    pub fn unmap(address: NonNull<u8>, length: usize) {
+        // SAFETY: see above, `Map` only unmaps when it is dropped
+        unsafe {
+            libc::munmap(address.as_ptr().cast(), length);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::{fs::File, os::windows::io::AsRawHandle, ptr::NonNull};
+
+    use windows_sys::Win32::{
+        Foundation::CloseHandle,
+        System::Memory::{
+            CreateFileMappingW, FILE_MAP_READ, MEMORY_MAPPED_VIEW_ADDRESS, MapViewOfFile,
+            PAGE_READONLY, UnmapViewOfFile,
+        },
+    };
+
+    /// `length` must be the nonzero length of `file`
+    pub fn map(file: &File, length: usize) -> Result<NonNull<u8>, std::io::Error> {
+        // SAFETY: the handle is valid for the call, and a fresh read only
+        // view aliases no Rust memory
+        unsafe {
+            let mapping = CreateFileMappingW(
+                file.as_raw_handle(),
+                std::ptr::null(),
+                PAGE_READONLY,
+                0,
+                0,
+                std::ptr::null(),
+            );
+            if mapping.is_null() {
+                return Err(std::io::Error::last_os_error());
+            }
+
+            let view: MEMORY_MAPPED_VIEW_ADDRESS =
+                MapViewOfFile(mapping, FILE_MAP_READ, 0, 0, length);
+            let error: std::io::Error = std::io::Error::last_os_error();
+            // The view keeps the mapping alive on its own
+            CloseHandle(mapping);
+
+            NonNull::new(view.Value.cast()).ok_or(error)
+        }
+    }
+
+    /// `address` must come from `map`, and no slice of the view may
+    /// outlive this call
+    pub fn unmap(address: NonNull<u8>, _length: usize) {
+        // SAFETY: see above, `Map` only unmaps when it is dropped
+        unsafe {
+            UnmapViewOfFile(MEMORY_MAPPED_VIEW_ADDRESS {
+                Value: address.as_ptr().cast(),
+            });
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::{fs::File, ptr::NonNull};
+
+    pub fn map(_file: &File, _length: usize) -> Result<NonNull<u8>, std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Memory maps are not supported on this platform",
+        ))
+    }
+
+    pub fn unmap(_address: NonNull<u8>, _length: usize) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::File,
+        io::Write,
+        path::{Path, PathBuf},
+        sync::Arc,
+        thread,
+    };
+
+    use super::Map;
+
+    /// A file in the temporary directory, removed when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &[u8]) -> Self {
+            let path: PathBuf =
+                std::env::temp_dir().join(format!("pursue-map-{}-{}", std::process::id(), name));
+            File::create(&path).unwrap().write_all(contents).unwrap();
+            Self(path)
+        }
+
+        fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[cfg(unix)]
+    fn page_size() -> usize {
+        // SAFETY: sysconf only reads a system setting
+        usize::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) }).unwrap()
+    }
+
+    #[cfg(not(unix))]
+    fn page_size() -> usize {
+        4096
+    }
+
+    #[test]
+    fn empty_file() {
+        let file: TempFile = TempFile::new("empty", b"");
+        let map: Map = Map::open(file.path()).unwrap();
+
+        assert!(map.is_empty());
+        assert_eq!(map.as_bytes(), b"");
+        assert_eq!(map.lines().count(), 0);
+    }
+
+    #[test]
+    fn exact_page_size_file() {
+        let page_size: usize = page_size();
+        let contents: Vec<u8> = (0..page_size).map(|index| (index % 251) as u8).collect();
+        let file: TempFile = TempFile::new("page", &contents);
+        let map: Map = Map::open(file.path()).unwrap();
+
+        assert_eq!(map.len(), page_size);
+        assert_eq!(map.as_bytes(), contents.as_slice());
+        assert_eq!(map.last(), contents.last());
+    }
+
+    #[test]
+    fn lines_without_endings() {
+        let file: TempFile = TempFile::new("lines", b"one\r\ntwo\n\nthree\n");
+        let map: Map = Map::open(file.path()).unwrap();
+
+        let lines: Vec<&[u8]> = map.lines().collect();
+        assert_eq!(lines, [&b"one"[..], b"two", b"", b"three"]);
+    }
+
+    #[test]
+    fn concurrent_drop() {
+        let contents: Vec<u8> = b"shared between threads\n".repeat(1000);
+        let file: TempFile = TempFile::new("shared", &contents);
+
+        // The last thread to let go of the map unmaps it
+        let shared: Arc<Map> = Arc::new(Map::open(file.path()).unwrap());
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                let map: Arc<Map> = Arc::clone(&shared);
+                let contents: &[u8] = &contents;
+                scope.spawn(move || {
+                    assert_eq!(map.as_bytes(), contents);
+                    drop(map);
+                });
+            }
+        });
+        drop(shared);
+
+        // Maps of one file opened and dropped on many threads at once
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        let map: Map = Map::open(file.path()).unwrap();
+                        assert_eq!(map.len(), contents.len());
+                    }
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,119 @@
+/*
+ *  matching.rs - How keywords are told apart from the rest of a comment
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{collections::BTreeMap, ops::Range};
+
+use serde::Deserialize;
+
+/// How a keyword is looked for. By default it matches anywhere it appears
+/// exactly as written
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Matching {
+    /// `todo` and `Todo` count as `TODO`
+    pub ignore_case: bool,
+    /// Only whole words count, so `mastodon` and `TODOS` aren't `TODO`
+    pub whole_word: bool,
+}
+
+/// What a config file changes about how one keyword is matched, e.g.
+/// `[matching.TODO] ignore_case = true`
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct MatchingOverride {
+    pub ignore_case: Option<bool>,
+    pub whole_word: Option<bool>,
+}
+
+impl MatchingOverride {
+    /// This override with the settings `other` makes on top of it
+    pub fn and(self, other: MatchingOverride) -> MatchingOverride {
+        MatchingOverride {
+            ignore_case: other.ignore_case.or(self.ignore_case),
+            whole_word: other.whole_word.or(self.whole_word),
+        }
+    }
+}
+
+/// Whether `c` continues a word, so a keyword next to it isn't whole
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Matching {
+    /// `self` with what `changes` sets taking its place
+    pub fn with(self, changes: MatchingOverride) -> Matching {
+        Matching {
+            ignore_case: changes.ignore_case.unwrap_or(self.ignore_case),
+            whole_word: changes.whole_word.unwrap_or(self.whole_word),
+        }
+    }
+
+    /// How `keyword` is matched, going by `keywords` for those a config
+    /// file set up and by `self` for the rest
+    pub fn of(self, keyword: &str, keywords: &BTreeMap<String, Matching>) -> Matching {
+        keywords.get(keyword).copied().unwrap_or(self)
+    }
+
+    /// Bytes of `text` the first match of `keyword` spans
+    pub fn find(&self, text: &str, keyword: &str) -> Option<Range<usize>> {
+        if keyword.is_empty() {
+            return None;
+        }
+
+        match self.ignore_case {
+            false => text
+                .match_indices(keyword)
+                .map(|(start, _)| start..start + keyword.len())
+                .find(|found| self.is_whole(text, found)),
+            true => text
+                .char_indices()
+                .filter_map(|(start, _)| {
+                    Some(start..start + Self::folded_match(&text[start..], keyword)?)
+                })
+                .find(|found| self.is_whole(text, found)),
+        }
+    }
+
+    /// Whether `text` has a match of `keyword`
+    pub fn is_match(&self, text: &str, keyword: &str) -> bool {
+        self.find(text, keyword).is_some()
+    }
+
+    /// Whether `found` in `text` stands on its own, when that matters
+    fn is_whole(&self, text: &str, found: &Range<usize>) -> bool {
+        !self.whole_word
+            || (!text[..found.start]
+                .chars()
+                .next_back()
+                .is_some_and(is_word_char)
+                && !text[found.end..].chars().next().is_some_and(is_word_char))
+    }
+
+    /// Length in bytes of `keyword` at the start of `text` ignoring case,
+    /// if it is there
+    fn folded_match(text: &str, keyword: &str) -> Option<usize> {
+        let mut chars = text.char_indices();
+        for expected in keyword.chars() {
+            let (_, found) = chars.next()?;
+            if found != expected && !found.to_lowercase().eq(expected.to_lowercase()) {
+                return None;
+            }
+        }
+        Some(chars.next().map_or(text.len(), |(end, _)| end))
+    }
+}
@@ -0,0 +1,67 @@
+/*
+ *  output.rs - Write results to a file instead of stdout
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    ffi::OsString,
+    fs::{File, OpenOptions},
+    io::{BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+/// Write whatever `render` writes to `path`. Unless appending, it is
+/// written next to `path` first and renamed over it once complete, so
+/// readers never see half a report and a failed run leaves the old one
+pub fn write_to(
+    path: &Path,
+    append: bool,
+    render: impl FnOnce(&mut dyn Write) -> Result<(), std::io::Error>,
+) -> Result<(), std::io::Error> {
+    if append {
+        let mut writer: BufWriter<File> =
+            BufWriter::new(OpenOptions::new().create(true).append(true).open(path)?);
+        render(&mut writer)?;
+        return writer.flush();
+    }
+
+    let mut name: OsString = OsString::from(".");
+    name.push(path.file_name().ok_or_else(|| {
+        std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} does not name a file", path.display()),
+        )
+    })?);
+    name.push(format!(".{}.tmp", std::process::id()));
+    let temporary: PathBuf = path.with_file_name(name);
+
+    let written: Result<(), std::io::Error> = (|| {
+        let mut writer: BufWriter<File> = BufWriter::new(File::create(&temporary)?);
+        render(&mut writer)?;
+        writer
+            .into_inner()
+            .map_err(|error| error.into_error())?
+            .sync_all()
+    })();
+
+    match written.and_then(|_| std::fs::rename(&temporary, path)) {
+        Ok(()) => Ok(()),
+        Err(error) => {
+            let _ = std::fs::remove_file(&temporary);
+            Err(error)
+        }
+    }
+}
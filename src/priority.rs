@@ -0,0 +1,172 @@
+/*
+ *  priority.rs - Scan the paths that matter most first
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+/// Gitignore style patterns of paths to scan first, most important first
+pub struct Priorities {
+    root: PathBuf,
+    /// Each pattern along with the directories it names before its first
+    /// wildcard, e.g. `src/gen` for `src/gen/**/*.rs`
+    patterns: Vec<(Gitignore, PathBuf)>,
+}
+
+impl Priorities {
+    pub fn none() -> Self {
+        Self {
+            root: PathBuf::new(),
+            patterns: Vec::new(),
+        }
+    }
+
+    /// `patterns` are relative to `root`
+    pub fn new(root: &Path, patterns: &[String]) -> Result<Self, std::io::Error> {
+        let patterns: Vec<(Gitignore, PathBuf)> = patterns
+            .iter()
+            .map(|pattern| {
+                let invalid = |error: ignore::Error| {
+                    std::io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Invalid --prioritize pattern {:?}: {}", pattern, error),
+                    )
+                };
+
+                let mut builder: GitignoreBuilder = GitignoreBuilder::new(root);
+                builder.add_line(None, pattern).map_err(invalid)?;
+                let prefix: PathBuf = pattern
+                    .trim_start_matches('/')
+                    .split('/')
+                    .take_while(|part| !part.contains(['*', '?', '[', '{']))
+                    .collect();
+                Ok((builder.build().map_err(invalid)?, prefix))
+            })
+            .collect::<Result<_, std::io::Error>>()?;
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            patterns,
+        })
+    }
+
+    /// Position of the first pattern covering `path`, which is scanned
+    /// before anything of a higher rank. A directory is covered when a
+    /// pattern could match something inside it. Paths no pattern covers
+    /// rank last
+    pub fn rank(&self, path: &Path, is_dir: bool) -> usize {
+        let relative: &Path = match path.strip_prefix(&self.root) {
+            Ok(relative) => relative,
+            Err(_) => return self.patterns.len(),
+        };
+
+        self.patterns
+            .iter()
+            .position(|(pattern, prefix)| {
+                pattern
+                    .matched_path_or_any_parents(path, is_dir)
+                    .is_ignore()
+                    || (is_dir && prefix.starts_with(relative))
+            })
+            .unwrap_or(self.patterns.len())
+    }
+}
+
+struct Queued<T> {
+    rank: usize,
+    /// Order of arrival, so equally ranked work stays first come first served
+    sequence: u64,
+    item: T,
+}
+
+// BinaryHeap pops the greatest, which here is the lowest rank
+impl<T> Ord for Queued<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.rank, other.sequence).cmp(&(self.rank, self.sequence))
+    }
+}
+
+impl<T> PartialOrd for Queued<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> PartialEq for Queued<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Queued<T> {}
+
+struct QueueState<T> {
+    heap: BinaryHeap<Queued<T>>,
+    next_sequence: u64,
+}
+
+/// Queue handing out the lowest ranked work first, which workers add to
+/// as they go. Every push is paired with a task on the thread pool that
+/// pops, so whichever task runs first does the most important work
+pub struct WorkQueue<T> {
+    state: Mutex<QueueState<T>>,
+}
+
+impl<T> Default for WorkQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> WorkQueue<T> {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                heap: BinaryHeap::new(),
+                next_sequence: 0,
+            }),
+        }
+    }
+
+    pub fn push(&self, rank: usize, item: T) {
+        let mut state = self.state.lock().unwrap();
+        let sequence: u64 = state.next_sequence;
+        state.next_sequence += 1;
+        state.heap.push(Queued {
+            rank,
+            sequence,
+            item,
+        });
+    }
+
+    /// The most important piece of work queued, if any
+    pub fn pop(&self) -> Option<T> {
+        self.state
+            .lock()
+            .unwrap()
+            .heap
+            .pop()
+            .map(|queued| queued.item)
+    }
+}
@@ -0,0 +1,1957 @@
+/*
+ *  report.rs - Results of a finished profile and their output formats
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::{self, Write},
+    ops::{AddAssign, SubAssign},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    license::LicenseCheck,
+    matching::Matching,
+    severity::Severity,
+    terminal::{Align, BOLD, GREEN, RED, Table},
+    text,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    /// GitHub flavored, meant to be posted as a pull request comment
+    Markdown,
+    Csv,
+    /// Shaped like `tokei --output json`
+    TokeiJson,
+    /// Shaped like `cloc --json`
+    ClocJson,
+    /// A single self-contained page
+    Html,
+    /// One JSON object per finding and line, printed as files are scanned
+    Jsonl,
+    /// A SARIF 2.1.0 log, for code scanning dashboards
+    Sarif,
+}
+
+impl OutputFormat {
+    /// Whether stdout is reserved for the result itself, meaning status
+    /// messages have to go to stderr instead.
+    pub fn is_machine_readable(&self) -> bool {
+        !matches!(self, OutputFormat::Table)
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "csv" => Ok(OutputFormat::Csv),
+            "tokei-json" => Ok(OutputFormat::TokeiJson),
+            "cloc-json" => Ok(OutputFormat::ClocJson),
+            "html" => Ok(OutputFormat::Html),
+            "jsonl" => Ok(OutputFormat::Jsonl),
+            "sarif" => Ok(OutputFormat::Sarif),
+            _ => Err(format!(
+                "Unknown output format {:?}, expected one of: table, json, markdown, csv, tokei-json, cloc-json, html, jsonl, sarif",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Table => write!(f, "table"),
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Markdown => write!(f, "markdown"),
+            OutputFormat::Csv => write!(f, "csv"),
+            OutputFormat::TokeiJson => write!(f, "tokei-json"),
+            OutputFormat::ClocJson => write!(f, "cloc-json"),
+            OutputFormat::Html => write!(f, "html"),
+            OutputFormat::Jsonl => write!(f, "jsonl"),
+            OutputFormat::Sarif => write!(f, "sarif"),
+        }
+    }
+}
+
+/// Lines broken down the way cloc and tokei do. A line with both code and a
+/// comment on it counts as code
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LineCounts {
+    pub code: usize,
+    pub comment: usize,
+    pub blank: usize,
+}
+
+impl LineCounts {
+    pub fn total(&self) -> usize {
+        self.code + self.comment + self.blank
+    }
+}
+
+/// One row of the per language breakdown
+pub struct LanguageSummary<'a> {
+    pub name: &'a str,
+    pub files: usize,
+    pub lines: LineCounts,
+    pub doc_lines: usize,
+    pub bytes: u64,
+}
+
+impl LanguageSummary<'_> {
+    /// Doc comment lines for every 100 lines of code, a rough measure of how
+    /// well documented the language is. None without any code
+    pub fn documentation(&self) -> Option<f64> {
+        match self.lines.code {
+            0 => None,
+            code => Some(self.doc_lines as f64 * 100.0 / code as f64),
+        }
+    }
+
+    /// `documentation` as shown in tables. It is a rate rather than a share,
+    /// so it is shown without a percent sign
+    pub fn documentation_label(&self) -> String {
+        self.documentation()
+            .map_or("-".to_string(), |per_100| format!("{:.1}", per_100))
+    }
+}
+
+impl AddAssign for LineCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.code += other.code;
+        self.comment += other.comment;
+        self.blank += other.blank;
+    }
+}
+
+impl SubAssign for LineCounts {
+    fn sub_assign(&mut self, other: Self) {
+        self.code = self.code.saturating_sub(other.code);
+        self.comment = self.comment.saturating_sub(other.comment);
+        self.blank = self.blank.saturating_sub(other.blank);
+    }
+}
+
+/// Totals of a scan, named the same way in every output format. Lines are
+/// physical lines read, and a finding is only ever counted in `findings`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Files whose lines were read and counted
+    #[serde(default)]
+    pub files: usize,
+    pub lines: usize,
+    pub code_lines: usize,
+    pub comment_lines: usize,
+    pub blank_lines: usize,
+    pub doc_comment_lines: usize,
+    pub findings: usize,
+}
+
+impl Metrics {
+    /// Every metric by name, in the order reports list them
+    pub fn entries(&self) -> [(&'static str, usize); 7] {
+        [
+            ("files", self.files),
+            ("lines", self.lines),
+            ("code_lines", self.code_lines),
+            ("comment_lines", self.comment_lines),
+            ("blank_lines", self.blank_lines),
+            ("doc_comment_lines", self.doc_comment_lines),
+            ("findings", self.findings),
+        ]
+    }
+}
+
+impl AddAssign for Metrics {
+    fn add_assign(&mut self, other: Self) {
+        self.files += other.files;
+        self.lines += other.lines;
+        self.code_lines += other.code_lines;
+        self.comment_lines += other.comment_lines;
+        self.blank_lines += other.blank_lines;
+        self.doc_comment_lines += other.doc_comment_lines;
+        self.findings += other.findings;
+    }
+}
+
+impl SubAssign for Metrics {
+    fn sub_assign(&mut self, other: Self) {
+        self.files = self.files.saturating_sub(other.files);
+        self.lines = self.lines.saturating_sub(other.lines);
+        self.code_lines = self.code_lines.saturating_sub(other.code_lines);
+        self.comment_lines = self.comment_lines.saturating_sub(other.comment_lines);
+        self.blank_lines = self.blank_lines.saturating_sub(other.blank_lines);
+        self.doc_comment_lines = self
+            .doc_comment_lines
+            .saturating_sub(other.doc_comment_lines);
+        self.findings = self.findings.saturating_sub(other.findings);
+    }
+}
+
+/// One keyword hit, located by its 1 based line number
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Occurrence {
+    pub line: usize,
+    /// Character the keyword starts at on its line, counting from 1
+    #[serde(default)]
+    pub column: usize,
+    /// Bytes before the keyword on its line
+    #[serde(default)]
+    pub byte_offset: usize,
+    pub keyword: String,
+    /// How urgently the keyword should be dealt with
+    #[serde(default)]
+    pub severity: Severity,
+    /// The line the keyword was found on, trimmed
+    pub snippet: String,
+    #[serde(default)]
+    pub in_doc_comment: bool,
+    #[serde(default)]
+    pub fingerprint: String,
+    /// Last author of the line, with `--blame`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// When the line was last touched, in seconds since the epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authored: Option<u64>,
+    /// Severity before an `[escalation]` rule raised it for its age
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escalated_from: Option<Severity>,
+    /// Who the marker is for, `alice` in `TODO(alice): fix overflow`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    /// What the marker says after its keyword and assignee
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+    /// Language of the file the finding is in
+    #[serde(default)]
+    pub language: String,
+    /// Size of that file on disk
+    #[serde(default)]
+    pub file_bytes: u64,
+    /// What the language was recognized by, e.g. `extension "rs"`
+    #[serde(default)]
+    pub classified_by: String,
+}
+
+/// A finding as `--format jsonl` prints it
+#[derive(Serialize)]
+struct FindingLine<'a> {
+    file: &'a Path,
+    line: usize,
+    column: usize,
+    byte_offset: usize,
+    keyword: &'a str,
+    severity: Severity,
+    text: &'a str,
+    language: &'a str,
+    file_bytes: u64,
+    classified_by: &'a str,
+}
+
+impl Occurrence {
+    /// This finding of the file at `path` as one line of JSON
+    pub fn json_line(&self, path: &Path) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&FindingLine {
+            file: path,
+            line: self.line,
+            column: self.column,
+            byte_offset: self.byte_offset,
+            keyword: &self.keyword,
+            severity: self.severity,
+            text: &self.snippet,
+            language: &self.language,
+            file_bytes: self.file_bytes,
+            classified_by: &self.classified_by,
+        })
+    }
+
+    /// Assignee and message of the marker `keyword` starts in `comment`.
+    /// `TODO(alice): fix overflow` is for `alice` and says `fix overflow`
+    pub fn parse_marker(
+        comment: &str,
+        keyword: &str,
+        matching: Matching,
+    ) -> (Option<String>, String) {
+        let rest: &str = matching
+            .find(comment, keyword)
+            .map_or("", |found| &comment[found.end..]);
+
+        let (assignee, rest): (Option<String>, &str) =
+            match rest.strip_prefix('(').and_then(|rest| rest.split_once(')')) {
+                Some((assignee, rest)) => {
+                    let assignee: &str = assignee.trim().trim_start_matches('@');
+                    ((!assignee.is_empty()).then(|| assignee.to_string()), rest)
+                }
+                None => (None, rest),
+            };
+
+        let message: &str = rest.trim_start_matches([':', '-', ' ', '\t']).trim_end();
+        (assignee, message.to_string())
+    }
+}
+
+/// Counts for a single profiled file
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub file_type: String,
+    /// What the language was recognized by, e.g. `extension "rs"`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub classified_by: String,
+    pub line_count: usize,
+    pub line_counts: LineCounts,
+    /// Size on disk
+    #[serde(default)]
+    pub bytes: u64,
+    #[serde(default)]
+    pub doc_comment_line_count: usize,
+    /// Only keywords found at least once in this file
+    pub keyword_table: BTreeMap<String, usize>,
+    pub occurrences: Vec<Occurrence>,
+    /// Hash of the file's lines, used to spot vendored copies when merging
+    /// reports of several repositories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Reading failed partway through, so only the findings up to
+    /// `line_count` are included
+    #[serde(default)]
+    pub partial: bool,
+    /// Line lengths, indentation, and functions, with `--health`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<FileHealth>,
+    /// Whether the file starts with a license header, with
+    /// `--check-license`. None for files that weren't checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license_header: Option<bool>,
+    /// Whitespace problems and long lines, with `--hygiene`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hygiene: Option<Hygiene>,
+    /// Archive the file was read out of, with `--archives`. Its path is
+    /// the archive's joined with the one inside of it, which isn't on disk
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<PathBuf>,
+}
+
+/// Rough measures of how hard a file is to read, without parsing it
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileHealth {
+    /// Characters in the longest line
+    pub max_line_length: usize,
+    /// Characters in every line together
+    pub total_line_length: usize,
+    /// How many lines with code or comments on them are indented each
+    /// number of levels, a level being a tab or four spaces
+    pub indentation: Vec<usize>,
+    /// Functions defined, found by the keywords that define them. None for
+    /// languages without such keywords
+    pub functions: Option<usize>,
+}
+
+impl FileHealth {
+    /// Count the length and indentation of `line`
+    pub fn measure(&mut self, line: &str, tab_width: usize) {
+        let length: usize = line.chars().count();
+        self.max_line_length = self.max_line_length.max(length);
+        self.total_line_length += length;
+        if line.trim().is_empty() {
+            return;
+        }
+
+        let columns: usize = line
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| match c {
+                '\t' => tab_width,
+                _ => 1,
+            })
+            .sum();
+        let level: usize = columns / tab_width;
+        if self.indentation.len() <= level {
+            self.indentation.resize(level + 1, 0);
+        }
+        self.indentation[level] += 1;
+    }
+
+    /// Mean characters per line of a file `line_count` lines long
+    pub fn average_line_length(&self, line_count: usize) -> f64 {
+        match line_count {
+            0 => 0.0,
+            lines => self.total_line_length as f64 / lines as f64,
+        }
+    }
+
+    /// Levels the deepest indented line is at
+    pub fn max_indentation(&self) -> usize {
+        self.indentation.len().saturating_sub(1)
+    }
+}
+
+/// Lines of a file that are untidy in ways a formatter would fix
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Hygiene {
+    /// Lines ending in spaces or tabs
+    pub trailing_whitespace: usize,
+    /// Lines indented with both tabs and spaces
+    pub mixed_indentation: usize,
+    /// Lines longer than the limit they were checked against
+    pub long_lines: usize,
+}
+
+impl Hygiene {
+    /// Lines `--hygiene` lets be this many characters long by default
+    pub const DEFAULT_MAX_LINE_LENGTH: usize = 100;
+
+    /// Count what is untidy about `line`
+    pub fn measure(&mut self, line: &str, max_line_length: usize) {
+        let line: &str = line.strip_suffix('\r').unwrap_or(line);
+        if line.ends_with([' ', '\t']) {
+            self.trailing_whitespace += 1;
+        }
+        let indentation: &str = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+        if indentation.contains(' ') && indentation.contains('\t') {
+            self.mixed_indentation += 1;
+        }
+        if line.chars().count() > max_line_length {
+            self.long_lines += 1;
+        }
+    }
+}
+
+impl AddAssign for Hygiene {
+    fn add_assign(&mut self, other: Self) {
+        self.trailing_whitespace += other.trailing_whitespace;
+        self.mixed_indentation += other.mixed_indentation;
+        self.long_lines += other.long_lines;
+    }
+}
+
+impl FileReport {
+    /// What this file adds to the totals of a report
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            files: 1,
+            lines: self.line_count,
+            code_lines: self.line_counts.code,
+            comment_lines: self.line_counts.comment,
+            blank_lines: self.line_counts.blank,
+            doc_comment_lines: self.doc_comment_line_count,
+            findings: self.occurrences.len(),
+        }
+    }
+}
+
+/// A file or directory the scan could not read, in full or at all
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Warning {
+    pub path: PathBuf,
+    /// What went wrong, e.g. `could not open: Permission denied`
+    pub message: String,
+}
+
+/// A file the single threaded reference pass of `--verify` counted
+/// differently than the scan did
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Discrepancy {
+    pub path: PathBuf,
+    /// What differs, e.g. `line_count: scan 10, reference 11`
+    pub differences: Vec<String>,
+}
+
+/// Outcome of `--verify`
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Verification {
+    pub files_checked: usize,
+    pub files_total: usize,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+/// How a directory fared against one of its keyword budgets
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BudgetResult {
+    pub directory: PathBuf,
+    pub keyword: String,
+    pub max: usize,
+    pub count: usize,
+    /// Config file the budget was declared in
+    pub source: PathBuf,
+    /// Count in the report compared against, see `Report::compare_budgets`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_count: Option<usize>,
+}
+
+impl BudgetResult {
+    pub fn exceeded(&self) -> bool {
+        self.count > self.max
+    }
+
+    /// Change since the base report, when there is one
+    pub fn delta(&self) -> Option<isize> {
+        self.base_count
+            .map(|base_count| self.count as isize - base_count as isize)
+    }
+}
+
+/// Where one finding of a cluster is
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ClusterLocation {
+    pub path: PathBuf,
+    pub line: usize,
+}
+
+/// Findings in several files that say nearly the same thing, see
+/// `cluster::annotate`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Cluster {
+    /// Most common wording among the findings, normalized
+    pub message: String,
+    pub keywords: Vec<String>,
+    pub locations: Vec<ClusterLocation>,
+}
+
+impl Cluster {
+    pub fn file_count(&self) -> usize {
+        self.locations
+            .iter()
+            .map(|location| &location.path)
+            .collect::<HashSet<&PathBuf>>()
+            .len()
+    }
+}
+
+/// Files with the same contents, see `duplicates::annotate`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DuplicateSet {
+    /// Content hash the files share
+    pub hash: String,
+    /// Size of each of the files
+    pub bytes: u64,
+    pub paths: Vec<PathBuf>,
+}
+
+impl DuplicateSet {
+    /// Bytes taken up by every copy past the first
+    pub fn wasted_bytes(&self) -> u64 {
+        self.bytes * (self.paths.len() as u64 - 1)
+    }
+}
+
+/// Most findings of one keyword a scan may have, e.g. `FIXME=0`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Threshold {
+    pub keyword: String,
+    pub max: usize,
+}
+
+impl FromStr for Threshold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.rsplit_once('=') {
+            Some((keyword, max)) if !keyword.trim().is_empty() => Ok(Threshold {
+                keyword: text::nfc(keyword.trim()).into_owned(),
+                max: max.trim().parse().map_err(|_| {
+                    format!("Threshold {:?} has to be a whole number of findings", s)
+                })?,
+            }),
+            _ => Err(format!("Expected a threshold like FIXME=0, found {:?}", s)),
+        }
+    }
+}
+
+/// A number of bytes, written plainly or with a binary unit, e.g. `10M`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ByteSize(pub u64);
+
+impl FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed: &str = s.trim();
+        let digits: usize = trimmed
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(trimmed.len());
+        let (number, unit): (&str, &str) = trimmed.split_at(digits);
+
+        let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+            "" | "B" => 1,
+            "K" | "KB" | "KIB" => 1 << 10,
+            "M" | "MB" | "MIB" => 1 << 20,
+            "G" | "GB" | "GIB" => 1 << 30,
+            _ => return Err(format!("Expected a size like 500K or 10M, found {:?}", s)),
+        };
+        number
+            .parse::<u64>()
+            .ok()
+            .and_then(|number| number.checked_mul(multiplier))
+            .map(ByteSize)
+            .ok_or_else(|| format!("Expected a size like 500K or 10M, found {:?}", s))
+    }
+}
+
+/// Everything found while profiling a directory
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Report {
+    /// Hash of the remote URL of the repository that was profiled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    #[serde(default)]
+    pub metrics: Metrics,
+    pub keyword_table: BTreeMap<String, usize>,
+    /// Severity of each keyword as configured at the scan root, where it
+    /// differs from the default
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub severities: BTreeMap<String, Severity>,
+    /// Keywords found inside documentation comments, counted apart from
+    /// `keyword_table`
+    pub doc_keyword_table: BTreeMap<String, usize>,
+    pub filetype_table: BTreeMap<String, usize>,
+    /// Total size of the files of each language, counted in `filetype_table`
+    #[serde(default)]
+    pub language_byte_counts: BTreeMap<String, u64>,
+    pub language_line_counts: BTreeMap<String, LineCounts>,
+    /// Doc comment lines of each language, counted in `language_line_counts`
+    /// as comments
+    #[serde(default)]
+    pub language_doc_line_counts: BTreeMap<String, usize>,
+    /// Keyword hits left out of every table since they were dismissed
+    pub dismissed_count: usize,
+    /// Keyword hits left out of every table since they are in the baseline
+    #[serde(default)]
+    pub baselined_count: usize,
+    /// Files left unread for being bigger than `--max-filesize`
+    #[serde(default)]
+    pub oversized_file_count: usize,
+    /// Files left unread for being binary, by extension or a null byte
+    /// near their start
+    #[serde(default)]
+    pub binary_file_count: usize,
+    /// Files left unread since no language recognizes them, by name or by
+    /// their first lines
+    #[serde(default)]
+    pub unrecognized_file_count: usize,
+    /// Directories left unwalked for being deeper than `--max-depth`
+    #[serde(default)]
+    pub too_deep_directory_count: usize,
+    /// Keyword hits left out of every table by a `pursue:ignore` or
+    /// `pursue:ignore-file` directive
+    #[serde(default)]
+    pub suppressed_count: usize,
+    /// Findings per last author of their line, with `--blame`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub author_table: BTreeMap<String, usize>,
+    /// Age after which findings count as stale, with `--blame`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u64>,
+    #[serde(default)]
+    pub stale_count: usize,
+    /// Findings per assignee named in the marker, `TODO(alice)`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub assignee_table: BTreeMap<String, usize>,
+    /// How many files and markers the ranked summaries list, with `--top`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top: Option<usize>,
+    /// Whether the table is printed with ANSI colors, with `--color`
+    #[serde(skip)]
+    pub color: bool,
+    pub budgets: Vec<BudgetResult>,
+    /// Results of checking the scan against a reference pass, with `--verify`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<Verification>,
+    /// Files and directories that could not be read, so the counts may be
+    /// short of them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Warning>,
+    /// Similar findings across files, with `--clusters`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clusters: Vec<Cluster>,
+    /// The header every file with comments was checked for, with
+    /// `--check-license`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license_check: Option<LicenseCheck>,
+    /// Characters a line could have before it counted as long, with
+    /// `--hygiene`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_line_length: Option<usize>,
+    /// Sets of files with the same contents, with `--duplicates`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub duplicates: Vec<DuplicateSet>,
+    /// Files left out of a merged report as copies of a file from another
+    /// repository
+    #[serde(default)]
+    pub deduplicated_file_count: usize,
+    pub files: Vec<FileReport>,
+}
+
+/// `path` relative to `from` when it is inside of it, `.` for `from` itself
+fn relative_to(path: &mut PathBuf, from: &Path) {
+    if let Ok(relative) = path.strip_prefix(from) {
+        *path = match relative.as_os_str().is_empty() {
+            true => PathBuf::from("."),
+            false => relative.to_path_buf(),
+        };
+    }
+}
+
+impl Report {
+    /// Make every path inside `from` relative to it, for the report of a
+    /// checkout that is removed before the report is read
+    pub fn relocate(&mut self, from: &Path) {
+        for file in &mut self.files {
+            relative_to(&mut file.path, from);
+            if let Some(archive) = &mut file.archive {
+                relative_to(archive, from);
+            }
+        }
+        for budget in &mut self.budgets {
+            relative_to(&mut budget.directory, from);
+            relative_to(&mut budget.source, from);
+        }
+        for warning in &mut self.warnings {
+            relative_to(&mut warning.path, from);
+        }
+        if let Some(verification) = &mut self.verification {
+            for discrepancy in &mut verification.discrepancies {
+                relative_to(&mut discrepancy.path, from);
+            }
+        }
+        for cluster in &mut self.clusters {
+            for location in &mut cluster.locations {
+                relative_to(&mut location.path, from);
+            }
+        }
+        for duplicate in &mut self.duplicates {
+            for path in &mut duplicate.paths {
+                relative_to(path, from);
+            }
+        }
+    }
+
+    /// Every keyword hit along with the file it was found in
+    pub fn occurrences(&self) -> impl Iterator<Item = (&PathBuf, &Occurrence)> {
+        self.files.iter().flat_map(|file| {
+            file.occurrences
+                .iter()
+                .map(move |occurrence| (&file.path, occurrence))
+        })
+    }
+
+    /// How many findings there are of every severity, most urgent first
+    pub fn severity_counts(&self) -> Vec<(Severity, usize)> {
+        Severity::ALL
+            .iter()
+            .map(|severity| {
+                (
+                    *severity,
+                    self.occurrences()
+                        .filter(|(_, occurrence)| occurrence.severity == *severity)
+                        .count(),
+                )
+            })
+            .collect()
+    }
+
+    /// Findings at least as urgent as `severity`
+    pub fn findings_at_least(&self, severity: Severity) -> usize {
+        self.occurrences()
+            .filter(|(_, occurrence)| occurrence.severity >= severity)
+            .count()
+    }
+
+    /// The `count` files with the most findings per 100 lines, densest
+    /// first, along with that density
+    pub fn densest_files(&self, count: usize) -> Vec<(&FileReport, f64)> {
+        let mut files: Vec<(&FileReport, f64)> = self
+            .files
+            .iter()
+            .filter(|file| !file.occurrences.is_empty())
+            .map(|file| {
+                (
+                    file,
+                    file.occurrences.len() as f64 * 100.0 / file.line_count.max(1) as f64,
+                )
+            })
+            .collect();
+        files.sort_by(|(left, left_density), (right, right_density)| {
+            right_density
+                .total_cmp(left_density)
+                .then(right.occurrences.len().cmp(&left.occurrences.len()))
+                .then(left.path.cmp(&right.path))
+        });
+        files.truncate(count);
+        files
+    }
+
+    /// The `count` findings whose line was last touched longest ago, oldest
+    /// first. Only findings attributed with `--blame` have an age
+    pub fn oldest_markers(&self, count: usize) -> Vec<(&PathBuf, &Occurrence)> {
+        let mut markers: Vec<(&PathBuf, &Occurrence)> = self
+            .occurrences()
+            .filter(|(_, occurrence)| occurrence.authored.is_some())
+            .collect();
+        markers.sort_by_key(|(path, occurrence)| (occurrence.authored, *path, occurrence.line));
+        markers.truncate(count);
+        markers
+    }
+
+    /// Files checked for a license header that lack one
+    pub fn unlicensed_files(&self) -> impl Iterator<Item = &FileReport> {
+        self.files
+            .iter()
+            .filter(|file| file.license_header == Some(false))
+    }
+
+    /// How many of the files checked against `license` lack a header
+    fn license_summary(&self, license: &LicenseCheck) -> String {
+        let checked: usize = self
+            .files
+            .iter()
+            .filter(|file| file.license_header.is_some())
+            .count();
+        format!(
+            "{} of {} files lack a license header ({}) in their first {} lines",
+            self.unlicensed_files().count(),
+            checked,
+            license.patterns.join(" or "),
+            license.lines
+        )
+    }
+
+    /// Untidy lines of the files checked with `--hygiene`, and how many
+    /// files were checked, per language
+    pub fn hygiene_by_language(&self) -> BTreeMap<&str, (usize, Hygiene)> {
+        let mut languages: BTreeMap<&str, (usize, Hygiene)> = BTreeMap::new();
+        for file in &self.files {
+            if let Some(hygiene) = file.hygiene {
+                let (files, total) = languages.entry(file.file_type.as_str()).or_default();
+                *files += 1;
+                *total += hygiene;
+            }
+        }
+        languages
+    }
+
+    pub fn exceeded_budgets(&self) -> impl Iterator<Item = &BudgetResult> {
+        self.budgets.iter().filter(|budget| budget.exceeded())
+    }
+
+    /// Files, lines, and bytes of every scanned language, by name
+    pub fn language_breakdown(&self) -> Vec<LanguageSummary<'_>> {
+        self.filetype_table
+            .iter()
+            .map(|(name, &files)| LanguageSummary {
+                name,
+                files,
+                lines: self
+                    .language_line_counts
+                    .get(name)
+                    .copied()
+                    .unwrap_or_default(),
+                doc_lines: self
+                    .language_doc_line_counts
+                    .get(name)
+                    .copied()
+                    .unwrap_or(0),
+                bytes: self.language_byte_counts.get(name).copied().unwrap_or(0),
+            })
+            .collect()
+    }
+
+    /// Mean size of the files of `language`, rounded down
+    pub fn average_file_size(&self, language: &str) -> u64 {
+        match self.filetype_table.get(language) {
+            Some(&count) if count > 0 => {
+                self.language_byte_counts
+                    .get(language)
+                    .copied()
+                    .unwrap_or(0)
+                    / count as u64
+            }
+            _ => 0,
+        }
+    }
+
+    /// Files found but left unread: unrecognized, binary, or oversized
+    pub fn skipped_file_count(&self) -> usize {
+        self.unrecognized_file_count + self.binary_file_count + self.oversized_file_count
+    }
+
+    /// Findings of `keyword`, in doc comments or not
+    pub fn keyword_count(&self, keyword: &str) -> usize {
+        [&self.keyword_table, &self.doc_keyword_table]
+            .iter()
+            .filter_map(|table| table.get(keyword))
+            .sum()
+    }
+
+    /// Thresholds this report has more findings than, with those counts
+    pub fn exceeded_thresholds<'a>(
+        &self,
+        thresholds: &'a [Threshold],
+    ) -> Vec<(&'a Threshold, usize)> {
+        thresholds
+            .iter()
+            .map(|threshold| (threshold, self.keyword_count(&threshold.keyword)))
+            .filter(|(threshold, count)| *count > threshold.max)
+            .collect()
+    }
+
+    /// Load a report previously printed with `--format json`
+    pub fn load(path: &Path) -> Result<Self, std::io::Error> {
+        let contents: String = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|error| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} is not a JSON report: {}", path.display(), error),
+            )
+        })
+    }
+
+    /// Combine the reports of several repositories into one. A repository
+    /// reported twice is only counted once, and a file whose contents were
+    /// already seen in another repository is dropped as a vendored copy
+    pub fn merge(reports: Vec<Report>) -> Report {
+        let mut merged: Report = Report {
+            repository: None,
+            metrics: Metrics::default(),
+            keyword_table: BTreeMap::new(),
+            severities: BTreeMap::new(),
+            doc_keyword_table: BTreeMap::new(),
+            filetype_table: BTreeMap::new(),
+            language_byte_counts: BTreeMap::new(),
+            language_line_counts: BTreeMap::new(),
+            language_doc_line_counts: BTreeMap::new(),
+            dismissed_count: 0,
+            baselined_count: 0,
+            suppressed_count: 0,
+            oversized_file_count: 0,
+            binary_file_count: 0,
+            unrecognized_file_count: 0,
+            too_deep_directory_count: 0,
+            author_table: BTreeMap::new(),
+            max_age_days: None,
+            top: None,
+            color: false,
+            stale_count: 0,
+            assignee_table: BTreeMap::new(),
+            verification: None,
+            warnings: Vec::new(),
+            budgets: Vec::new(),
+            clusters: Vec::new(),
+            duplicates: Vec::new(),
+            license_check: None,
+            max_line_length: None,
+            deduplicated_file_count: 0,
+            files: Vec::new(),
+        };
+
+        let mut repositories: HashSet<String> = HashSet::new();
+        // Content hash to the repository it was first seen in
+        let mut owners: HashMap<String, String> = HashMap::new();
+
+        for (index, report) in reports.into_iter().enumerate() {
+            let repository: String = match report.repository {
+                Some(repository) => match repositories.insert(repository.clone()) {
+                    true => repository,
+                    false => continue,
+                },
+                None => format!("#{}", index),
+            };
+
+            merged.metrics += report.metrics;
+            merged.dismissed_count += report.dismissed_count;
+            merged.baselined_count += report.baselined_count;
+            merged.suppressed_count += report.suppressed_count;
+            merged.oversized_file_count += report.oversized_file_count;
+            merged.binary_file_count += report.binary_file_count;
+            merged.unrecognized_file_count += report.unrecognized_file_count;
+            merged.warnings.extend(report.warnings);
+            merged.too_deep_directory_count += report.too_deep_directory_count;
+            merged.stale_count += report.stale_count;
+            merged.max_age_days = merged.max_age_days.or(report.max_age_days);
+            merged.top = merged.top.or(report.top);
+            merged.max_line_length = merged.max_line_length.or(report.max_line_length);
+            merged.color |= report.color;
+            merged.deduplicated_file_count += report.deduplicated_file_count;
+            merged.budgets.extend(report.budgets);
+            for (keyword, severity) in report.severities {
+                merged.severities.entry(keyword).or_insert(severity);
+            }
+            for (into, from) in [
+                (&mut merged.keyword_table, report.keyword_table),
+                (&mut merged.doc_keyword_table, report.doc_keyword_table),
+                (&mut merged.filetype_table, report.filetype_table),
+                (&mut merged.author_table, report.author_table),
+                (&mut merged.assignee_table, report.assignee_table),
+            ] {
+                for (key, count) in from {
+                    *into.entry(key).or_insert(0) += count;
+                }
+            }
+            for (language, counts) in report.language_line_counts {
+                *merged.language_line_counts.entry(language).or_default() += counts;
+            }
+            for (language, bytes) in report.language_byte_counts {
+                *merged.language_byte_counts.entry(language).or_default() += bytes;
+            }
+            for (language, lines) in report.language_doc_line_counts {
+                *merged.language_doc_line_counts.entry(language).or_default() += lines;
+            }
+
+            for file in report.files {
+                let is_copy: bool = match &file.content_hash {
+                    Some(hash) if file.line_count > 0 => {
+                        let owner: &String = owners
+                            .entry(hash.clone())
+                            .or_insert_with(|| repository.clone());
+                        *owner != repository
+                    }
+                    _ => false,
+                };
+
+                match is_copy {
+                    true => merged.remove_counts(&file),
+                    false => merged.files.push(file),
+                }
+            }
+        }
+
+        merged
+    }
+
+    /// Take everything `file` contributed back out of the totals
+    fn remove_counts(&mut self, file: &FileReport) {
+        fn decrement(table: &mut BTreeMap<String, usize>, key: &str, amount: usize) {
+            if let Some(count) = table.get_mut(key) {
+                *count = count.saturating_sub(amount);
+            }
+        }
+
+        self.metrics -= file.metrics();
+        for occurrence in &file.occurrences {
+            if let Some(author) = &occurrence.author {
+                decrement(&mut self.author_table, author, 1);
+            }
+            if let Some(assignee) = &occurrence.assignee {
+                decrement(&mut self.assignee_table, assignee, 1);
+            }
+            if let (Some(max_age_days), Some(authored)) = (self.max_age_days, occurrence.authored)
+                && authored < crate::blame::cutoff(max_age_days)
+            {
+                self.stale_count = self.stale_count.saturating_sub(1);
+            }
+            match occurrence.in_doc_comment {
+                true => decrement(&mut self.doc_keyword_table, &occurrence.keyword, 1),
+                false => decrement(&mut self.keyword_table, &occurrence.keyword, 1),
+            }
+        }
+        decrement(&mut self.filetype_table, &file.file_type, 1);
+        if let Some(bytes) = self.language_byte_counts.get_mut(&file.file_type) {
+            *bytes = bytes.saturating_sub(file.bytes);
+        }
+        if let Some(counts) = self.language_line_counts.get_mut(&file.file_type) {
+            *counts -= file.line_counts;
+        }
+        decrement(
+            &mut self.language_doc_line_counts,
+            &file.file_type,
+            file.doc_comment_line_count,
+        );
+        self.deduplicated_file_count += 1;
+    }
+
+    /// Record the counts `base` had for each budget so deltas can be shown
+    pub fn compare_budgets(&mut self, base: &Report) {
+        for budget in &mut self.budgets {
+            budget.base_count = base
+                .budgets
+                .iter()
+                .find(|other| {
+                    other.directory == budget.directory && other.keyword == budget.keyword
+                })
+                .map(|other| other.count);
+        }
+    }
+
+    pub fn print(&self, format: OutputFormat) -> Result<(), std::io::Error> {
+        self.write(format, &mut std::io::stdout().lock())
+    }
+
+    /// Render in `format` into `writer`
+    pub fn write(
+        &self,
+        format: OutputFormat,
+        writer: &mut dyn std::io::Write,
+    ) -> Result<(), std::io::Error> {
+        match format {
+            OutputFormat::Table => writer.write_all(self.table().as_bytes()),
+            OutputFormat::Json => self.write_json(writer),
+            OutputFormat::Markdown => writer.write_all(self.markdown().as_bytes()),
+            OutputFormat::Csv => writer.write_all(self.csv(false).as_bytes()),
+            OutputFormat::TokeiJson => writeln!(
+                writer,
+                "{}",
+                serde_json::to_string_pretty(&self.tokei_json())?
+            ),
+            OutputFormat::ClocJson => {
+                writeln!(
+                    writer,
+                    "{}",
+                    serde_json::to_string_pretty(&self.cloc_json())?
+                )
+            }
+            OutputFormat::Html => writer.write_all(crate::html::standalone(self).as_bytes()),
+            OutputFormat::Jsonl => {
+                for (path, occurrence) in self.occurrences() {
+                    writeln!(writer, "{}", occurrence.json_line(path)?)?;
+                }
+                Ok(())
+            }
+            OutputFormat::Sarif => writeln!(
+                writer,
+                "{}",
+                serde_json::to_string_pretty(&self.sarif_json())?
+            ),
+        }
+    }
+
+    /// Everything as aligned tables, the default output
+    pub fn table(&self) -> String {
+        let mut table: String = String::new();
+        let partial_count: usize = self.files.iter().filter(|file| file.partial).count();
+        let mut summary: Table = Table::new(&[("", 20, Align::Left), ("", 10, Align::Left)]);
+        for (label, value, always_shown) in [
+            ("Files", self.metrics.files, true),
+            ("Files skipped", self.skipped_file_count(), true),
+            ("Unrecognized files", self.unrecognized_file_count, false),
+            ("Binary files skipped", self.binary_file_count, false),
+            ("Oversized files", self.oversized_file_count, false),
+            ("Lines", self.metrics.lines, true),
+            ("Code lines", self.metrics.code_lines, true),
+            ("Comment lines", self.metrics.comment_lines, true),
+            ("Blank lines", self.metrics.blank_lines, true),
+            ("Doc comment lines", self.metrics.doc_comment_lines, true),
+            ("Findings", self.metrics.findings, true),
+            ("Dismissed findings", self.dismissed_count, true),
+            ("Baselined findings", self.baselined_count, false),
+            ("Suppressed findings", self.suppressed_count, false),
+            ("Partially read files", partial_count, false),
+            ("Dirs past max depth", self.too_deep_directory_count, false),
+            ("Vendored copies", self.deduplicated_file_count, false),
+        ] {
+            if always_shown || value > 0 {
+                summary.row([label.to_string(), value.to_string()]);
+            }
+        }
+        table += &summary.render(self.color);
+        table.push('\n');
+
+        // Keywords take the color of their findings' severity
+        let severities: HashMap<&str, Severity> = self
+            .occurrences()
+            .map(|(_, occurrence)| (occurrence.keyword.as_str(), occurrence.severity))
+            .collect();
+        for (heading, keyword_table) in [
+            ("Key Comment", &self.keyword_table),
+            ("Doc Key Comment", &self.doc_keyword_table),
+        ] {
+            let mut keywords: Table =
+                Table::new(&[(heading, 20, Align::Left), ("Frequency", 15, Align::Left)]);
+            for (key, frequency) in keyword_table {
+                keywords.colored_row([
+                    (
+                        key.clone(),
+                        severities.get(key.as_str()).map(Severity::ansi_color),
+                    ),
+                    (frequency.to_string(), None),
+                ]);
+            }
+            if heading != "Key Comment" {
+                table.push('\n');
+            }
+            table += &keywords.render(self.color);
+        }
+
+        let mut severity_table: Table =
+            Table::new(&[("Severity", 20, Align::Left), ("Findings", 15, Align::Left)]);
+        for (severity, count) in self.severity_counts() {
+            severity_table.colored_row([
+                (severity.to_string(), Some(severity.ansi_color())),
+                (count.to_string(), None),
+            ]);
+        }
+        table.push('\n');
+        table += &severity_table.render(self.color);
+
+        let mut languages: Table = Table::new(&[
+            ("Language", 20, Align::Left),
+            ("Files", 8, Align::Right),
+            ("Lines", 9, Align::Right),
+            ("Code", 9, Align::Right),
+            ("Comment", 9, Align::Right),
+            ("Blank", 9, Align::Right),
+            ("Doc", 9, Align::Right),
+            ("Doc per 100 LOC", 15, Align::Right),
+            ("Bytes", 10, Align::Right),
+            ("Avg Bytes", 9, Align::Right),
+        ]);
+        let mut total: LanguageSummary = LanguageSummary {
+            name: "Total",
+            files: 0,
+            lines: LineCounts::default(),
+            doc_lines: 0,
+            bytes: 0,
+        };
+        for language in self.language_breakdown() {
+            languages.row([
+                language.name.to_string(),
+                language.files.to_string(),
+                language.lines.total().to_string(),
+                language.lines.code.to_string(),
+                language.lines.comment.to_string(),
+                language.lines.blank.to_string(),
+                language.doc_lines.to_string(),
+                language.documentation_label(),
+                language.bytes.to_string(),
+                self.average_file_size(language.name).to_string(),
+            ]);
+            total.files += language.files;
+            total.lines += language.lines;
+            total.doc_lines += language.doc_lines;
+            total.bytes += language.bytes;
+        }
+        languages.rule();
+        languages.colored_row(
+            [
+                total.name.to_string(),
+                total.files.to_string(),
+                total.lines.total().to_string(),
+                total.lines.code.to_string(),
+                total.lines.comment.to_string(),
+                total.lines.blank.to_string(),
+                total.doc_lines.to_string(),
+                total.documentation_label(),
+                total.bytes.to_string(),
+                match total.files {
+                    0 => 0,
+                    files => total.bytes / files as u64,
+                }
+                .to_string(),
+            ]
+            .map(|cell| (cell, Some(BOLD))),
+        );
+        table.push('\n');
+        table += &languages.render(self.color);
+
+        if let Some(max_age_days) = self.max_age_days {
+            let mut authors: Table =
+                Table::new(&[("Author", 20, Align::Left), ("Frequency", 15, Align::Left)]);
+            for (author, frequency) in &self.author_table {
+                authors.row([author.clone(), frequency.to_string()]);
+            }
+            table.push('\n');
+            table += &authors.render(self.color);
+
+            let _ = writeln!(
+                table,
+                "\n{} markers are older than {} days",
+                self.stale_count, max_age_days
+            );
+            let cutoff: u64 = crate::blame::cutoff(max_age_days);
+            for (path, occurrence) in self.occurrences() {
+                if let (Some(author), Some(authored)) = (&occurrence.author, occurrence.authored)
+                    && authored < cutoff
+                {
+                    let _ = writeln!(
+                        table,
+                        "  {} {} {}:{} {}",
+                        crate::blame::format_date(authored),
+                        author,
+                        path.display(),
+                        occurrence.line,
+                        occurrence.keyword
+                    );
+                }
+            }
+        }
+
+        if let Some(top) = self.top {
+            let _ = writeln!(table, "\nTop {} files by findings per 100 lines:", top);
+            for (file, density) in self.densest_files(top) {
+                let _ = writeln!(
+                    table,
+                    "  {: >7.2} {: >5} findings  {}",
+                    density,
+                    file.occurrences.len(),
+                    file.path.display()
+                );
+            }
+
+            let oldest: Vec<(&PathBuf, &Occurrence)> = self.oldest_markers(top);
+            if !oldest.is_empty() {
+                let _ = writeln!(table, "\nOldest {} markers:", oldest.len());
+                for (path, occurrence) in oldest {
+                    let _ = writeln!(
+                        table,
+                        "  {} {} {}:{}:{} {}",
+                        occurrence
+                            .authored
+                            .map_or(String::new(), crate::blame::format_date),
+                        occurrence.author.as_deref().unwrap_or("unknown"),
+                        path.display(),
+                        occurrence.line,
+                        occurrence.column,
+                        occurrence.keyword
+                    );
+                }
+            }
+        }
+
+        if !self.assignee_table.is_empty() {
+            let mut assignees: Table = Table::new(&[
+                ("Assignee", 20, Align::Left),
+                ("Open markers", 15, Align::Left),
+            ]);
+            for (assignee, frequency) in &self.assignee_table {
+                assignees.row([assignee.clone(), frequency.to_string()]);
+            }
+            table.push('\n');
+            table += &assignees.render(self.color);
+        }
+
+        for cluster in &self.clusters {
+            let _ = writeln!(
+                table,
+                "\n{} similar {} in {} files: {:?}",
+                cluster.locations.len(),
+                cluster.keywords.join("/"),
+                cluster.file_count(),
+                cluster.message
+            );
+            for location in &cluster.locations {
+                let _ = writeln!(table, "  {}:{}", location.path.display(), location.line);
+            }
+        }
+
+        if let Some(license) = &self.license_check {
+            let _ = writeln!(table, "\n{}", self.license_summary(license));
+            for file in self.unlicensed_files() {
+                let _ = writeln!(table, "  {}", file.path.display());
+            }
+        }
+
+        for duplicate in &self.duplicates {
+            let _ = writeln!(
+                table,
+                "\n{} identical files of {} bytes, {} bytes in copies:",
+                duplicate.paths.len(),
+                duplicate.bytes,
+                duplicate.wasted_bytes()
+            );
+            for path in &duplicate.paths {
+                let _ = writeln!(table, "  {}", path.display());
+            }
+        }
+
+        if let Some(verification) = &self.verification {
+            let _ = writeln!(
+                table,
+                "\nVerified {} of {} files with a single threaded pass: {} discrepancies",
+                verification.files_checked,
+                verification.files_total,
+                verification.discrepancies.len()
+            );
+            for discrepancy in &verification.discrepancies {
+                let _ = writeln!(
+                    table,
+                    "  {}: {}",
+                    discrepancy.path.display(),
+                    discrepancy.differences.join("; ")
+                );
+            }
+        }
+
+        if !self.budgets.is_empty() {
+            let mut budgets: Table = Table::new(&[
+                ("Budget", 30, Align::Left),
+                ("Keyword", 10, Align::Left),
+                ("Count", 8, Align::Left),
+                ("Max", 8, Align::Left),
+                ("Status", 6, Align::Left),
+            ]);
+            for budget in &self.budgets {
+                let status: (&str, Option<&'static str>) = match budget.exceeded() {
+                    true => ("FAIL", Some(RED)),
+                    false => ("PASS", Some(GREEN)),
+                };
+                budgets.colored_row([
+                    (budget.directory.display().to_string(), None),
+                    (budget.keyword.clone(), None),
+                    (budget.count.to_string(), None),
+                    (budget.max.to_string(), None),
+                    (status.0.to_string(), status.1),
+                ]);
+            }
+            table.push('\n');
+            table += &budgets.render(self.color);
+        }
+
+        if self.files.iter().any(|file| file.health.is_some()) {
+            let mut health: Table = Table::new(&[
+                ("File health", 30, Align::Left),
+                ("Lines", 7, Align::Right),
+                ("Longest", 7, Align::Right),
+                ("Average", 7, Align::Right),
+                ("Deepest", 7, Align::Right),
+                ("Functions", 9, Align::Right),
+            ]);
+            for file in &self.files {
+                let Some(file_health) = &file.health else {
+                    continue;
+                };
+                health.row([
+                    file.path.display().to_string(),
+                    file.line_count.to_string(),
+                    file_health.max_line_length.to_string(),
+                    format!("{:.1}", file_health.average_line_length(file.line_count)),
+                    file_health.max_indentation().to_string(),
+                    file_health
+                        .functions
+                        .map_or("-".to_string(), |functions| functions.to_string()),
+                ]);
+            }
+            table.push('\n');
+            table += &health.render(self.color);
+        }
+
+        if let Some(max_line_length) = self.max_line_length {
+            let mut hygiene: Table = Table::new(&[
+                ("Hygiene", 12, Align::Left),
+                ("Files", 5, Align::Right),
+                ("Trailing Space", 14, Align::Right),
+                ("Mixed Indent", 12, Align::Right),
+                ("Long Lines", 10, Align::Right),
+            ]);
+            let mut total: (usize, Hygiene) = (0, Hygiene::default());
+            for (language, (files, language_hygiene)) in self.hygiene_by_language() {
+                hygiene.row([
+                    language.to_string(),
+                    files.to_string(),
+                    language_hygiene.trailing_whitespace.to_string(),
+                    language_hygiene.mixed_indentation.to_string(),
+                    language_hygiene.long_lines.to_string(),
+                ]);
+                total.0 += files;
+                total.1 += language_hygiene;
+            }
+            hygiene.rule();
+            hygiene.colored_row(
+                [
+                    "Total".to_string(),
+                    total.0.to_string(),
+                    total.1.trailing_whitespace.to_string(),
+                    total.1.mixed_indentation.to_string(),
+                    total.1.long_lines.to_string(),
+                ]
+                .map(|cell| (cell, Some(BOLD))),
+            );
+            let _ = writeln!(
+                table,
+                "\nLong lines are over {} characters",
+                max_line_length
+            );
+            table += &hygiene.render(self.color);
+        }
+
+        if !self.warnings.is_empty() {
+            let mut warnings: Table =
+                Table::new(&[("Warning", 30, Align::Left), ("Problem", 20, Align::Left)]);
+            for warning in &self.warnings {
+                warnings.row([warning.path.display().to_string(), warning.message.clone()]);
+            }
+            table.push('\n');
+            table += &warnings.render(self.color);
+        }
+        table
+    }
+
+    pub fn markdown(&self) -> String {
+        let mut markdown: String = String::from("## Pursue report\n\n");
+        markdown += "| Metric | Value |\n|---|---:|\n";
+        for (name, value) in self.metrics.entries() {
+            let _ = writeln!(markdown, "| {} | {} |", name, value);
+        }
+        let _ = writeln!(
+            markdown,
+            "\n{} dismissed findings, {} baselined findings, {} suppressed findings\n",
+            self.dismissed_count, self.baselined_count, self.suppressed_count
+        );
+        if self.skipped_file_count() > 0 {
+            let _ = writeln!(
+                markdown,
+                "{} files skipped: {} unrecognized, {} binary, {} oversized\n",
+                self.skipped_file_count(),
+                self.unrecognized_file_count,
+                self.binary_file_count,
+                self.oversized_file_count
+            );
+        }
+
+        markdown += "| Keyword | Count | In doc comments |\n|---|---:|---:|\n";
+        for (keyword, count) in &self.keyword_table {
+            let _ = writeln!(
+                markdown,
+                "| {} | {} | {} |",
+                keyword,
+                count,
+                self.doc_keyword_table.get(keyword).copied().unwrap_or(0)
+            );
+        }
+        let severities: Vec<String> = self
+            .severity_counts()
+            .into_iter()
+            .map(|(severity, count)| format!("{} {}", count, severity))
+            .collect();
+        let _ = writeln!(markdown, "\n**Severity:** {}", severities.join(", "));
+
+        markdown += "\n### Languages\n\n| Language | Files | Lines | Code | Comment | Blank | Doc | Doc per 100 LOC |\n|---|---:|---:|---:|---:|---:|---:|---:|\n";
+        for language in self.language_breakdown() {
+            let _ = writeln!(
+                markdown,
+                "| {} | {} | {} | {} | {} | {} | {} | {} |",
+                language.name,
+                language.files,
+                language.lines.total(),
+                language.lines.code,
+                language.lines.comment,
+                language.lines.blank,
+                language.doc_lines,
+                language.documentation_label()
+            );
+        }
+
+        if !self.assignee_table.is_empty() {
+            markdown += "\n### By assignee\n\n| Assignee | Open markers |\n|---|---:|\n";
+            for (assignee, count) in &self.assignee_table {
+                let _ = writeln!(markdown, "| {} | {} |", assignee, count);
+            }
+        }
+
+        if let Some(top) = self.top {
+            markdown +=
+                "\n### Densest files\n\n| File | Findings | Per 100 lines |\n|---|---:|---:|\n";
+            for (file, density) in self.densest_files(top) {
+                let _ = writeln!(
+                    markdown,
+                    "| `{}` | {} | {:.2} |",
+                    file.path.display(),
+                    file.occurrences.len(),
+                    density
+                );
+            }
+
+            let oldest: Vec<(&PathBuf, &Occurrence)> = self.oldest_markers(top);
+            if !oldest.is_empty() {
+                markdown += "\n### Oldest markers\n\n| Last touched | Author | Where | Keyword |\n|---|---|---|---|\n";
+                for (path, occurrence) in oldest {
+                    let _ = writeln!(
+                        markdown,
+                        "| {} | {} | `{}:{}:{}` | {} |",
+                        occurrence
+                            .authored
+                            .map_or(String::new(), crate::blame::format_date),
+                        occurrence.author.as_deref().unwrap_or("unknown"),
+                        path.display(),
+                        occurrence.line,
+                        occurrence.column,
+                        occurrence.keyword
+                    );
+                }
+            }
+        }
+
+        if !self.clusters.is_empty() {
+            markdown += "\n### Similar findings\n\n";
+            markdown += "| Message | Keywords | Findings | Files |\n|---|---|---:|---:|\n";
+            for cluster in &self.clusters {
+                let _ = writeln!(
+                    markdown,
+                    "| {} | {} | {} | {} |",
+                    cluster.message,
+                    cluster.keywords.join(", "),
+                    cluster.locations.len(),
+                    cluster.file_count()
+                );
+            }
+        }
+
+        if let Some(license) = &self.license_check {
+            let _ = writeln!(
+                markdown,
+                "\n### License headers\n\n{}",
+                self.license_summary(license)
+            );
+            for file in self.unlicensed_files() {
+                let _ = writeln!(markdown, "- `{}`", file.path.display());
+            }
+        }
+
+        if let Some(max_line_length) = self.max_line_length {
+            let _ = writeln!(
+                markdown,
+                "\n### Hygiene\n\nLong lines are over {} characters.\n",
+                max_line_length
+            );
+            markdown +=
+                "| Language | Files | Trailing whitespace | Mixed indentation | Long lines |\n";
+            markdown += "|---|---:|---:|---:|---:|\n";
+            for (language, (files, hygiene)) in self.hygiene_by_language() {
+                let _ = writeln!(
+                    markdown,
+                    "| {} | {} | {} | {} | {} |",
+                    language,
+                    files,
+                    hygiene.trailing_whitespace,
+                    hygiene.mixed_indentation,
+                    hygiene.long_lines
+                );
+            }
+        }
+
+        if !self.duplicates.is_empty() {
+            markdown += "\n### Duplicate files\n\n";
+            markdown += "| Files | Bytes each | Bytes in copies |\n|---|---:|---:|\n";
+            for duplicate in &self.duplicates {
+                let _ = writeln!(
+                    markdown,
+                    "| {} | {} | {} |",
+                    duplicate
+                        .paths
+                        .iter()
+                        .map(|path| format!("`{}`", path.display()))
+                        .collect::<Vec<String>>()
+                        .join("<br>"),
+                    duplicate.bytes,
+                    duplicate.wasted_bytes()
+                );
+            }
+        }
+
+        if !self.warnings.is_empty() {
+            markdown += "\n### Warnings\n\n";
+            for warning in &self.warnings {
+                let _ = writeln!(
+                    markdown,
+                    "- `{}`: {}",
+                    warning.path.display(),
+                    warning.message
+                );
+            }
+        }
+
+        if self.budgets.is_empty() {
+            return markdown;
+        }
+
+        markdown += "\n### Budgets\n\n";
+        markdown += "| Directory | Keyword | Current | Limit | Delta | Status |\n";
+        markdown += "|---|---|---:|---:|---:|---|\n";
+        for budget in &self.budgets {
+            let _ = writeln!(
+                markdown,
+                "| `{}` | {} | {} | {} | {} | {} |",
+                budget.directory.display(),
+                budget.keyword,
+                budget.count,
+                budget.max,
+                match budget.delta() {
+                    Some(delta) if delta > 0 => format!("+{}", delta),
+                    Some(delta) => delta.to_string(),
+                    None => "n/a".to_string(),
+                },
+                match budget.exceeded() {
+                    true => ":x: Over budget",
+                    false => ":white_check_mark: Within budget",
+                }
+            );
+        }
+
+        let exceeded: usize = self.exceeded_budgets().count();
+        let _ = match exceeded {
+            0 => writeln!(markdown, "\nAll budgets pass."),
+            _ => writeln!(
+                markdown,
+                "\n**{} of {} budgets exceeded, this blocks merging.**",
+                exceeded,
+                self.budgets.len()
+            ),
+        };
+        markdown
+    }
+
+    /// Keyword and file type counts as `section,name,count` rows. With
+    /// `per_file`, a second table with a row per file follows a blank line
+    pub fn csv(&self, per_file: bool) -> String {
+        fn field(value: &str) -> String {
+            match value.contains([',', '"', '\n', '\r']) {
+                true => format!("\"{}\"", value.replace('"', "\"\"")),
+                false => value.to_string(),
+            }
+        }
+
+        let mut csv: String = String::from("section,name,count\n");
+        for (name, value) in self.metrics.entries() {
+            let _ = writeln!(csv, "metric,{},{}", name, value);
+        }
+        for (section, table) in [
+            ("keyword", &self.keyword_table),
+            ("doc_keyword", &self.doc_keyword_table),
+            ("filetype", &self.filetype_table),
+            ("assignee", &self.assignee_table),
+        ] {
+            for (name, count) in table {
+                let _ = writeln!(csv, "{},{},{}", section, field(name), count);
+            }
+        }
+        for (name, bytes) in &self.language_byte_counts {
+            let _ = writeln!(csv, "filetype_bytes,{},{}", field(name), bytes);
+        }
+        for (name, lines) in &self.language_doc_line_counts {
+            let _ = writeln!(csv, "filetype_doc_lines,{},{}", field(name), lines);
+        }
+        for (name, (_, hygiene)) in self.hygiene_by_language() {
+            for (section, count) in [
+                ("trailing_whitespace", hygiene.trailing_whitespace),
+                ("mixed_indentation", hygiene.mixed_indentation),
+                ("long_lines", hygiene.long_lines),
+            ] {
+                let _ = writeln!(csv, "filetype_{},{},{}", section, field(name), count);
+            }
+        }
+        for cluster in &self.clusters {
+            let _ = writeln!(
+                csv,
+                "cluster,{},{}",
+                field(&cluster.message),
+                cluster.locations.len()
+            );
+        }
+
+        if !per_file {
+            return csv;
+        }
+
+        let health: bool = self.files.iter().any(|file| file.health.is_some());
+        csv += "\npath,file_type,bytes,lines,code,comment,blank";
+        if health {
+            csv += ",max_line_length,average_line_length,max_indentation,functions";
+        }
+        for keyword in self.keyword_table.keys() {
+            let _ = write!(csv, ",{}", field(keyword));
+        }
+        csv += "\n";
+        for file in &self.files {
+            let _ = write!(
+                csv,
+                "{},{},{},{},{},{},{}",
+                field(&file.path.to_string_lossy()),
+                field(&file.file_type),
+                file.bytes,
+                file.line_count,
+                file.line_counts.code,
+                file.line_counts.comment,
+                file.line_counts.blank
+            );
+            if health {
+                let file_health: FileHealth = file.health.clone().unwrap_or_default();
+                let _ = write!(
+                    csv,
+                    ",{},{:.1},{},{}",
+                    file_health.max_line_length,
+                    file_health.average_line_length(file.line_count),
+                    file_health.max_indentation(),
+                    file_health
+                        .functions
+                        .map_or(String::new(), |functions| functions.to_string())
+                );
+            }
+            for keyword in self.keyword_table.keys() {
+                let _ = write!(
+                    csv,
+                    ",{}",
+                    file.keyword_table.get(keyword).copied().unwrap_or(0)
+                );
+            }
+            csv += "\n";
+        }
+        csv
+    }
+
+    /// Serialize straight into `writer` instead of building the whole
+    /// document as a string first
+    pub fn write_json(&self, writer: impl std::io::Write) -> Result<(), std::io::Error> {
+        use std::io::Write as _;
+
+        let mut writer: std::io::BufWriter<_> = std::io::BufWriter::new(writer);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.write_all(b"\n")?;
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        path::{Path, PathBuf},
+        process::Command,
+    };
+
+    use serde_json::Value;
+
+    use super::{LanguageSummary, LineCounts, Report};
+    use crate::{ScannerBuilder, repository::Checkout, severity::Severity};
+
+    fn scan(path: &Path) -> Report {
+        ScannerBuilder::new()
+            .paths([path.to_path_buf()])
+            .cache(false)
+            .build()
+            .unwrap()
+            .scan()
+            .unwrap()
+    }
+
+    #[test]
+    fn documentation_is_doc_lines_per_100_lines_of_code() {
+        let mut summary: LanguageSummary = LanguageSummary {
+            name: "Rust",
+            files: 1,
+            lines: LineCounts {
+                code: 40,
+                comment: 60,
+                blank: 0,
+            },
+            doc_lines: 50,
+            bytes: 0,
+        };
+        // More doc lines than code, which no share could be
+        assert_eq!(summary.documentation(), Some(125.0));
+        assert_eq!(summary.documentation_label(), "125.0");
+
+        summary.lines.code = 0;
+        assert_eq!(summary.documentation(), None);
+        assert_eq!(summary.documentation_label(), "-");
+    }
+
+    #[test]
+    fn serialized_findings_carry_their_file_and_configured_rule_level() {
+        let root: PathBuf =
+            std::env::temp_dir().join(format!("pursue-report-{}-serialized", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("notes.rs"), "// TODO: first\n// TODO: last\n").unwrap();
+
+        let mut report: Report = ScannerBuilder::new()
+            .paths([root.clone()])
+            .severities(BTreeMap::from([("TODO".to_string(), Severity::Warning)]))
+            .cache(false)
+            .build()
+            .unwrap()
+            .scan()
+            .unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        // As if the last one had been escalated for its age
+        let last = report.files[0].occurrences.last_mut().unwrap();
+        last.escalated_from = Some(last.severity);
+        last.severity = Severity::Error;
+
+        let (path, occurrence) = report.occurrences().next().unwrap();
+        let line: Value = serde_json::from_str(&occurrence.json_line(path).unwrap()).unwrap();
+        assert_eq!(line["language"], "Rust");
+        assert_eq!(line["file_bytes"], 29);
+        assert_eq!(line["classified_by"], "extension \"rs\"");
+
+        let sarif: Value = report.sarif_json();
+        let run: &Value = &sarif["runs"][0];
+        assert_eq!(
+            run["tool"]["driver"]["rules"][0]["defaultConfiguration"]["level"],
+            "warning"
+        );
+        assert_eq!(run["results"][1]["level"], "error");
+        assert_eq!(run["results"][0]["properties"]["language"], "Rust");
+        assert_eq!(run["results"][0]["properties"]["fileBytes"], 29);
+        assert_eq!(
+            run["results"][0]["properties"]["classifiedBy"],
+            "extension \"rs\""
+        );
+    }
+
+    #[test]
+    fn reports_of_a_revision_name_files_by_their_place_in_the_repository() {
+        let root: PathBuf =
+            std::env::temp_dir().join(format!("pursue-report-{}-revision", std::process::id()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        let root: PathBuf = root.canonicalize().unwrap();
+        let notes: PathBuf = root.join("src/notes.rs");
+        std::fs::write(&notes, "// TODO: committed\n").unwrap();
+        for args in [
+            &["init", "-q"][..],
+            &["add", "-A"],
+            &["commit", "-qm", "notes"],
+        ] {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(&root)
+                .args([
+                    "-c",
+                    "user.name=Reviser",
+                    "-c",
+                    "user.email=reviser@example.com",
+                ])
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+        let committed: Report = scan(&root);
+        std::fs::write(&notes, "// TODO: uncommitted\n").unwrap();
+
+        let revision: Checkout = Checkout::revision(&root, "HEAD").unwrap();
+        let mut report: Report = scan(&revision.locate(&root).unwrap());
+        report.relocate(revision.path());
+        drop(revision);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let (path, occurrence) = report.occurrences().next().unwrap();
+        assert_eq!(path, Path::new("src/notes.rs"));
+        assert_eq!(occurrence.message, "committed");
+        let (_, expected) = committed.occurrences().next().unwrap();
+        assert_eq!(occurrence.fingerprint, expected.fingerprint);
+    }
+}
@@ -0,0 +1,374 @@
+/*
+ *  repository.rs - Stable identity of the repository a scan came from
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::dismissed::{FNV_OFFSET_BASIS, fnv1a, to_hex};
+
+/// Git directory of the repository holding `path`, if any
+fn git_directory(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .map(|ancestor| ancestor.join(".git"))
+        .find(|git| git.is_dir())
+}
+
+/// Files git tracks under `path`, a directory or a single file, as listed
+/// by `git ls-files`. Fails when git is missing or `path` isn't in a
+/// repository
+pub fn tracked_files(path: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    git_files(path, &["ls-files", "-z"])
+}
+
+/// Files under `path` that changed since `revision`, committed or not,
+/// along with new files git doesn't ignore. Deleted files are left out
+pub fn changed_files(path: &Path, revision: &str) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut files: Vec<PathBuf> = git_files(
+        path,
+        &[
+            "diff",
+            "--name-only",
+            "-z",
+            "--relative",
+            "--diff-filter=d",
+            "--end-of-options",
+            revision,
+        ],
+    )?;
+    files.extend(git_files(
+        path,
+        &["ls-files", "-z", "--others", "--exclude-standard"],
+    )?);
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Files under `path` whose staged contents differ from `HEAD`, or every
+/// staged file before the first commit, leaving out deletions
+pub fn staged_files(path: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    git_files(
+        path,
+        &[
+            "diff",
+            "--cached",
+            "--name-only",
+            "-z",
+            "--relative",
+            "--diff-filter=d",
+        ],
+    )
+}
+
+/// What is staged for `file`, which may differ from what is on disk
+pub fn staged_contents(file: &Path) -> Result<Vec<u8>, std::io::Error> {
+    let mut staged = std::ffi::OsString::from(":./");
+    staged.push(file.file_name().unwrap_or(file.as_os_str()));
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(file.parent().unwrap_or(Path::new("")))
+        .arg("show")
+        .arg(staged)
+        .output()?;
+    match output.status.success() {
+        true => Ok(output.stdout),
+        false => Err(std::io::Error::other(format!(
+            "git show failed for {}: {}",
+            file.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))),
+    }
+}
+
+/// Files under `path` a git command taking a pathspec lists, separated by
+/// null bytes and relative to the directory it runs in
+fn git_files(path: &Path, arguments: &[&str]) -> Result<Vec<PathBuf>, std::io::Error> {
+    let (directory, pathspec): (&Path, &OsStr) = match path.is_dir() {
+        true => (path, OsStr::new(".")),
+        false => (
+            path.parent().unwrap_or(Path::new("")),
+            path.file_name().unwrap_or(path.as_os_str()),
+        ),
+    };
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(arguments)
+        .arg("--")
+        .arg(pathspec)
+        .output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "git {} failed in {}: {}",
+            arguments[0],
+            directory.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    // Names are relative to `directory`, and UTF-8 whatever the platform
+    Ok(output
+        .stdout
+        .split(|byte| *byte == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| directory.join(String::from_utf8_lossy(name).as_ref()))
+        .collect())
+}
+
+/// URL of the `origin` remote, or of the first remote when there is no
+/// origin, read straight from `.git/config`
+pub fn remote_url(path: &Path) -> Option<String> {
+    let config: String = std::fs::read_to_string(git_directory(path)?.join("config")).ok()?;
+
+    let mut remotes: Vec<(String, String)> = Vec::new();
+    let mut section: Option<String> = None;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            section = line
+                .strip_prefix("[remote \"")
+                .and_then(|rest| rest.strip_suffix("\"]"))
+                .map(str::to_string);
+            continue;
+        }
+
+        if let (Some(name), Some((key, value))) = (&section, line.split_once('='))
+            && key.trim() == "url"
+        {
+            remotes.push((name.clone(), value.trim().to_string()));
+        }
+    }
+
+    remotes
+        .iter()
+        .find(|(name, _)| name == "origin")
+        .or(remotes.first())
+        .map(|(_, url)| url.clone())
+}
+
+/// Reduce the ways one remote can be spelled to a single form, so
+/// `git@github.com:a/b.git` and `https://github.com/a/b` match
+pub fn normalize(url: &str) -> String {
+    let url: &str = url.trim().trim_end_matches('/');
+    let url: &str = url.strip_suffix(".git").unwrap_or(url);
+    let url: &str = match url.split_once("://") {
+        Some((_, rest)) => rest,
+        None => url,
+    };
+    let url: &str = match url.split_once('@') {
+        Some((_, rest)) => rest,
+        None => url,
+    };
+
+    let (host, path): (&str, &str) = url.split_once(['/', ':']).unwrap_or((url, ""));
+    // Either scp style `host:owner/repo` or a port, `host:22/owner/repo`
+    let path: &str = match path.split_once('/') {
+        Some((port, rest))
+            if url[host.len()..].starts_with(':')
+                && !port.is_empty()
+                && port.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            rest
+        }
+        _ => path,
+    };
+
+    format!("{}/{}", host, path).to_ascii_lowercase()
+}
+
+/// Hash of the normalized remote URL of the repository holding `path`.
+/// The URL itself is left out of reports since it may hold credentials
+pub fn identity(path: &Path) -> Option<String> {
+    let url: String = normalize(&remote_url(path)?);
+    Some(to_hex(fnv1a(FNV_OFFSET_BASIS, url.as_bytes())))
+}
+
+/// Top directory of the repository holding `path`, as git sees it
+pub(crate) fn top_level(path: &Path) -> Result<PathBuf, std::io::Error> {
+    let directory: &Path = match path.is_dir() {
+        true => path,
+        false => path.parent().unwrap_or(Path::new("")),
+    };
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(["rev-parse", "--show-toplevel"])
+        .output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "{} is not in a git repository: {}",
+            directory.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim_end(),
+    ))
+}
+
+/// Tree of a commit without files, to compare against before the first
+/// commit of a repository
+pub const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Whether the repository holding `path` has a commit checked out, which
+/// it doesn't until the first one is made
+pub fn has_head(path: &Path) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(["rev-parse", "--verify", "--quiet", "HEAD"])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Directory git runs the hooks of the repository holding `path` from,
+/// which `core.hooksPath` may move out of `.git/hooks`
+pub fn hooks_directory(path: &Path) -> Result<PathBuf, std::io::Error> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(["rev-parse", "--path-format=absolute", "--git-path", "hooks"])
+        .output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "{} is not in a git repository: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim_end(),
+    ))
+}
+
+/// Files of a repository checked out in the temporary directory, either a
+/// shallow clone of a remote or a commit of a local repository. Removed
+/// along with everything in it when dropped
+pub struct Checkout {
+    /// Temporary directory holding the checkout, named after the process so
+    /// two scans never share one
+    directory: PathBuf,
+    path: PathBuf,
+    /// Top directory of the repository a commit was checked out of, which
+    /// knows of the checkout as one of its worktrees
+    repository: Option<PathBuf>,
+}
+
+impl Checkout {
+    /// Empty checkout called `name` in a new temporary directory
+    fn new(name: &str, repository: Option<PathBuf>) -> Result<Checkout, std::io::Error> {
+        let started: u128 = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos());
+        let directory: PathBuf =
+            std::env::temp_dir().join(format!("pursue-clone-{}-{}", std::process::id(), started));
+        std::fs::create_dir_all(&directory)?;
+        Ok(Checkout {
+            path: directory.join(name),
+            directory,
+            repository,
+        })
+    }
+
+    /// Clone the latest commit of `url` with `git clone --depth 1`. Fails
+    /// rather than prompting when the remote wants credentials
+    pub fn shallow(url: &str) -> Result<Checkout, std::io::Error> {
+        // Named like the repository, so the paths of the report read well
+        let url: &str = url.trim();
+        let trimmed: &str = url.trim_end_matches('/');
+        let name: &str = trimmed
+            .strip_suffix(".git")
+            .unwrap_or(trimmed)
+            .rsplit(['/', ':', '\\'])
+            .next()
+            .filter(|name| !name.is_empty() && *name != "..")
+            .unwrap_or("repository");
+        let checkout: Checkout = Checkout::new(name, None)?;
+
+        let output = Command::new("git")
+            .args(["clone", "--depth", "1", "--quiet", "--"])
+            .arg(url)
+            .arg(&checkout.path)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .output()?;
+        if !output.status.success() {
+            return Err(std::io::Error::other(format!(
+                "git clone failed for {}: {}",
+                normalize(url),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(checkout)
+    }
+
+    /// Check out `revision` of the repository holding `path` as a detached
+    /// worktree, which git commands like blame work in as usual
+    pub fn revision(path: &Path, revision: &str) -> Result<Checkout, std::io::Error> {
+        let repository: PathBuf = top_level(path)?;
+        let name: String = repository
+            .file_name()
+            .map_or("repository".into(), |name| name.to_string_lossy().into());
+        let checkout: Checkout = Checkout::new(&name, Some(repository.clone()))?;
+
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&repository)
+            .args(["worktree", "add", "--detach", "--quiet", "--"])
+            .arg(&checkout.path)
+            .arg(revision)
+            .output()?;
+        if !output.status.success() {
+            return Err(std::io::Error::other(format!(
+                "could not check out {}: {}",
+                revision,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(checkout)
+    }
+
+    /// Directory of the checked out files
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Where `path`, inside the repository a commit was checked out of, is
+    /// in the checkout. None when it isn't part of the commit
+    pub fn locate(&self, path: &Path) -> Option<PathBuf> {
+        let relative: &Path = path.strip_prefix(self.repository.as_ref()?).ok()?;
+        let located: PathBuf = self.path.join(relative);
+        located.exists().then_some(located)
+    }
+}
+
+impl Drop for Checkout {
+    fn drop(&mut self) {
+        if let Some(repository) = &self.repository {
+            let _ = Command::new("git")
+                .arg("-C")
+                .arg(repository)
+                .args(["worktree", "remove", "--force", "--"])
+                .arg(&self.path)
+                .output();
+        }
+        let _ = std::fs::remove_dir_all(&self.directory);
+    }
+}
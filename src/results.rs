@@ -0,0 +1,118 @@
+/*
+ *  results.rs - Everything a scan counts, gathered without locking
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use crate::{
+    cache::{Classifications, ScannedFiles},
+    config::Budget,
+    report::{FileReport, Warning},
+};
+
+/// What a scan found. Every worker fills in its own and they are merged
+/// once all of them are done, so scanning never waits on another thread
+#[derive(Default)]
+pub(crate) struct Results {
+    pub keyword_table: HashMap<Arc<str>, usize>,
+    pub doc_keyword_table: HashMap<Arc<str>, usize>,
+    pub filetype_table: HashMap<Arc<str>, usize>,
+    pub files: Vec<FileReport>,
+    /// Budgets of every config file met along the way
+    pub budgets: Vec<Budget>,
+    pub dismissed_count: usize,
+    pub baselined_count: usize,
+    pub suppressed_count: usize,
+    pub oversized_file_count: usize,
+    pub binary_file_count: usize,
+    pub unrecognized_file_count: usize,
+    pub too_deep_directory_count: usize,
+    /// Files and directories that could not be read
+    pub warnings: Vec<Warning>,
+    /// Files classified by their contents, for the classification cache
+    pub classifications: Classifications,
+    /// Files scanned or found unchanged, for the result cache
+    pub scanned: ScannedFiles,
+}
+
+/// Add `count` to `key` in `table`, only allocating the first time
+fn add(table: &mut HashMap<Arc<str>, usize>, key: &str, count: usize) {
+    match table.get_mut(key) {
+        Some(value) => *value += count,
+        None => {
+            table.insert(key.into(), count);
+        }
+    }
+}
+
+impl Results {
+    pub fn increment_keyword(&mut self, keyword: &str, in_doc_comment: bool) {
+        let table: &mut HashMap<Arc<str>, usize> = match in_doc_comment {
+            true => &mut self.doc_keyword_table,
+            false => &mut self.keyword_table,
+        };
+        add(table, keyword, 1);
+    }
+
+    /// Take a finding counted by `increment_keyword` back out
+    pub fn decrement_keyword(&mut self, keyword: &str, in_doc_comment: bool) {
+        let table: &mut HashMap<Arc<str>, usize> = match in_doc_comment {
+            true => &mut self.doc_keyword_table,
+            false => &mut self.keyword_table,
+        };
+        if let Some(value) = table.get_mut(keyword) {
+            *value = value.saturating_sub(1);
+        }
+    }
+
+    /// Note that `path` could not be read, for the report's warnings
+    pub fn warn(&mut self, path: &Path, message: String) {
+        self.warnings.push(Warning {
+            path: path.to_path_buf(),
+            message,
+        });
+    }
+
+    pub fn increment_filetype(&mut self, name: &str) {
+        add(&mut self.filetype_table, name, 1);
+    }
+
+    /// Fold what another worker found into these
+    pub fn merge(&mut self, other: Results) {
+        for (keyword, count) in other.keyword_table {
+            add(&mut self.keyword_table, &keyword, count);
+        }
+        for (keyword, count) in other.doc_keyword_table {
+            add(&mut self.doc_keyword_table, &keyword, count);
+        }
+        for (name, count) in other.filetype_table {
+            add(&mut self.filetype_table, &name, count);
+        }
+        self.files.extend(other.files);
+        self.budgets.extend(other.budgets);
+        self.dismissed_count += other.dismissed_count;
+        self.baselined_count += other.baselined_count;
+        self.suppressed_count += other.suppressed_count;
+        self.oversized_file_count += other.oversized_file_count;
+        self.binary_file_count += other.binary_file_count;
+        self.unrecognized_file_count += other.unrecognized_file_count;
+        self.too_deep_directory_count += other.too_deep_directory_count;
+        self.warnings.extend(other.warnings);
+        self.classifications.merge(other.classifications);
+        self.scanned.merge(other.scanned);
+    }
+}
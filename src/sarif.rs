@@ -0,0 +1,112 @@
+/*
+ *  sarif.rs - Reports as SARIF logs for code scanning dashboards
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{collections::BTreeSet, fmt::Write, path::Path};
+
+use serde_json::{Value, json};
+
+use crate::{report::Report, severity::Severity};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// `file://` URI of `path`, escaping what can't appear in one as is
+fn file_uri(path: &Path) -> String {
+    let path: String = path.to_string_lossy().replace('\\', "/");
+    let mut uri: String = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'.' | b'_' | b'~' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => {
+                let _ = write!(uri, "%{:02X}", byte);
+            }
+        }
+    }
+    uri
+}
+
+impl Report {
+    /// A SARIF 2.1.0 log with one rule per keyword, at the level its
+    /// severity is configured to, and one result per finding, at the level
+    /// of its own severity
+    pub fn sarif_json(&self) -> Value {
+        let mut rules: BTreeSet<&str> = BTreeSet::new();
+        let results: Vec<Value> = self
+            .occurrences()
+            .map(|(path, occurrence)| {
+                rules.insert(&occurrence.keyword);
+                let text: &str = match occurrence.message.is_empty() {
+                    true => &occurrence.snippet,
+                    false => &occurrence.message,
+                };
+                json!({
+                    "ruleId": occurrence.keyword,
+                    "level": occurrence.severity.sarif_level(),
+                    "message": { "text": text },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": file_uri(path) },
+                            "region": {
+                                "startLine": occurrence.line,
+                                "startColumn": occurrence.column,
+                            },
+                        },
+                    }],
+                    "partialFingerprints": { "pursue/v1": occurrence.fingerprint },
+                    "properties": {
+                        "language": occurrence.language,
+                        "fileBytes": occurrence.file_bytes,
+                        "classifiedBy": occurrence.classified_by,
+                    },
+                })
+            })
+            .collect();
+
+        let rules: Vec<Value> = rules
+            .into_iter()
+            .map(|keyword| {
+                json!({
+                    "id": keyword,
+                    "shortDescription": { "text": format!("{} comment", keyword) },
+                    "defaultConfiguration": {
+                        "level": Severity::of(keyword, &self.severities).sarif_level(),
+                    },
+                })
+            })
+            .collect();
+
+        json!({
+            "$schema": SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "pursue",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules,
+                    },
+                },
+                "results": results,
+            }],
+        })
+    }
+}
@@ -0,0 +1,385 @@
+/*
+ *  scanner.rs - Library entry point for configuring and running scans
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    collections::BTreeMap,
+    io::ErrorKind,
+    num::NonZero,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    Options,
+    cache::ResultCache,
+    filetype::Registry,
+    license::LicenseCheck,
+    logger::Logger,
+    matching::Matching,
+    report::OutputFormat,
+    report::{FileReport, Occurrence, Report},
+    severity::Severity,
+    sink::{FileCallback, FindingSink, Sinks},
+    terminal::ColorChoice,
+    why::FileTrace,
+};
+
+/// Everything a scan found
+pub type ScanReport = Report;
+
+/// Sets up a `Scanner`, starting from the defaults of `Options`
+///
+/// ```no_run
+/// let scanner = pursue::ScannerBuilder::new()
+///     .path("src")
+///     .keywords(["TODO", "SAFETY"])
+///     .threads(4)
+///     .exclude("generated/")
+///     .build()?;
+/// let report = scanner.scan()?;
+/// println!("{} findings", report.metrics.findings);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ScannerBuilder {
+    paths: Vec<PathBuf>,
+    options: Options,
+    sinks: Sinks,
+}
+
+impl ScannerBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from `options` instead of the defaults
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Add a directory or file to scan. Paths nested in another one are
+    /// only scanned once
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.paths.push(path.into());
+        self
+    }
+
+    pub fn paths<P: Into<PathBuf>>(mut self, paths: impl IntoIterator<Item = P>) -> Self {
+        self.paths.extend(paths.into_iter().map(Into::into));
+        self
+    }
+
+    /// Keywords to look for instead of the default ones
+    pub fn keywords<K: Into<String>>(mut self, keywords: impl IntoIterator<Item = K>) -> Self {
+        self.options.keywords = keywords.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Worker threads to scan with. 0 means one per CPU, the default
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.options.jobs = NonZero::new(threads);
+        self
+    }
+
+    /// Skip paths matching a gitignore style pattern, relative to the scan
+    /// root
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.options.excludes.push(pattern.into());
+        self
+    }
+
+    pub fn excludes<S: Into<String>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.options
+            .excludes
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Scan paths matching a gitignore style pattern before the rest, in
+    /// the order they are added
+    pub fn prioritize(mut self, pattern: impl Into<String>) -> Self {
+        self.options.prioritize.push(pattern.into());
+        self
+    }
+
+    pub fn priorities<S: Into<String>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.options
+            .prioritize
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn default_ignores(mut self, default_ignores: bool) -> Self {
+        self.options.default_ignores = default_ignores;
+        self
+    }
+
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.options.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Scan the files git tracks instead of walking the directories
+    pub fn git(mut self, git: bool) -> Self {
+        self.options.git = git;
+        self
+    }
+
+    /// Scan only the files changed since a git revision, e.g. `main` for
+    /// what a branch touched
+    pub fn since(mut self, revision: Option<String>) -> Self {
+        self.options.since = revision;
+        self
+    }
+
+    pub fn use_baseline(mut self, use_baseline: bool) -> Self {
+        self.options.use_baseline = use_baseline;
+        self
+    }
+
+    pub fn cache(mut self, cache: bool) -> Self {
+        self.options.cache = cache;
+        self
+    }
+
+    pub fn verify(mut self, verify: bool) -> Self {
+        self.options.verify = verify;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.options.max_depth = max_depth;
+        self
+    }
+
+    /// Skip files bigger than this many bytes without reading them
+    pub fn max_filesize(mut self, max_filesize: Option<u64>) -> Self {
+        self.options.max_filesize = max_filesize;
+        self
+    }
+
+    /// TOML file of languages to add to the built in ones or replace them
+    /// with
+    pub fn languages(mut self, languages: Option<PathBuf>) -> Self {
+        self.options.languages = languages;
+        self
+    }
+
+    /// Format the report will be printed in, which keeps status output off
+    /// of stdout when machine readable
+    pub fn format(mut self, format: OutputFormat) -> Self {
+        self.options.format = format;
+        self
+    }
+
+    /// Print every finding as it is found
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.options.verbose = verbose;
+        self
+    }
+
+    /// Also measure line lengths, indentation, and functions of every file
+    pub fn health(mut self, health: bool) -> Self {
+        self.options.health = health;
+        self
+    }
+
+    /// Check that every file with comments starts with a license header
+    pub fn check_license(mut self, license: Option<LicenseCheck>) -> Self {
+        self.options.license = license;
+        self
+    }
+
+    /// Count lines with trailing whitespace, with tabs and spaces mixed in
+    /// their indentation, and longer than `max_line_length` characters
+    pub fn hygiene(mut self, max_line_length: Option<usize>) -> Self {
+        self.options.hygiene = max_line_length;
+        self
+    }
+
+    /// Also scan the files inside zip and tar archives
+    pub fn archives(mut self, archives: bool) -> Self {
+        self.options.archives = archives;
+        self
+    }
+
+    /// Keep the report of every file in `file`, and only parse the files
+    /// that changed since the run that saved it
+    pub fn result_cache(mut self, file: Option<PathBuf>) -> Self {
+        self.options.result_cache = file;
+        self
+    }
+
+    pub fn color(mut self, color: ColorChoice) -> Self {
+        self.options.color = color;
+        self
+    }
+
+    /// Print the findings of every file as JSON Lines as soon as the file
+    /// is scanned
+    pub fn stream(mut self, stream: bool) -> Self {
+        self.options.stream = stream;
+        self
+    }
+
+    /// Give `keyword` a severity other than its default
+    pub fn severity(mut self, keyword: impl Into<String>, severity: Severity) -> Self {
+        self.options.severities.insert(keyword.into(), severity);
+        self
+    }
+
+    pub fn severities(mut self, severities: BTreeMap<String, Severity>) -> Self {
+        self.options.severities.extend(severities);
+        self
+    }
+
+    /// How keywords are matched, unless set for one of them
+    pub fn matching(mut self, matching: Matching) -> Self {
+        self.options.matching = matching;
+        self
+    }
+
+    /// How `keyword` in particular is matched
+    pub fn matching_for(mut self, keyword: impl Into<String>, matching: Matching) -> Self {
+        self.options
+            .keyword_matching
+            .insert(keyword.into(), matching);
+        self
+    }
+
+    pub fn keyword_matching(mut self, keyword_matching: BTreeMap<String, Matching>) -> Self {
+        self.options.keyword_matching.extend(keyword_matching);
+        self
+    }
+
+    /// Hand every finding and file to `sink` as soon as it is scanned
+    pub fn sink(mut self, sink: impl FindingSink + 'static) -> Self {
+        self.sinks.push(Arc::new(sink));
+        self
+    }
+
+    /// Call `callback` with every finding, and the file it is in, as soon
+    /// as the file is scanned
+    pub fn on_finding(
+        self,
+        callback: impl Fn(&FileReport, &Occurrence) + Send + Sync + 'static,
+    ) -> Self {
+        self.sink(callback)
+    }
+
+    /// Call `callback` with every file as soon as it is scanned
+    pub fn on_file(self, callback: impl Fn(&FileReport) + Send + Sync + 'static) -> Self {
+        self.sink(FileCallback(callback))
+    }
+
+    /// Check the settings and load the languages, failing on no paths, an
+    /// empty keyword, or a languages file that can't be read
+    pub fn build(self) -> Result<Scanner, std::io::Error> {
+        if self.paths.is_empty() {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "No paths to scan",
+            ));
+        }
+        if self
+            .options
+            .keywords
+            .iter()
+            .any(|keyword| keyword.trim().is_empty())
+        {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "Keywords must not be empty",
+            ));
+        }
+
+        let registry: Registry = Registry::load(self.options.languages.as_deref())?;
+        Ok(Scanner {
+            paths: self.paths,
+            options: self.options,
+            registry,
+            sinks: self.sinks,
+        })
+    }
+}
+
+/// A configured scan that can be run any number of times, e.g. whenever
+/// the tree changes
+pub struct Scanner {
+    paths: Vec<PathBuf>,
+    options: Options,
+    registry: Registry,
+    sinks: Sinks,
+}
+
+impl Scanner {
+    /// Scan every path as one codebase
+    pub fn scan(&self) -> Result<ScanReport, std::io::Error> {
+        let mut logger = Logger::new(self.paths.clone(), &self.options, self.registry.clone())
+            .with_sinks(&self.sinks);
+        logger.log()
+    }
+
+    /// Scan like `scan`, but take the reports of files unchanged since the
+    /// last scan with `cache` from it, and leave those of this scan in it
+    pub fn scan_with(&self, cache: &mut ResultCache) -> Result<ScanReport, std::io::Error> {
+        let mut logger = Logger::new(self.paths.clone(), &self.options, self.registry.clone())
+            .with_sinks(&self.sinks)
+            .with_result_cache(std::mem::replace(cache, ResultCache::resident()));
+        let report: Result<ScanReport, std::io::Error> = logger.log();
+        if let Some(kept) = logger.take_result_cache() {
+            *cache = kept;
+        }
+        report
+    }
+
+    /// Scan what is staged in git for the changed files instead of what is
+    /// on disk, so a commit is checked as it will be made
+    pub fn scan_staged(&self) -> Result<ScanReport, std::io::Error> {
+        let mut logger = Logger::new(self.paths.clone(), &self.options, self.registry.clone())
+            .with_sinks(&self.sinks);
+        logger.log_staged()
+    }
+
+    /// Classify and parse one file with the settings of the scan, tracing
+    /// how every line of it is read
+    pub fn explain(&self, file: &Path) -> Result<FileTrace, std::io::Error> {
+        let mut logger = Logger::new(self.paths.clone(), &self.options, self.registry.clone());
+        logger.explain(file)
+    }
+
+    /// Cheap summary of the files and directories the scan would walk,
+    /// which changes when any of them does
+    pub fn tree_stamp(&self) -> Result<u64, std::io::Error> {
+        Logger::new(self.paths.clone(), &self.options, self.registry.clone()).tree_stamp()
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// Directory config files are read from
+    pub fn root(&self) -> PathBuf {
+        Logger::common_root(&self.paths)
+    }
+}
@@ -0,0 +1,185 @@
+/*
+ *  serve.rs - Serve a rendered HTML report from memory on localhost
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    sync::{Arc, RwLock},
+    thread,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use crate::html::{INDEX_PAGE, Page};
+
+/// Polled by served pages to find out when to reload
+const GENERATION_PATH: &str = "/__pursue/generation";
+
+struct Rendered {
+    generation: u64,
+    pages: HashMap<String, String>,
+}
+
+/// Cheap summary of a tree built from metadata alone, used to notice changes
+/// without reading any file contents
+pub fn tree_stamp(root: &Path) -> u64 {
+    fn visit(path: &Path, hasher: &mut DefaultHasher) {
+        let metadata = match std::fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(_) => return,
+        };
+
+        path.hash(hasher);
+        metadata.len().hash(hasher);
+        if let Ok(modified) = metadata.modified() {
+            modified
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .hash(hasher);
+        }
+
+        if metadata.is_dir()
+            && let Ok(entries) = path.read_dir()
+        {
+            let mut children: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+            children.sort();
+            for child in children {
+                visit(&child, hasher);
+            }
+        }
+    }
+
+    let mut hasher: DefaultHasher = DefaultHasher::new();
+    visit(root, &mut hasher);
+    hasher.finish()
+}
+
+fn live_reload_script(generation: u64) -> String {
+    format!(
+        "<script>setInterval(function(){{fetch('{}').then(function(r){{return r.text()}})\
+         .then(function(g){{if(g!=='{}')location.reload()}})}},1000)</script>",
+        GENERATION_PATH, generation
+    )
+}
+
+fn store(rendered: &RwLock<Rendered>, pages: Vec<Page>, live_reload: bool) {
+    let mut rendered = rendered.write().unwrap();
+    rendered.generation += 1;
+
+    let script: String = live_reload_script(rendered.generation);
+    rendered.pages = pages
+        .into_iter()
+        .map(|page| {
+            let contents: String = match live_reload {
+                true => page
+                    .contents
+                    .replace("</body>", &(script.clone() + "</body>")),
+                false => page.contents,
+            };
+            (format!("/{}", page.name), contents)
+        })
+        .collect();
+}
+
+fn respond(mut stream: TcpStream, rendered: &RwLock<Rendered>) -> Result<(), std::io::Error> {
+    let mut request_line: String = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let path: &str = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let path: &str = path.split('?').next().unwrap_or(path);
+    let rendered = rendered.read().unwrap();
+
+    let (status, content_type, body): (&str, &str, String) = match path {
+        GENERATION_PATH => ("200 OK", "text/plain", rendered.generation.to_string()),
+        "/" => match rendered.pages.get(&format!("/{}", INDEX_PAGE)) {
+            Some(page) => ("200 OK", "text/html; charset=utf-8", page.clone()),
+            None => ("404 Not Found", "text/plain", "Not found".to_string()),
+        },
+        _ => match rendered.pages.get(path) {
+            Some(page) => ("200 OK", "text/html; charset=utf-8", page.clone()),
+            None => ("404 Not Found", "text/plain", "Not found".to_string()),
+        },
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+/// Serve the pages produced by `render` on `port` until the process is
+/// killed. With `watch` set, `root` is checked for changes on that interval
+/// and open pages reload themselves whenever the report is re-rendered.
+pub fn serve<F>(
+    port: u16,
+    root: &Path,
+    watch: Option<Duration>,
+    render: F,
+) -> Result<(), std::io::Error>
+where
+    F: Fn() -> Result<Vec<Page>, std::io::Error> + Send + 'static,
+{
+    let rendered: Arc<RwLock<Rendered>> = Arc::new(RwLock::new(Rendered {
+        generation: 0,
+        pages: HashMap::new(),
+    }));
+    store(&rendered, render()?, watch.is_some());
+
+    let listener: TcpListener = TcpListener::bind(("127.0.0.1", port))?;
+    println!("Serving report on http://{}/", listener.local_addr()?);
+
+    if let Some(interval) = watch {
+        let rendered: Arc<RwLock<Rendered>> = Arc::clone(&rendered);
+        let root = root.to_path_buf();
+        thread::spawn(move || {
+            let mut stamp: u64 = tree_stamp(&root);
+            loop {
+                thread::sleep(interval);
+                let current: u64 = tree_stamp(&root);
+                if current == stamp {
+                    continue;
+                }
+
+                stamp = current;
+                match render() {
+                    Ok(pages) => store(&rendered, pages, true),
+                    Err(error) => eprintln!("WARNING: Could not refresh report: {}", error),
+                }
+            }
+        });
+    }
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(error) = respond(stream, &rendered) {
+                    eprintln!("WARNING: Could not answer request: {}", error);
+                }
+            }
+            Err(error) => eprintln!("WARNING: Could not accept connection: {}", error),
+        }
+    }
+
+    Ok(())
+}
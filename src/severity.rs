@@ -0,0 +1,109 @@
+/*
+ *  severity.rs - How much each keyword matters
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// How urgently a finding should be dealt with, least urgent first
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Info,
+    Warning,
+    Error,
+}
+
+/// Severity a keyword is raised to once its line has gone untouched for
+/// `older_than_days`, e.g. `[escalation.TODO] older_than_days = 365`
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Escalation {
+    pub older_than_days: u64,
+    pub severity: Severity,
+}
+
+/// Severities of the default keywords, used unless a config file maps them
+/// to another one. Any other keyword is `info`
+const DEFAULT_SEVERITIES: [(&str, Severity); 4] = [
+    ("TODO", Severity::Info),
+    ("HACK", Severity::Warning),
+    ("BUG", Severity::Error),
+    ("FIXME", Severity::Error),
+];
+
+impl Severity {
+    pub const ALL: [Severity; 3] = [Severity::Error, Severity::Warning, Severity::Info];
+
+    /// Severity of `keyword`, from `severities` or else the defaults
+    pub fn of(keyword: &str, severities: &BTreeMap<String, Severity>) -> Severity {
+        severities.get(keyword).copied().unwrap_or_else(|| {
+            DEFAULT_SEVERITIES
+                .iter()
+                .find(|(default, _)| *default == keyword)
+                .map_or(Severity::Info, |(_, severity)| *severity)
+        })
+    }
+
+    /// The matching SARIF result level
+    pub fn sarif_level(&self) -> &'static str {
+        match self {
+            Severity::Info => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    /// ANSI escape sequence findings of this severity are highlighted with
+    pub fn ansi_color(&self) -> &'static str {
+        match self {
+            Severity::Info => "\x1b[1;36m",
+            Severity::Warning => "\x1b[1;33m",
+            Severity::Error => "\x1b[1;31m",
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "info" | "note" => Ok(Severity::Info),
+            "warning" | "warn" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(format!(
+                "Unknown severity {:?}, expected one of: error, warning, info",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+/*
+ *  sink.rs - Findings handed to library consumers as they are made
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{fmt, io::Write, sync::Arc};
+
+use crate::report::{FileReport, Occurrence};
+
+/// Gets every finding and file of a scan as soon as the file is scanned,
+/// before the report is done. Files are scanned on several threads, so
+/// calls can come from any of them at once, in no particular order
+///
+/// ```no_run
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// use pursue::{FindingSink, report::FileReport};
+///
+/// #[derive(Default)]
+/// struct Lines(AtomicUsize);
+///
+/// impl FindingSink for Lines {
+///     fn file(&self, file: &FileReport) {
+///         self.0.fetch_add(file.line_count, Ordering::Relaxed);
+///     }
+/// }
+///
+/// pursue::ScannerBuilder::new()
+///     .path("src")
+///     .sink(Lines::default())
+///     .on_finding(|file, finding| println!("{}:{} {}", file.path.display(), finding.line, finding.keyword))
+///     .build()?
+///     .scan()?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub trait FindingSink: Send + Sync {
+    /// A finding of `file`, once the whole file was scanned, since a
+    /// directive further down can still suppress it
+    fn finding(&self, file: &FileReport, occurrence: &Occurrence) {
+        let _ = (file, occurrence);
+    }
+
+    /// A scanned file, after each of its findings
+    fn file(&self, file: &FileReport) {
+        let _ = file;
+    }
+}
+
+/// Closures taking a file and one of its findings are sinks of findings
+impl<F> FindingSink for F
+where
+    F: Fn(&FileReport, &Occurrence) + Send + Sync,
+{
+    fn finding(&self, file: &FileReport, occurrence: &Occurrence) {
+        self(file, occurrence)
+    }
+}
+
+/// Sink of a closure taking every scanned file
+pub(crate) struct FileCallback<F>(pub(crate) F);
+
+impl<F> FindingSink for FileCallback<F>
+where
+    F: Fn(&FileReport) + Send + Sync,
+{
+    fn file(&self, file: &FileReport) {
+        (self.0)(file)
+    }
+}
+
+/// Every finding as a line of JSON on stdout, a file at a time so lines of
+/// different files don't interleave. A closed stdout just stops the stream
+pub(crate) struct JsonLines;
+
+impl FindingSink for JsonLines {
+    fn file(&self, file: &FileReport) {
+        if file.occurrences.is_empty() {
+            return;
+        }
+        let mut stdout = std::io::stdout().lock();
+        for occurrence in &file.occurrences {
+            if let Ok(line) = occurrence.json_line(&file.path) {
+                let _ = writeln!(stdout, "{}", line);
+            }
+        }
+    }
+}
+
+/// Every sink a scan reports to
+#[derive(Clone, Default)]
+pub(crate) struct Sinks(Vec<Arc<dyn FindingSink>>);
+
+impl Sinks {
+    pub(crate) fn push(&mut self, sink: Arc<dyn FindingSink>) {
+        self.0.push(sink);
+    }
+
+    pub(crate) fn extend(&mut self, sinks: &Sinks) {
+        self.0.extend(sinks.0.iter().cloned());
+    }
+
+    /// Hand `file` and its findings to every sink
+    pub(crate) fn file(&self, file: &FileReport) {
+        for sink in &self.0 {
+            for occurrence in &file.occurrences {
+                sink.finding(file, occurrence);
+            }
+            sink.file(file);
+        }
+    }
+}
+
+impl fmt::Debug for Sinks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} sinks", self.0.len())
+    }
+}
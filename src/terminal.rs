@@ -0,0 +1,218 @@
+/*
+ *  terminal.rs - Colors and aligned tables for terminal output
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{fmt, str::FromStr};
+
+use crate::text;
+
+pub(crate) const BOLD: &str = "\x1b[1m";
+pub(crate) const RED: &str = "\x1b[31m";
+pub(crate) const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+/// Whether output is colored, with `--color`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Only on terminals, unless `NO_COLOR` is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color output going to a terminal or not, per
+    /// `is_terminal`
+    pub fn enabled(&self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                is_terminal
+                    && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                    && std::env::var_os("TERM").is_none_or(|term| term != "dumb")
+            }
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!(
+                "Unknown color choice {:?}, expected one of: auto, always, never",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ColorChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorChoice::Auto => write!(f, "auto"),
+            ColorChoice::Always => write!(f, "always"),
+            ColorChoice::Never => write!(f, "never"),
+        }
+    }
+}
+
+/// `text` wrapped in the ANSI escape sequence `color` when coloring
+pub(crate) fn paint(text: &str, color: Option<&str>, enabled: bool) -> String {
+    match (color, enabled) {
+        (Some(color), true) => format!("{}{}{}", color, text, RESET),
+        _ => text.to_string(),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Align {
+    Left,
+    Right,
+}
+
+struct Cell {
+    text: String,
+    color: Option<&'static str>,
+}
+
+enum Row {
+    Cells(Vec<Cell>),
+    Rule,
+}
+
+/// Table whose columns are as wide as their widest cell, measured in
+/// terminal columns so wide characters and colors don't throw them off
+pub(crate) struct Table {
+    /// Heading, least width, and alignment of every column
+    columns: Vec<(&'static str, usize, Align)>,
+    rows: Vec<Row>,
+}
+
+impl Table {
+    pub(crate) fn new(columns: &[(&'static str, usize, Align)]) -> Self {
+        Self {
+            columns: columns.to_vec(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub(crate) fn row<T: ToString>(&mut self, cells: impl IntoIterator<Item = T>) {
+        self.colored_row(cells.into_iter().map(|cell| (cell, None)));
+    }
+
+    /// Row whose cells each come with the color they are printed in
+    pub(crate) fn colored_row<T: ToString>(
+        &mut self,
+        cells: impl IntoIterator<Item = (T, Option<&'static str>)>,
+    ) {
+        self.rows.push(Row::Cells(
+            cells
+                .into_iter()
+                .map(|(text, color)| Cell {
+                    text: text.to_string(),
+                    color,
+                })
+                .collect(),
+        ));
+    }
+
+    /// A line across the table, e.g. above a total
+    pub(crate) fn rule(&mut self) {
+        self.rows.push(Row::Rule);
+    }
+
+    pub(crate) fn render(&self, color: bool) -> String {
+        let widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(index, (heading, least, _))| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| match row {
+                        Row::Cells(cells) => cells.get(index).map(|cell| text::width(&cell.text)),
+                        Row::Rule => None,
+                    })
+                    .chain([text::width(heading), *least])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let rule: String = "-".repeat(widths.iter().sum::<usize>() + 3 * (widths.len() - 1));
+
+        let line = |cells: Vec<(&str, Option<&str>)>| -> String {
+            let last: usize = cells.len() - 1;
+            let cells: Vec<String> = cells
+                .into_iter()
+                .zip(&self.columns)
+                .zip(&widths)
+                .enumerate()
+                .map(|(index, (((text, cell_color), (_, _, align)), width))| {
+                    let padding: String = " ".repeat(width.saturating_sub(text::width(text)));
+                    let painted: String = paint(text, cell_color, color);
+                    match (align, index == last) {
+                        (Align::Right, _) => padding + &painted,
+                        (Align::Left, true) => painted,
+                        (Align::Left, false) => painted + &padding,
+                    }
+                })
+                .collect();
+            cells.join(" | ")
+        };
+
+        let mut rendered: String = format!("{}\n", rule);
+        // Tables of labelled values go without headings
+        if self
+            .columns
+            .iter()
+            .any(|(heading, _, _)| !heading.is_empty())
+        {
+            rendered += &line(
+                self.columns
+                    .iter()
+                    .map(|(heading, _, _)| (*heading, Some(BOLD)))
+                    .collect(),
+            );
+            rendered += &format!("\n{}\n", rule);
+        }
+        for row in &self.rows {
+            match row {
+                Row::Cells(cells) => {
+                    rendered += &line(
+                        cells
+                            .iter()
+                            .map(|cell| (cell.text.as_str(), cell.color))
+                            .collect(),
+                    );
+                    rendered.push('\n');
+                }
+                Row::Rule => {
+                    rendered += &rule;
+                    rendered.push('\n');
+                }
+            }
+        }
+        rendered
+    }
+}
@@ -0,0 +1,114 @@
+/*
+ *  text.rs - Unicode aware helpers for matching and laying out text
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{borrow::Cow, ops::Range};
+
+use unicode_normalization::{IsNormalized, UnicodeNormalization, is_nfc_quick};
+use unicode_width::UnicodeWidthStr;
+
+/// `text` in composed form, so `Ä` typed as one character matches `Ä`
+/// typed as `A` plus a combining diaeresis. Borrowed when already composed,
+/// which every ASCII line is
+pub(crate) fn nfc(text: &str) -> Cow<'_, str> {
+    match text.is_ascii() || is_nfc_quick(text.chars()) == IsNormalized::Yes {
+        true => Cow::Borrowed(text),
+        false => Cow::Owned(text.nfc().collect()),
+    }
+}
+
+/// Bytes `range` of `line`, widened out to whole characters so a position
+/// inside a multi-byte character can't split it, and cut off at the end of
+/// the line
+pub(crate) fn slice(line: &str, range: Range<usize>) -> &str {
+    let start: usize = line.floor_char_boundary(range.start);
+    let end: usize = line.ceil_char_boundary(range.end).max(start);
+    &line[start..end]
+}
+
+/// Columns `text` takes up in a terminal. Most CJK characters take two
+pub(crate) fn width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// `text` padded with spaces on the right to fill `columns` terminal
+/// columns, since `{: <N}` pads by characters rather than columns
+pub(crate) fn pad(text: &str, columns: usize) -> String {
+    let mut padded: String = text.to_string();
+    padded.extend(std::iter::repeat_n(
+        ' ',
+        columns.saturating_sub(width(text)),
+    ));
+    padded
+}
+
+/// Encodings text files are told apart by, going by their byte order mark
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    /// Encoding of text starting with `head`, along with the length of its
+    /// byte order mark. UTF-8 unless a mark says otherwise
+    pub(crate) fn detect(head: &[u8]) -> (Encoding, usize) {
+        match head {
+            [0xEF, 0xBB, 0xBF, ..] => (Encoding::Utf8, 3),
+            [0xFF, 0xFE, ..] => (Encoding::Utf16Le, 2),
+            [0xFE, 0xFF, ..] => (Encoding::Utf16Be, 2),
+            _ => (Encoding::Utf8, 0),
+        }
+    }
+}
+
+/// UTF-16 `bytes` after their byte order mark as UTF-8, with unpaired
+/// surrogates and a dangling last byte replaced by U+FFFD
+pub(crate) fn decode_utf16(bytes: &[u8], encoding: Encoding) -> String {
+    let units = bytes.chunks(2).map(|pair| match (pair, encoding) {
+        ([high, low], Encoding::Utf16Be) => u16::from_be_bytes([*high, *low]),
+        ([low, high], _) => u16::from_le_bytes([*low, *high]),
+        _ => 0xFFFD,
+    });
+    char::decode_utf16(units)
+        .map(|decoded| decoded.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::slice;
+
+    #[test]
+    fn slice_widens_to_whole_characters() {
+        let line: &str = "a😀b";
+
+        // The emoji takes bytes 1 to 5
+        assert_eq!(slice(line, 2..3), "😀");
+        assert_eq!(slice(line, 0..2), "a😀");
+        assert_eq!(slice(line, 3..line.len()), "😀b");
+        assert_eq!(slice(line, 5..6), "b");
+    }
+
+    #[test]
+    fn slice_stops_at_the_end_of_the_line() {
+        assert_eq!(slice("é", 1..10), "é");
+        assert_eq!(slice("é", 10..20), "");
+        assert_eq!(slice("", 0..1), "");
+    }
+}
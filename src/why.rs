@@ -0,0 +1,412 @@
+/*
+ *  why.rs - Explain how a single file would be treated by a scan
+ *  Copyright (C) 2024  Sebastian Pineda (spineda.wpi.alum@gmail.com)
+ *
+ *  This program is free software; you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation; either version 2 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License along
+ *  with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::Serialize;
+
+use crate::{
+    Options,
+    config::Scope,
+    filetype::{Language, Registry},
+    report::Occurrence,
+};
+
+/// An exclude pattern that matched the file or one of its directories
+#[derive(Serialize, Clone, Debug)]
+pub struct RuleMatch {
+    pub path: PathBuf,
+    pub pattern: String,
+    pub source: PathBuf,
+    pub excluded: bool,
+}
+
+/// Directory whose exclude patterns were checked on the way to the file
+#[derive(Serialize, Clone, Debug)]
+pub struct ExcludeList {
+    pub directory: PathBuf,
+    pub patterns: usize,
+}
+
+/// Everything that decides whether and how a file is profiled
+#[derive(Serialize, Clone, Debug)]
+pub struct Explanation {
+    pub path: PathBuf,
+    pub root: PathBuf,
+    pub profiled: bool,
+    pub exclude_lists: Vec<ExcludeList>,
+    pub rule_matches: Vec<RuleMatch>,
+    pub language: Option<String>,
+    pub classified_by: Option<String>,
+    pub inline_comments: Vec<String>,
+    pub block_comments: Vec<(String, String)>,
+    pub doc_comments: Vec<String>,
+    pub doc_comment_before: Vec<String>,
+    pub keywords: Vec<String>,
+}
+
+/// Explain how `path` would be treated when profiling `root`
+pub fn explain(root: &Path, path: &Path, options: &Options) -> Result<Explanation, std::io::Error> {
+    let relative: &Path = path.strip_prefix(root).map_err(|_| {
+        std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("{:?} is not inside {:?}", path, root),
+        )
+    })?;
+
+    let keywords: Vec<Arc<str>> = options
+        .keywords
+        .iter()
+        .map(|keyword| keyword.as_str().into())
+        .collect();
+    let mut scope: Arc<Scope> = Scope::root(
+        &keywords,
+        &options.severities,
+        options.default_ignores,
+        root,
+        &options.excludes,
+    )?;
+    let mut rule_matches: Vec<RuleMatch> = Vec::new();
+    let mut profiled: bool = true;
+
+    // Same order the scan makes its decisions in: every directory on the
+    // way down can exclude everything below it
+    let mut current: PathBuf = root.to_path_buf();
+    if root.is_dir() {
+        scope = scope.descend(root)?;
+    }
+    for component in relative.components() {
+        current.push(component);
+        let is_dir: bool = current.is_dir();
+        if let Some(exclusion) = scope.exclusion(&current, is_dir) {
+            rule_matches.push(RuleMatch {
+                path: current.clone(),
+                pattern: exclusion.pattern,
+                source: exclusion.source,
+                excluded: exclusion.excluded,
+            });
+            if exclusion.excluded {
+                profiled = false;
+                break;
+            }
+        }
+        if is_dir {
+            scope = scope.descend(&current)?;
+        }
+    }
+
+    let exclude_lists: Vec<ExcludeList> = scope
+        .exclude_lists()
+        .into_iter()
+        .map(|(directory, patterns)| ExcludeList {
+            directory,
+            patterns,
+        })
+        .collect();
+
+    let mut explanation: Explanation = Explanation {
+        path: path.to_path_buf(),
+        root: root.to_path_buf(),
+        profiled,
+        exclude_lists,
+        rule_matches,
+        language: None,
+        classified_by: None,
+        inline_comments: Vec::new(),
+        block_comments: Vec::new(),
+        doc_comments: Vec::new(),
+        doc_comment_before: Vec::new(),
+        keywords: scope
+            .keywords()
+            .iter()
+            .map(|keyword| keyword.to_string())
+            .collect(),
+    };
+
+    let registry: Registry = Registry::load(options.languages.as_deref())?;
+    if let Some((language, rule)) = registry.classify_file(path) {
+        let language: &Language = language;
+        explanation.language = Some(language.name.clone());
+        explanation.classified_by = Some(rule);
+        explanation.inline_comments = language.inline_comments.clone();
+        explanation.block_comments = language.block_comments.clone();
+        explanation.doc_comments = language.doc_comments.clone();
+        explanation.doc_comment_before = language.doc_comment_before.clone();
+    } else {
+        explanation.profiled = false;
+    }
+
+    Ok(explanation)
+}
+
+impl Explanation {
+    /// `key: value` lines, one fact per line so they are easy to grep
+    pub fn render(&self) -> String {
+        let mut lines: Vec<String> = vec![
+            format!("path: {}", self.path.display()),
+            format!("root: {}", self.root.display()),
+            format!("profiled: {}", self.profiled),
+        ];
+
+        for list in &self.exclude_lists {
+            lines.push(format!(
+                "exclude patterns: {} in {}",
+                list.patterns,
+                list.directory.display()
+            ));
+        }
+        for rule in &self.rule_matches {
+            lines.push(format!(
+                "{}: {} by {:?} from {}",
+                match rule.excluded {
+                    true => "excluded",
+                    false => "re-included",
+                },
+                rule.path.display(),
+                rule.pattern,
+                rule.source.display()
+            ));
+        }
+
+        match (&self.language, &self.classified_by) {
+            (Some(language), Some(rule)) => {
+                lines.push(format!("language: {}", language));
+                lines.push(format!("classified by: {}", rule));
+            }
+            _ => lines.push("language: none, no rule matches this file".to_string()),
+        }
+
+        if self.language.is_some() {
+            lines.push(format!(
+                "inline comments: {}",
+                match self.inline_comments.is_empty() {
+                    true => "none".to_string(),
+                    false => self.inline_comments.join(" "),
+                }
+            ));
+            lines.push(format!(
+                "block comments: {}",
+                match self.block_comments.is_empty() {
+                    true => "none".to_string(),
+                    false => self
+                        .block_comments
+                        .iter()
+                        .map(|(start, end)| format!("{} {}", start, end))
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                }
+            ));
+            lines.push(format!(
+                "doc comments: {}",
+                match self.doc_comments.is_empty() {
+                    true => "none".to_string(),
+                    false => self.doc_comments.join(" "),
+                }
+            ));
+            if !self.doc_comment_before.is_empty() {
+                lines.push(format!(
+                    "comments right above these document them: {}",
+                    self.doc_comment_before
+                        .iter()
+                        .map(|declaration| declaration.trim_end())
+                        .collect::<Vec<&str>>()
+                        .join(" ")
+                ));
+            }
+        }
+        lines.push(format!("keywords: {}", self.keywords.join(",")));
+
+        lines.join("\n") + "\n"
+    }
+}
+
+/// A comment found on a line
+#[derive(Serialize, Clone, Debug)]
+pub struct CommentTrace {
+    /// Characters of the line it starts and ends at, counting from 1
+    pub columns: (usize, usize),
+    /// The inline comment marker, or the opener of its block comment
+    pub marker: String,
+    /// An earlier line opened it
+    pub continued: bool,
+    /// It goes on to the next line
+    pub left_open: bool,
+    /// What it says between its markers
+    pub text: String,
+}
+
+/// How one line was read
+#[derive(Serialize, Clone, Debug)]
+pub struct LineTrace {
+    pub line: usize,
+    /// `code`, `comment`, or `blank`
+    pub kind: String,
+    /// Opener of the block comment the line starts inside of, and how many
+    /// of them are open
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inside: Option<(String, usize)>,
+    /// Counted as a line of documentation
+    pub documentation: bool,
+    pub comments: Vec<CommentTrace>,
+    /// Why keywords of the line weren't counted
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+    pub findings: Vec<Occurrence>,
+    pub source: String,
+}
+
+/// How a file is classified and parsed, line by line, along with every
+/// finding and the state of the parser it was made in
+#[derive(Serialize, Clone, Debug)]
+pub struct FileTrace {
+    pub path: PathBuf,
+    pub language: String,
+    pub classified_by: String,
+    pub inline_comments: Vec<String>,
+    pub block_comments: Vec<(String, String)>,
+    /// Openers of the block comments that nest
+    pub nesting: Vec<String>,
+    pub doc_comments: Vec<String>,
+    pub strings: Vec<(String, String)>,
+    pub raw_strings: Vec<(String, String)>,
+    pub keywords: Vec<String>,
+    /// A `pursue:ignore-file` directive leaves every finding out
+    pub ignore_file: bool,
+    pub lines: Vec<LineTrace>,
+}
+
+impl FileTrace {
+    /// `key: value` lines about the file, then every line of it followed
+    /// by the comments, findings, and skipped keywords on it
+    pub fn render(&self) -> String {
+        let pairs = |pairs: &[(String, String)]| -> String {
+            match pairs.is_empty() {
+                true => "none".to_string(),
+                false => pairs
+                    .iter()
+                    .map(|(start, end)| format!("{} {}", start, end))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            }
+        };
+
+        let mut lines: Vec<String> = vec![
+            format!("path: {}", self.path.display()),
+            format!("language: {}", self.language),
+            format!("classified by: {}", self.classified_by),
+            format!(
+                "inline comments: {}",
+                match self.inline_comments.is_empty() {
+                    true => "none".to_string(),
+                    false => self.inline_comments.join(" "),
+                }
+            ),
+            format!(
+                "block comments: {}",
+                match self.block_comments.is_empty() {
+                    true => "none".to_string(),
+                    false => self
+                        .block_comments
+                        .iter()
+                        .map(|(start, end)| match self.nesting.contains(start) {
+                            true => format!("{} {} nesting", start, end),
+                            false => format!("{} {}", start, end),
+                        })
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                }
+            ),
+            format!(
+                "doc comments: {}",
+                match self.doc_comments.is_empty() {
+                    true => "none".to_string(),
+                    false => self.doc_comments.join(" "),
+                }
+            ),
+            format!("strings: {}", pairs(&self.strings)),
+            format!("raw strings: {}", pairs(&self.raw_strings)),
+            format!("keywords: {}", self.keywords.join(",")),
+        ];
+        if self.ignore_file {
+            lines.push("pursue:ignore-file leaves every finding below out".to_string());
+        }
+        lines.push(String::new());
+
+        for line in &self.lines {
+            let mut state: Vec<String> = Vec::new();
+            if let Some((opener, depth)) = &line.inside {
+                state.push(match depth {
+                    1 => format!("inside {}", opener),
+                    _ => format!("inside {} {} deep", opener, depth),
+                });
+            }
+            if line.documentation {
+                state.push("documentation".to_string());
+            }
+            lines.push(format!(
+                "{:>5} {:<7} {}| {}",
+                line.line,
+                line.kind,
+                match state.is_empty() {
+                    true => String::new(),
+                    false => format!("({}) ", state.join(", ")),
+                },
+                line.source
+            ));
+
+            for comment in &line.comments {
+                lines.push(format!(
+                    "        comment at {}-{}{}{}: {:?}",
+                    comment.columns.0,
+                    comment.columns.1,
+                    match comment.continued {
+                        true => format!(", continuing {}", comment.marker),
+                        false => format!(", opened by {}", comment.marker),
+                    },
+                    match comment.left_open {
+                        true => ", left open",
+                        false => "",
+                    },
+                    comment.text
+                ));
+            }
+            for finding in &line.findings {
+                lines.push(format!(
+                    "        {} at column {}, {}{}: {:?}",
+                    finding.keyword,
+                    finding.column,
+                    finding.severity,
+                    match finding.in_doc_comment {
+                        true => ", in documentation",
+                        false => "",
+                    },
+                    finding.message
+                ));
+            }
+            for skipped in &line.skipped {
+                lines.push(format!("        skipped: {}", skipped));
+            }
+        }
+
+        lines.join("\n") + "\n"
+    }
+}